@@ -0,0 +1,181 @@
+// can we read a process's memory, and if not, why
+// built by the goat (danielscos)
+//
+// process_vm_readv goes through the same check as PTRACE_ATTACH. root and
+// CAP_SYS_PTRACE pass it unless ptrace_scope is 3. everyone else needs the
+// target to run as their uid and be dumpable, and yama has to agree
+// (scope 1: only our own descendants, scope 2: nobody without the capability).
+// inspect() predicts the answer from /proc, preflight() also reads a byte
+
+use crate::error::{DenialReason, MemscanError};
+use crate::memory::{MemoryReader, get_memory_regions};
+use crate::utils::get_ptrace_scope;
+use std::os::unix::fs::MetadataExt;
+
+pub const CAP_SYS_PTRACE: u32 = 19;
+
+#[derive(Debug, Clone)]
+pub struct AccessReport {
+    pub pid: u32,
+    pub our_uid: u32,
+    // real, effective and saved uid of the target
+    pub target_uids: Option<(u32, u32, u32)>,
+    pub cap_sys_ptrace: bool,
+    pub ptrace_scope: Option<u32>,
+    pub dumpable: Option<bool>,
+    pub tracer: Option<u32>,
+    // we're its parent, grandparent, ... (what ptrace_scope 1 wants)
+    pub descendant: bool,
+    // what the checks above predict, None = should be accessible
+    pub denial: Option<DenialReason>,
+    // the one-byte read: the address it read, or why it failed. None when
+    // not tried (inspect only)
+    pub probe: Option<Result<usize, String>>,
+}
+
+impl AccessReport {
+    pub fn accessible(&self) -> bool {
+        match &self.probe {
+            Some(probe) => probe.is_ok(),
+            None => self.denial.is_none(),
+        }
+    }
+
+    // the error an attach would run into, None when it would work
+    pub fn error(&self) -> Option<MemscanError> {
+        if self.accessible() {
+            return None;
+        }
+        Some(MemscanError::PermissionDenied {
+            pid: Some(self.pid),
+            reason: self.denial.clone().unwrap_or(DenialReason::Unknown),
+        })
+    }
+}
+
+// is this capability in our effective set
+pub fn has_capability(cap: u32) -> bool {
+    status_field("self", "CapEff:")
+        .and_then(|v| u64::from_str_radix(&v, 16).ok())
+        .is_some_and(|caps| caps & (1 << cap) != 0)
+}
+
+fn status_field(pid: &str, key: &str) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()?
+        .lines()
+        .find_map(|l| l.strip_prefix(key).map(|v| v.trim().to_string()))
+}
+
+fn uids(pid: &str) -> Option<(u32, u32, u32)> {
+    let line = status_field(pid, "Uid:")?;
+    let mut ids = line.split_whitespace().map(|v| v.parse::<u32>().ok());
+    Some((ids.next()??, ids.next()??, ids.next()??))
+}
+
+// walk the target's parents looking for us
+fn is_descendant(pid: u32) -> bool {
+    let own_pid = std::process::id();
+    let mut current = pid;
+    for _ in 0..64 {
+        let parent =
+            match status_field(&current.to_string(), "PPid:").and_then(|v| v.parse::<u32>().ok()) {
+                Some(parent) => parent,
+                None => return false,
+            };
+        if parent == own_pid {
+            return true;
+        }
+        if parent <= 1 {
+            return false;
+        }
+        current = parent;
+    }
+    false
+}
+
+// everything /proc tells us, without touching the target's memory
+pub fn inspect(pid: u32) -> Result<AccessReport, MemscanError> {
+    let target = pid.to_string();
+    let target_uids = uids(&target);
+    if target_uids.is_none() && !std::path::Path::new(&format!("/proc/{}", pid)).exists() {
+        return Err(MemscanError::ProcessNotFound { pid });
+    }
+
+    let our_uid = uids("self").map(|(uid, _, _)| uid).unwrap_or(u32::MAX);
+    let cap_sys_ptrace = has_capability(CAP_SYS_PTRACE);
+    let ptrace_scope = get_ptrace_scope();
+    let tracer = status_field(&target, "TracerPid:")
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&t| t != 0);
+
+    // a non-dumpable process gets its /proc/PID handed to root
+    let dumpable = match (std::fs::metadata(format!("/proc/{}", pid)), target_uids) {
+        (Ok(metadata), Some((_, euid, _))) => Some(metadata.uid() == euid),
+        _ => None,
+    };
+    let descendant = is_descendant(pid);
+
+    let denial = if ptrace_scope == Some(3) {
+        Some(DenialReason::PtraceScope(3))
+    } else if cap_sys_ptrace {
+        None
+    } else if let Some((uid, euid, suid)) = target_uids
+        && let Some(&theirs) = [euid, uid, suid].iter().find(|&&id| id != our_uid)
+    {
+        Some(DenialReason::UidMismatch {
+            ours: our_uid,
+            theirs,
+        })
+    } else if dumpable == Some(false) {
+        Some(DenialReason::NotDumpable)
+    } else {
+        match ptrace_scope {
+            Some(2) => Some(DenialReason::MissingCapability("CAP_SYS_PTRACE")),
+            // PR_SET_PTRACER exceptions aren't visible from outside, the
+            // probe read settles those
+            Some(1) if !descendant => Some(DenialReason::PtraceScope(1)),
+            _ => None,
+        }
+    };
+
+    Ok(AccessReport {
+        pid,
+        our_uid,
+        target_uids,
+        cap_sys_ptrace,
+        ptrace_scope,
+        dumpable,
+        tracer,
+        descendant,
+        denial,
+        probe: None,
+    })
+}
+
+// inspect, then read one byte of the first readable mapping to be sure
+pub fn preflight(pid: u32) -> Result<AccessReport, MemscanError> {
+    let mut report = inspect(pid)?;
+
+    let probe = get_memory_regions(pid).and_then(|regions| {
+        let region = regions
+            .iter()
+            .find(|r| {
+                r.readable
+                    && !matches!(
+                        r.path.as_deref(),
+                        Some("[vvar]") | Some("[vvar_vclock]") | Some("[vsyscall]")
+                    )
+            })
+            .ok_or(MemscanError::NoMemoryRegions)?;
+        MemoryReader::new(pid).read_memory(region.start_address, 1)?;
+        Ok(region.start_address)
+    });
+
+    report.probe = Some(match probe {
+        Ok(address) => Ok(address),
+        Err(e) if e.is_process_gone() => return Err(e),
+        Err(e) => Err(e.to_string()),
+    });
+    Ok(report)
+}
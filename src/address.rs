@@ -0,0 +1,341 @@
+// address expressions for commands that take an address
+// built by the goat (danielscos)
+//
+//   0x7fff12345678, 7fff12345678    plain hex
+//   rsp+0x20, rip-8                 a register of the main thread plus an offset
+//   1235:rsp+0x20                   a register of thread 1235
+//   libc!printf+0x1c, counter!score  a symbol of a loaded module
+//   libc.so.6+0x1000                 an offset into a module
+//   !score                           a symbol in whichever module has it
+//   test_target!player.health        a struct field, from debug info
+//   game!world->0x30->8              a pointer chain: read the pointer at
+//                                    game!world, add 0x30, read the pointer
+//                                    there and add 8
+//
+// offsets are hex with 0x, decimal without
+
+use crate::dwarf;
+use crate::error::MemscanError;
+use crate::process::{ProcessHandle, get_process};
+use crate::source::MemorySource;
+use crate::symbols::Symbolizer;
+use crate::threads::{self, is_register};
+use std::fmt;
+
+// the base is resolved once, the pointers are read again on every resolve(),
+// so the chain keeps up with objects the program moves around
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointerChain {
+    // the base as it was written, to find it again in a new process
+    pub base: String,
+    pub base_address: usize,
+    pub offsets: Vec<i64>,
+}
+
+pub fn parse_hex(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+fn parse_offset(s: &str) -> Option<i64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+// split "base+off" / "base-off" at the last sign, None when no offset
+// follows it. module names have dashes in them (ld-linux-x86-64.so.2,
+// libfoo-2.so), so the caller still has to try the whole text as a name
+fn split_offset(expr: &str) -> Option<(&str, i64)> {
+    let pos = expr.rfind(['+', '-']).filter(|&pos| pos > 0)?;
+    let offset = parse_offset(&expr[pos + 1..])?;
+    if expr.as_bytes()[pos] == b'-' {
+        Some((&expr[..pos], -offset))
+    } else {
+        Some((&expr[..pos], offset))
+    }
+}
+
+// source is where module/symbol names are looked up, without one it's opened
+// from pid. pid is needed for anything that isn't a plain number
+pub fn resolve_address(
+    expr: &str,
+    source: Option<&dyn MemorySource>,
+    pid: Option<u32>,
+) -> Result<usize, MemscanError> {
+    let expr = expr.trim();
+    if let Some(address) = parse_hex(expr) {
+        return Ok(address);
+    }
+
+    if is_pointer_chain(expr) {
+        let chain = PointerChain::parse(expr, source, pid)?;
+        return match source {
+            Some(source) => chain.resolve(source),
+            None => chain.resolve(&open_process(pid, expr)?),
+        };
+    }
+
+    // "libfoo-2" is a module before it is libfoo minus 2, the suffix is only
+    // an offset when the whole text doesn't resolve on its own
+    let whole = resolve_base(expr, source, pid);
+    let Some((base, offset)) = split_offset(expr) else {
+        return whole;
+    };
+    if whole.is_ok() {
+        return whole;
+    }
+    let base_address = resolve_base(base, source, pid)?;
+
+    base_address
+        .checked_add_signed(offset as isize)
+        .ok_or_else(|| MemscanError::invalid_address(expr, "out of range"))
+}
+
+fn resolve_base(
+    base: &str,
+    source: Option<&dyn MemorySource>,
+    pid: Option<u32>,
+) -> Result<usize, MemscanError> {
+    if let Some(address) = parse_hex(base) {
+        return Ok(address);
+    }
+
+    if let Some((module, symbol)) = base.split_once('!') {
+        // symbol table first, then debug info for variables and their fields
+        return with_symbolizer(source, pid, base, |s| {
+            s.resolve(module, symbol).or_else(|e| {
+                dwarf::find_variable(s, module, symbol)
+                    .map(|v| v.address)
+                    .map_err(|_| e)
+            })
+        });
+    }
+
+    let (tid, register) = match base.split_once(':') {
+        Some((tid, register)) => {
+            let tid = tid
+                .parse::<u32>()
+                .map_err(|_| MemscanError::invalid_address(base, "bad thread ID"))?;
+            (Some(tid), register)
+        }
+        None => (None, base),
+    };
+
+    if !is_register(register) {
+        if tid.is_none() && (source.is_some() || pid.is_some()) {
+            return with_symbolizer(source, pid, base, |s| {
+                s.module_base(base).ok_or_else(|| {
+                    MemscanError::invalid_address(base, "not a number, register or module")
+                })
+            });
+        }
+        return Err(MemscanError::invalid_address(
+            base,
+            "not a number or register",
+        ));
+    }
+
+    let tid = match (tid, pid) {
+        (Some(tid), _) => tid,
+        (None, Some(pid)) => pid,
+        (None, None) => {
+            return Err(MemscanError::invalid_address(
+                base,
+                "needs a running process to read registers from",
+            ));
+        }
+    };
+
+    let registers = threads::read_registers(tid)?;
+    Ok(registers.get(register).unwrap_or(0) as usize)
+}
+
+fn with_symbolizer(
+    source: Option<&dyn MemorySource>,
+    pid: Option<u32>,
+    base: &str,
+    resolve: impl FnOnce(&Symbolizer) -> Result<usize, MemscanError>,
+) -> Result<usize, MemscanError> {
+    let symbolizer = match source {
+        Some(source) => Symbolizer::cached(source)?,
+        None => Symbolizer::cached(&open_process(pid, base)?)?,
+    };
+
+    resolve(&symbolizer)
+}
+
+fn open_process(pid: Option<u32>, expr: &str) -> Result<ProcessHandle, MemscanError> {
+    let pid = pid.ok_or_else(|| {
+        MemscanError::invalid_address(expr, "needs a process or memory file to look up symbols in")
+    })?;
+    let mut process = get_process(pid).ok_or(MemscanError::ProcessNotFound { pid })?;
+    process.open()?;
+    process
+        .handle
+        .ok_or_else(|| MemscanError::invalid_state("Failed to get process handle"))
+}
+
+pub fn is_pointer_chain(expr: &str) -> bool {
+    expr.contains("->")
+}
+
+impl PointerChain {
+    // "base->off->off...", at least one offset
+    pub fn parse(
+        expr: &str,
+        source: Option<&dyn MemorySource>,
+        pid: Option<u32>,
+    ) -> Result<Self, MemscanError> {
+        let mut parts = expr.trim().split("->");
+        let base = parts.next().unwrap_or("").trim();
+        let offsets = parts
+            .map(|part| {
+                let part = part.trim();
+                let offset = match part.strip_prefix('-') {
+                    Some(negative) => parse_offset(negative).map(|offset| -offset),
+                    None => parse_offset(part.trim_start_matches('+')),
+                };
+                offset.ok_or_else(|| {
+                    MemscanError::invalid_address(expr, format!("'{}' is not an offset", part))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if base.is_empty() || offsets.is_empty() {
+            return Err(MemscanError::invalid_address(
+                expr,
+                "a pointer chain is BASE->OFFSET[->OFFSET...]",
+            ));
+        }
+
+        Ok(Self {
+            base: base.to_string(),
+            base_address: resolve_address(base, source, pid)?,
+            offsets,
+        })
+    }
+
+    // where the chain points right now
+    pub fn resolve(&self, source: &dyn MemorySource) -> Result<usize, MemscanError> {
+        let mut address = self.base_address;
+        for (step, &offset) in self.offsets.iter().enumerate() {
+            let pointer = source.read_pointer(address)?;
+            if pointer == 0 {
+                return Err(MemscanError::invalid_address(
+                    &self.to_string(),
+                    format!("null pointer at step {}", step + 1),
+                ));
+            }
+            address = pointer
+                .checked_add_signed(offset as isize)
+                .ok_or_else(|| MemscanError::invalid_address(&self.to_string(), "out of range"))?;
+        }
+        Ok(address)
+    }
+}
+
+impl fmt::Display for PointerChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base)?;
+        for &offset in &self.offsets {
+            if offset < 0 {
+                write!(f, "->-0x{:x}", offset.unsigned_abs())?;
+            } else {
+                write!(f, "->0x{:x}", offset)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryRegion;
+    use std::path::PathBuf;
+
+    // mappings with nothing in them, enough for the module list. the ELF
+    // layout comes from the mapped file since the memory reads as zeros
+    struct MappedFiles {
+        name: String,
+        regions: Vec<MemoryRegion>,
+    }
+
+    impl MemorySource for MappedFiles {
+        fn read_memory(&self, _address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+            Ok(vec![0; size])
+        }
+
+        fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+            Ok(self.regions.clone())
+        }
+
+        fn describe(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    // the test binary under a library-looking name with a dash in it
+    fn library_named(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("memscan-address-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = std::fs::remove_file(&path);
+        std::os::unix::fs::symlink(std::env::current_exe().unwrap(), &path).unwrap();
+        path
+    }
+
+    fn mapping(start_address: usize, path: &std::path::Path) -> MemoryRegion {
+        MemoryRegion {
+            start_address,
+            size: 0x1000,
+            readable: true,
+            writable: false,
+            executable: false,
+            offset: 0,
+            path: Some(path.display().to_string()),
+        }
+    }
+
+    #[test]
+    fn splits_offsets_off_the_end() {
+        assert_eq!(split_offset("rsp+0x20"), Some(("rsp", 0x20)));
+        assert_eq!(split_offset("rip-8"), Some(("rip", -8)));
+        assert_eq!(
+            split_offset("libc.so.6+0x1000"),
+            Some(("libc.so.6", 0x1000))
+        );
+        assert_eq!(split_offset("ld-linux-x86-64.so.2"), None);
+        assert_eq!(split_offset("-8"), None);
+    }
+
+    #[test]
+    fn dashed_module_names_are_not_offsets() {
+        let library = library_named("libfoo-2.so");
+        let source = MappedFiles {
+            name: "dashed modules".to_string(),
+            regions: vec![mapping(0x7f0000000000, &library)],
+        };
+
+        let resolve = |expr| resolve_address(expr, Some(&source), None).unwrap();
+        assert_eq!(resolve("libfoo-2"), 0x7f0000000000);
+        assert_eq!(resolve("libfoo-2.so"), 0x7f0000000000);
+        assert_eq!(resolve("libfoo-2+0x10"), 0x7f0000000010);
+        assert_eq!(resolve("libfoo-2-0x10"), 0x7efffffffff0);
+        assert_eq!(resolve("0x1000+8"), 0x1008);
+    }
+
+    #[test]
+    fn offsets_still_apply_to_a_shorter_module_name() {
+        let library = library_named("libbar.so.1");
+        let source = MappedFiles {
+            name: "plain modules".to_string(),
+            regions: vec![mapping(0x7f0000100000, &library)],
+        };
+
+        let resolve = |expr| resolve_address(expr, Some(&source), None);
+        assert_eq!(resolve("libbar-2").unwrap(), 0x7f00000ffffe);
+        assert_eq!(resolve("libbar.so.1+0x40").unwrap(), 0x7f0000100040);
+        assert!(resolve("libbaz-2").is_err());
+    }
+}
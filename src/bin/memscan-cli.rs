@@ -21,17 +21,47 @@
 //===============================================================================================
 
 use memscan::{
+    access::preflight,
+    address::{PointerChain, is_pointer_chain, parse_hex, resolve_address},
+    coredump::write_core_dump,
+    dwarf::{TypedAddress, find_variable, load_debug_info},
+    elf::{SHF_EXECINSTR, SHF_WRITE},
+    error::MemscanError,
+    history::{HistoryQuery, HistoryRecord, HistoryRecorder, load_history},
     memory_optimization::get_allocated_bytes,
-    monitor::{DataType, MemoryMonitor},
-    process::{Process, ProcessHandle, enumerate_processes},
+    modules::find_module,
+    monitor::{
+        DataType, MemoryMonitor, MonitorChange, MonitorEvent, MonitorExit, MonitorTarget,
+        data_type_name, format_value, parse_data_type,
+    },
+    process::{
+        Process, ProcessFilter, ProcessHandle, ProcessInfo, ProcessSort, find_processes,
+        get_process, match_processes, resolve_ns_pid, root_prefix, user_id, user_name,
+    },
+    range::RangeState,
     scanner::{
         scan_process_for_f32, scan_process_for_f64, scan_process_for_i32, scan_process_for_i64,
         scan_process_for_string,
     },
+    session::{ScanFilter, ScanSession, value_size},
+    snapshot::write_snapshot,
+    softdirty::sample_write_heat,
+    source::{MemorySource, RawDump, open_memory_file},
+    spawn::{SpawnOptions, SpawnedProcess, spawn_stopped},
+    stats::RATE_WINDOWS,
+    symbols::Symbolizer,
+    threads::{DISPLAY_ORDER, Registers, list_threads, read_registers, write_register},
+    tracer::{BreakpointHit, MAX_WATCHPOINTS, TraceEvent, Tracer, WatchKind, WatchpointHit},
+    trigger::{TriggerAction, TriggerHit, parse_action, parse_condition},
     utils::{display_system_info, loading_with_checks, suggest_fixes},
 };
+use regex::Regex;
+use std::collections::BTreeMap;
 use std::env;
 use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 
 const DANIELSCOS_BANNER: &str = r#"
@@ -52,66 +82,156 @@ const DANIELSCOS_BANNER: &str = r#"
 lazy_static::lazy_static! {
     static ref GLOBAL_MONITOR: Arc<Mutex<Option<MemoryMonitor>>> = Arc::new(Mutex::new(None));
     static ref GLOBAL_PROCESS_HANDLE: Arc<Mutex<Option<Arc<ProcessHandle>>>> = Arc::new(Mutex::new(None));
+    static ref GLOBAL_SCAN_SESSION: Mutex<Option<(Box<dyn MemorySource>, ScanSession)>> = Mutex::new(None);
+    // executable mapping of the monitored process and the watch list of the
+    // last monitor that was stopped, so 'wait --restore' can carry it over
+    static ref GLOBAL_MONITORED_IMAGE: Mutex<Option<ImageRange>> = Mutex::new(None);
+    static ref LAST_WATCH_LIST: Mutex<Option<SavedWatchList>> = Mutex::new(None);
+    static ref GLOBAL_SPAWNED: Mutex<Option<SpawnedProcess>> = Mutex::new(None);
+    // set by --ns-of for the command being run
+    static ref NS_OF: Mutex<Option<u32>> = Mutex::new(None);
+    // 'record <FILE>' streaming the monitor's changes to disk
+    static ref RECORDER: Mutex<Option<HistoryRecorder>> = Mutex::new(None);
+    // hardware watchpoints on the monitored process, and the type each one's
+    // hits are shown as
+    static ref TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+    static ref WATCHPOINT_TYPES: Mutex<BTreeMap<usize, DataType>> = Mutex::new(BTreeMap::new());
+}
+
+// print monitor changes as they happen ('notifications on|off')
+static LIVE_NOTIFICATIONS: AtomicBool = AtomicBool::new(false);
+
+// write end of the pipe the signal handler pokes, see catch_interrupts
+static INTERRUPT_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+#[derive(Debug, Clone)]
+struct ImageRange {
+    path: String,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone)]
+struct SavedWatchList {
+    image: Option<ImageRange>,
+    targets: Vec<MonitorTarget>,
 }
 
-fn print_banner_and_initialize() -> Result<(), String> {
+fn print_banner_and_initialize() -> Result<(), MemscanError> {
     print!("{}", DANIELSCOS_BANNER);
 
     match loading_with_checks() {
         Ok(system_info) => {
             display_system_info(&system_info);
 
-            if !system_info.has_sudo || system_info.ptrace_scope.unwrap_or(0) > 1 {
-                suggest_fixes(&system_info);
-            }
+            suggest_fixes(&system_info);
 
             println!("\n Memscan ready for operation");
             Ok(())
         }
         Err(error) => {
             eprintln!("\n Initialization failed: {}", error);
-            eprintln!(" Try running with sudo");
+            eprintln!(
+                " {}",
+                error
+                    .hint()
+                    .unwrap_or_else(|| "Try running with sudo".to_string())
+            );
             Err(error)
         }
     }
 }
 
 fn main() {
-    if let Err(_) = print_banner_and_initialize() {
+    if print_banner_and_initialize().is_err() {
         std::process::exit(1);
     }
 
+    catch_interrupts();
+
     let args: Vec<String> = env::args().collect();
 
     if args.len() >= 2 {
         execute_command(&args[1..]);
+        shut_down();
     } else {
         run_interactive_mode();
     }
 }
 
+// everything that has to happen before memscan goes away, however it goes:
+// the tracer takes its int3s and debug registers back out of the target and
+// the recording is finished
+fn shut_down() {
+    cmd_stop_monitor();
+}
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    let fd = INTERRUPT_PIPE.load(Ordering::Relaxed);
+    // write() is all a signal handler may do here
+    unsafe { libc::write(fd, [1u8].as_ptr() as *const libc::c_void, 1) };
+}
+
+// Ctrl-C, SIGTERM and SIGHUP shut down cleanly instead of leaving breakpoints
+// in the target. the handler only wakes a thread that does the work
+fn catch_interrupts() {
+    use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction};
+
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return;
+    }
+    INTERRUPT_PIPE.store(fds[1], Ordering::Relaxed);
+
+    std::thread::spawn(move || {
+        let mut byte = 0u8;
+        if unsafe { libc::read(fds[0], &mut byte as *mut u8 as *mut libc::c_void, 1) } == 1 {
+            println!("\n Interrupted, cleaning up");
+            shut_down();
+            std::process::exit(130);
+        }
+    });
+
+    let action = SigAction::new(
+        SigHandler::Handler(on_interrupt),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    for signal in [Signal::SIGINT, Signal::SIGTERM, Signal::SIGHUP] {
+        let _ = unsafe { sigaction(signal, &action) };
+    }
+}
+
+// Show memory usage and monitor status in prompt
+fn prompt() -> String {
+    let memory_mb = get_allocated_bytes() as f64 / 1024.0 / 1024.0;
+    // try_lock: the notification thread may ask while a command holds it
+    let monitor_status = match GLOBAL_MONITOR.try_lock().as_deref() {
+        Ok(Some(monitor)) if monitor.is_running() => "🔴",
+        Ok(Some(_)) => "⚪",
+        Ok(None) => "⚫",
+        Err(_) => "🔴",
+    };
+    format!("memscan({:.1}MB){} > ", memory_mb, monitor_status)
+}
+
 fn run_interactive_mode() {
     println!(" CLI started. Type 'help' for commands or 'exit' to quit.");
 
     let stdin = io::stdin();
 
     loop {
-        // Show memory usage and monitor status in prompt
-        let memory_mb = get_allocated_bytes() as f64 / 1024.0 / 1024.0;
-        let monitor_status = {
-            let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-            if let Some(ref monitor) = *monitor_guard {
-                if monitor.is_running() { "🔴" } else { "⚪" }
-            } else {
-                "⚫"
-            }
-        };
-
-        print!("memscan({:.1}MB){} > ", memory_mb, monitor_status);
+        print!("{}", prompt());
         io::stdout().flush().unwrap();
 
         let mut input = String::new();
         match stdin.read_line(&mut input) {
+            // end of input, like a script piped in without 'exit'
+            Ok(0) => {
+                println!();
+                shut_down();
+                break;
+            }
             Ok(_) => {
                 let input = input.trim();
 
@@ -126,7 +246,7 @@ fn run_interactive_mode() {
 
                 if matches!(args[0], "exit" | "quit" | "q") {
                     // Clean shutdown - stop monitoring if running
-                    cmd_stop_monitor();
+                    shut_down();
                     println!("👋 Au Revoir");
                     break;
                 }
@@ -138,6 +258,7 @@ fn run_interactive_mode() {
             }
             Err(e) => {
                 eprintln!(" Error reading input: {}", e);
+                shut_down();
                 break;
             }
         }
@@ -145,21 +266,74 @@ fn run_interactive_mode() {
 }
 
 fn execute_command(args: &[String]) {
+    // "--ns-of <PID>" anywhere: PIDs in this command are the ones seen inside
+    // that process's pid namespace (a container). spawn passes its arguments on
+    let mut args = args.to_vec();
+    let mut ns_of = None;
+    if args[0] != "spawn"
+        && let Some(index) = args.iter().position(|a| a == "--ns-of")
+    {
+        match args.get(index + 1).map(|v| resolve_pid(v)) {
+            Some(Some(pid)) => ns_of = Some(pid),
+            Some(None) => return,
+            None => {
+                println!(" --ns-of needs a PID");
+                return;
+            }
+        }
+        args.drain(index..index + 2);
+        if args.is_empty() {
+            return;
+        }
+    }
+
+    *NS_OF.lock().unwrap() = ns_of;
+    dispatch_command(&args);
+    *NS_OF.lock().unwrap() = None;
+}
+
+fn dispatch_command(args: &[String]) {
     match args[0].as_str() {
         // Original commands
         "help" | "--help" | "-h" => print_usage(),
         "version" | "--version" | "-v" => print_version(),
-        "list" => cmd_list_processes(),
+        "list" => cmd_list_processes(args),
         "scan" => cmd_scan(args),
+        "unknown" => cmd_unknown_scan(args),
+        "next" => cmd_next_scan(args),
+        "results" => cmd_scan_results(args),
         "info" => cmd_process_info(args),
+        "access" => cmd_access_check(args),
         "dump" => cmd_dump_memory(args),
+        "read" => cmd_read_value(args),
+        "snapshot" => cmd_snapshot(args),
         "write" => cmd_write_memory(args),
         "modify" => cmd_modify_value(args),
+        "core" => cmd_core_dump(args),
+        "modules" => cmd_list_modules(args),
+        "vars" => cmd_list_variables(args),
+        "threads" => cmd_list_threads(args),
+        "regs" => cmd_show_registers(args),
+        "spawn" => cmd_spawn(args),
+        "continue" | "resume" => cmd_continue_spawned(),
+        "kill" => cmd_kill_spawned(),
 
         // New real-time monitoring commands
         "monitor" => cmd_start_monitor(args),
+        "wait" => cmd_wait_for_process(args),
         "watch" => cmd_add_watch(args),
         "unwatch" => cmd_remove_watch(args),
+        "enable" => cmd_enable_watch(args, true),
+        "disable" => cmd_enable_watch(args, false),
+        "group" => cmd_group(args),
+        "hotspots" => cmd_hot_spots(args),
+        "watchpoint" | "wp" => cmd_watchpoint(args),
+        "breakpoint" | "bp" => cmd_breakpoint(args),
+        "detach" => cmd_detach(),
+        "trigger" => cmd_trigger(args),
+        "record" => cmd_record(args),
+        "history" => cmd_history(args, false),
+        "replay" => cmd_history(args, true),
         "changes" => cmd_show_changes(args),
         "status" => cmd_monitor_status(),
         "stop" | "stop-monitor" => cmd_stop_monitor(),
@@ -187,17 +361,72 @@ fn print_usage() {
     println!("BASIC COMMANDS:");
     println!("  help                                 Show this help message");
     println!("  version                              Show version information");
-    println!("  list                                 List running processes");
-    println!("  scan <PID> <VALUE> <TYPE>           Scan for value in process memory");
-    println!("  info <PID>                          Show process memory information");
-    println!("  dump <PID> <ADDRESS> <SIZE>         Dump memory region as hex");
+    println!("  list [-l] [-a] [NAME] [FILTERS]      List running processes (-l: details)");
+    println!("       --regex RE  --cmd TEXT  --user USER  --parent PID  --mine");
+    println!("       --sort name|pid|mem|start");
+    println!("  scan <TARGET> <VALUE> <TYPE>        Scan for value in process memory");
+    println!("  info <TARGET> [--heat]              Show process memory information");
+    println!("                                      --heat samples page writes for 500ms");
+    println!("  access <PID>                        Explain whether a process can be attached to");
+    println!("  dump <TARGET> <ADDRESS> <SIZE>      Dump memory region as hex");
+    println!("  read <TARGET> <ADDRESS> [TYPE]      Read a typed value (type from debug info)");
+    println!("  unknown <TARGET> <TYPE>             Start an unknown initial value scan");
+    println!("  next <FILTER>                       Narrow results: changed, unchanged,");
+    println!("                                      increased, decreased or a value");
+    println!("  results [count]                     Show current scan candidates");
     println!("  write <PID> <ADDRESS> <VALUE> <TYPE> Write value to memory");
     println!("  modify <PID> <ADDRESS> <VALUE> <TYPE> Safe modify with validation");
+    println!("  core <PID> [FILE]                   Write an ELF core file (default: core.<PID>)");
+    println!("  snapshot <TARGET> <FILE>            Save all readable memory to a snapshot");
+    println!("  modules <TARGET> [MODULE]           List loaded modules, or one module's sections");
+    println!("  vars <TARGET> [MODULE] [FILTER]     List global variables from debug info (-g)");
+    println!("  threads <PID>                       List the threads of a process");
+    println!("  regs <TID> [REG=VALUE ...]          Show (or set) a thread's registers");
+    println!();
+    println!("LAUNCHING:");
+    println!("  spawn [OPTIONS] <PROGRAM> [ARGS...]  Start a program stopped at its entry point");
+    println!("        --cwd DIR  --env KEY=VALUE  --clear-env");
+    println!(
+        "  continue                            Let a spawned, paused or breakpoint-stopped program run"
+    );
+    println!("  kill                                Kill the spawned program");
+    println!();
+    println!("TARGETS:");
+    println!("  A PID, a core file or memscan snapshot path, or a raw dump as FILE@0xBASE.");
+    println!("  Anywhere a PID is expected a process name or /regex/ works too.");
+    println!("  --ns-of <PID> reads PIDs as seen inside that process's container.");
+    println!("  Addresses are hex or register expressions: rsp+0x20, 1235:rbp-8");
+    println!("  Symbols work too: libc!printf+0x10, test_target!player.health");
+    println!("  Files are opened read-only and work with scan, info, dump and read.");
     println!();
     println!("REAL TIME MONITORING:");
     println!("  monitor <PID> [interval_ms]         Start real-time monitoring (default: 100ms)");
-    println!("  watch <ADDRESS> <TYPE> <NAME>       Add memory address to watch list");
-    println!("  unwatch <ADDRESS>                   Remove address from monitoring");
+    println!(
+        "  wait <NAME> [--restore] [--timeout S] Wait for a process to start, then monitor it"
+    );
+    println!("  watch <ADDRESS> [TYPE] [NAME] [--group G] Add memory address to watch list");
+    println!("        <ADDRESS> bytes:<SIZE> [NAME] [--as TYPE] Watch a block for changed bytes");
+    println!(
+        "        <BASE>-><OFF>[-><OFF>...] <TYPE> [NAME] Follow a pointer chain on every read"
+    );
+    println!("  hotspots <WATCH> [COUNT]            Offsets of a range that change most often");
+    println!("  watchpoint <ADDRESS> [TYPE|SIZE] [write|access] Catch what writes an address");
+    println!("  watchpoint [list|remove <ID>]       Hits per instruction, or remove one");
+    println!("  breakpoint <ADDRESS> [--continue]   Stop (or just count) when code runs");
+    println!("  breakpoint [list|remove <ID>|regs <ID>] Hits, remove one, last registers");
+    println!("  detach                              Clear watch/breakpoints, stop tracing");
+    println!("  unwatch <WATCH>                     Remove targets from monitoring");
+    println!("  enable|disable <WATCH>              Resume or pause reading targets");
+    println!("  group [<NAME> add|remove <WATCH>...] List groups, or change who is in one");
+    println!("          WATCH: #ID, an address (every type watched there) or @GROUP");
+    println!("  trigger <WATCH> <COND> [ACTION]     Act when a watched value meets a condition");
+    println!("          COND: above N, below N, equals X, changed-by N, rate K, nan");
+    println!("          ACTION: notify, pause, mark, snapshot <ADDRESS> <SIZE> [PREFIX]");
+    println!("  trigger [list|markers|remove <ID>]  Show triggers or marker hits, remove one");
+    println!("  record <FILE>|stop                  Stream changes to FILE (.jsonl or .csv)");
+    println!("  history <FILE> [QUERY]              List the changes in a recording");
+    println!("  replay <FILE> [QUERY] [--speed X]   Play a recording back with its timing");
+    println!("          QUERY: --target NAME|ADDR --from T --to T --value V --limit N");
     println!("  changes [count]                     Show recent memory changes");
     println!("  targets                             List all monitored addresses");
     println!("  status                              Show monitoring status and statistics");
@@ -207,13 +436,11 @@ fn print_usage() {
         "  live                                Show live memory changes (press Ctrl+C to exit)"
     );
     println!("  notifications <on|off>              Enable/disable automatic change notifications");
-    println!(
-        "  interval <ms>                       Set monitoring update interval (default: 100ms)"
-    );
+    println!("  interval [WATCH] <ms>               Change the polling interval while running");
     println!("  debug <on|off>                      Enable debug output for troubleshooting");
     println!();
     println!("DATA TYPES:");
-    println!("  i32, i64, f32, f64, string, string:<size>");
+    println!("  i32, i64, f32, f64, string, string:<size>, bytes:<size> (watch ranges)");
     println!();
     println!("EXAMPLES:");
     println!("   Basic Usage:");
@@ -237,6 +464,9 @@ fn print_usage() {
     println!("  ⚡ Advanced:");
     println!("    write 1234 0x7fff12345678 999 i32 # Write value to address");
     println!("    dump 1234 0x7fff12345678 64       # Hex dump 64 bytes");
+    println!("    core 1234 game.core               # Core file for gdb");
+    println!("    scan game.core 100 i32            # Scan a core file offline");
+    println!("    read heap.bin@0x5555deadb000 0x5555deadb010 i64 # Read from a raw dump");
     println!();
     println!("MONITOR STATUS INDICATORS:");
     println!("  ⚫ No monitor running    🔴 Monitor active    ⚪ Monitor stopped");
@@ -253,89 +483,262 @@ fn print_usage() {
     println!("  - Fast intervals (< 100ms) may catch writes in progress");
 }
 
-fn cmd_list_processes() {
+fn cmd_list_processes(args: &[String]) {
+    let mut filter = ProcessFilter::default();
+    let mut sort = ProcessSort::Name;
+    let mut long = false;
+    let mut show_all = false;
+
+    let mut options = args.iter().skip(1);
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "-l" | "--long" => long = true,
+            "-a" | "--all" => show_all = true,
+            "--mine" | "--attachable" => filter.attachable = true,
+            "--name" | "--regex" | "--cmd" | "--user" | "--parent" | "--sort" => {
+                let value = match options.next() {
+                    Some(v) => v,
+                    None => {
+                        println!(" {} needs a value", option);
+                        return;
+                    }
+                };
+                match option.as_str() {
+                    "--name" => filter.name = Some(value.clone()),
+                    "--regex" => match Regex::new(value) {
+                        Ok(regex) => filter.name_regex = Some(regex),
+                        Err(e) => {
+                            println!(" Invalid regex '{}': {}", value, e);
+                            return;
+                        }
+                    },
+                    "--cmd" => filter.cmdline = Some(value.clone()),
+                    "--user" => match user_id(value) {
+                        Some(uid) => filter.uid = Some(uid),
+                        None => {
+                            println!(" Unknown user '{}'", value);
+                            return;
+                        }
+                    },
+                    "--parent" => match value.parse() {
+                        Ok(ppid) => filter.parent = Some(ppid),
+                        Err(_) => {
+                            println!(" Invalid parent PID '{}'", value);
+                            return;
+                        }
+                    },
+                    _ => match ProcessSort::parse(value) {
+                        Some(s) => sort = s,
+                        None => {
+                            println!(" Unknown sort '{}' (name, pid, mem, start)", value);
+                            return;
+                        }
+                    },
+                }
+            }
+            // a bare word is a name filter
+            name if !name.starts_with('-') => filter.name = Some(name.to_string()),
+            _ => {
+                println!(" Unknown option '{}'", option);
+                println!(
+                    " Usage: list [-l] [-a] [NAME] [--regex RE] [--cmd TEXT] [--user USER] [--parent PID] [--mine] [--sort name|pid|mem|start]"
+                );
+                return;
+            }
+        }
+    }
+
+    // anything narrower than the full list is shown in full
+    let filtered = args.len() > 1 && !(args.len() == 2 && long);
+
     println!(" Enumerating processes...");
 
-    match enumerate_processes() {
+    match find_processes(&filter, sort) {
         Ok(processes) => {
             if processes.is_empty() {
+                if filtered {
+                    println!("  No matching processes");
+                    return;
+                }
                 println!("  No processes found or insufficient privileges");
                 println!(" Try running with sudo");
                 return;
             }
 
             println!(" Found {} accessible processes:", processes.len());
-            println!("{:-<60}", "");
 
-            for (i, process) in processes.iter().enumerate() {
-                if i < 30 {
-                    // Show first 30 processes
+            let limit = if show_all || filtered {
+                processes.len()
+            } else {
+                30
+            };
+
+            if long {
+                print_process_table(&processes[..limit.min(processes.len())]);
+            } else {
+                println!("{:-<60}", "");
+                for (i, process) in processes.iter().take(limit).enumerate() {
                     println!("  {:3}. {} (PID: {})", i + 1, process.name, process.pid);
-                } else if i == 30 {
-                    println!("  ... and {} more processes", processes.len() - 30);
-                    break;
                 }
             }
 
-            println!("{:-<60}", "");
+            if processes.len() > limit {
+                println!(
+                    "  ... and {} more processes (use 'list -a' or a filter)",
+                    processes.len() - limit
+                );
+            }
+
+            if !long {
+                println!("{:-<60}", "");
+            }
             println!(" Use 'scan <PID> <VALUE> <TYPE>' to search process memory");
             println!(" Use 'monitor <PID>' to start real-time monitoring");
         }
         Err(e) => {
-            println!(" Failed to enumerate processes: {}", e);
+            report_error("Failed to enumerate processes", &e);
             println!(" Try running with elevated privileges");
         }
     }
 }
 
+fn print_process_table(processes: &[Process]) {
+    println!("{:-<100}", "");
+    println!(
+        "{:>7} {:>7} {:<10} {:<5} {:>4} {:>9}  COMMAND",
+        "PID", "PPID", "USER", "STATE", "THR", "RSS"
+    );
+    println!("{:-<100}", "");
+
+    for process in processes {
+        let info = match process.info() {
+            Some(info) => info,
+            None => continue,
+        };
+
+        let user = user_name(info.uid).unwrap_or_else(|| info.uid.to_string());
+        let rss = match info.vm_rss_kb {
+            Some(kb) if kb >= 1024 * 1024 => format!("{:.1}G", kb as f64 / 1024.0 / 1024.0),
+            Some(kb) if kb >= 1024 => format!("{:.1}M", kb as f64 / 1024.0),
+            Some(kb) => format!("{}K", kb),
+            None => "-".to_string(),
+        };
+        let mut command = info.command_line();
+        if command.chars().count() > 60 {
+            command = format!("{}...", command.chars().take(57).collect::<String>());
+        }
+
+        println!(
+            "{:>7} {:>7} {:<10} {:<5} {:>4} {:>9}  {}{}",
+            info.pid,
+            info.ppid,
+            user,
+            info.state,
+            info.threads,
+            rss,
+            command,
+            if info.is_traced() { "  [traced]" } else { "" }
+        );
+    }
+    println!("{:-<100}", "");
+}
+
+fn print_process_details(info: &ProcessInfo) {
+    let user = |id: u32| match user_name(id) {
+        Some(name) => format!("{} ({})", id, name),
+        None => id.to_string(),
+    };
+
+    println!("Name:           {}", info.name);
+    println!("Command:        {}", info.command_line());
+    if let Some(exe) = &info.exe {
+        println!("Executable:     {}", exe.display());
+    }
+    if let Some(cwd) = &info.cwd {
+        println!("Working Dir:    {}", cwd.display());
+    }
+    println!("State:          {} ({})", info.state, info.state_name());
+    println!("Parent PID:     {}", info.ppid);
+    println!("Threads:        {}", info.threads);
+    if info.euid == info.uid {
+        println!("User:           {}", user(info.uid));
+    } else {
+        println!(
+            "User:           {} (effective {})",
+            user(info.uid),
+            user(info.euid)
+        );
+    }
+    if info.egid == info.gid {
+        println!("Group:          {}", info.gid);
+    } else {
+        println!("Group:          {} (effective {})", info.gid, info.egid);
+    }
+    if let Some(uptime) = info.uptime() {
+        let secs = uptime.as_secs();
+        println!(
+            "Running For:    {}h {:02}m {:02}s",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        );
+    }
+    if let (Some(rss), Some(size)) = (info.vm_rss_kb, info.vm_size_kb) {
+        println!(
+            "Memory:         {:.1} MB resident / {:.1} MB virtual",
+            rss as f64 / 1024.0,
+            size as f64 / 1024.0
+        );
+    }
+    if info.is_traced() {
+        println!(
+            "Traced By:      PID {} (a debugger is attached)",
+            info.tracer_pid
+        );
+    }
+    if !info.namespaces.is_empty() {
+        let namespaces: Vec<String> = info
+            .namespaces
+            .iter()
+            .map(|(kind, inode)| format!("{}:{}", kind, inode))
+            .collect();
+        println!("Namespaces:     {}", namespaces.join(" "));
+    }
+    if info.in_child_pid_namespace() {
+        let chain: Vec<String> = info.ns_pids.iter().map(|p| p.to_string()).collect();
+        println!(
+            "Namespace PID:  {} (NSpid {})",
+            info.ns_pid(),
+            chain.join(" -> ")
+        );
+    }
+    if let Some(root) = root_prefix(info.pid) {
+        println!("Files Via:      {} (own filesystem)", root.display());
+    }
+}
+
 fn cmd_scan(args: &[String]) {
     if args.len() < 4 {
-        println!(" Usage: scan <PID> <VALUE> <TYPE>");
+        println!(" Usage: scan <TARGET> <VALUE> <TYPE>");
         println!(" Types: i32, i64, f32, f64, string");
         println!(" Example: scan 1234 100 i32");
+        println!(" TARGET is a PID, a core file/snapshot, or a raw dump as FILE@0xBASE");
         return;
     }
 
-    let pid: u32 = match args[1].parse() {
-        Ok(p) => p,
-        Err(_) => {
-            println!(" Invalid PID: {}", args[1]);
-            return;
-        }
-    };
-
     let value_str = &args[2];
     let data_type = &args[3];
 
     println!(
-        " Scanning process {} for value '{}' of type {}",
-        pid, value_str, data_type
+        " Scanning {} for value '{}' of type {}",
+        args[1], value_str, data_type
     );
 
-    // Get process
-    let mut process = match get_process_by_pid(pid) {
-        Some(p) => p,
-        None => {
-            println!(" Process {} not found", pid);
-            println!(" Use 'list' to see available processes");
-            return;
-        }
-    };
-
-    // Open process
-    if let Err(e) = process.open() {
-        println!(" Failed to open process {}: {}", pid, e);
-        println!(" Try running with sudo");
-        return;
-    }
-
-    let handle = match process.handle {
-        Some(ref h) => h,
-        None => {
-            println!(" Failed to get process handle");
-            return;
-        }
+    let (source, pid) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
     };
+    let handle = source.as_ref();
 
     // Perform scan based on type
     let scan_result = match data_type.as_str() {
@@ -360,10 +763,16 @@ fn cmd_scan(args: &[String]) {
                 println!(" Found {} matches:", results.len());
                 println!("{:-<50}", "");
 
+                let symbolizer = Symbolizer::cached(handle).ok();
                 for (i, result) in results.iter().enumerate() {
                     if i < 20 {
-                        // Show first 20 results
-                        println!("  {:2}. 0x{:x}", i + 1, result.address);
+                        // Show first 20 results, with the symbol they fall in
+                        match symbolizer.as_ref().and_then(|s| s.describe(result.address)) {
+                            Some(symbol) => {
+                                println!("  {:2}. 0x{:x}  {}", i + 1, result.address, symbol)
+                            }
+                            None => println!("  {:2}. 0x{:x}", i + 1, result.address),
+                        }
                     } else if i == 20 {
                         println!("  ... and {} more matches", results.len() - 20);
                         break;
@@ -371,60 +780,191 @@ fn cmd_scan(args: &[String]) {
                 }
 
                 println!("{:-<50}", "");
+                println!(" Use 'next <changed|unchanged|increased|decreased|VALUE>' to narrow");
                 println!(" Use 'write <PID> <ADDRESS> <VALUE> <TYPE>' to modify");
                 println!(
                     " Use 'monitor <PID>' then 'watch <ADDRESS> <TYPE> <NAME>' for real-time tracking"
                 );
+
+                let session_type = match data_type.as_str() {
+                    "i32" => DataType::I32,
+                    "i64" => DataType::I64,
+                    "f32" => DataType::F32,
+                    "f64" => DataType::F64,
+                    _ => DataType::String(value_str.len()),
+                };
+                let session = ScanSession::from_results(handle, pid, session_type, &results);
+                *GLOBAL_SCAN_SESSION.lock().unwrap() = Some((source, session));
             }
         }
         Err(e) => {
-            println!(" Scan failed: {}", e);
+            report_error("Scan failed", &e);
         }
     }
 }
 
-fn cmd_process_info(args: &[String]) {
-    if args.len() < 2 {
-        println!(" Usage: info <PID>");
-        println!(" Example: info 1234");
+fn cmd_unknown_scan(args: &[String]) {
+    if args.len() < 3 {
+        println!(" Usage: unknown <TARGET> <TYPE>");
+        println!(" Types: i32, i64, f32, f64");
+        println!(" Example: unknown 1234 i32");
+        println!(" Then use 'next changed', 'next increased', ... to narrow the results");
         return;
     }
 
-    let pid: u32 = match args[1].parse() {
-        Ok(p) => p,
-        Err(_) => {
-            println!(" Invalid PID: {}", args[1]);
+    let data_type = match args[2].as_str() {
+        "i32" => DataType::I32,
+        "i64" => DataType::I64,
+        "f32" => DataType::F32,
+        "f64" => DataType::F64,
+        _ => {
+            println!(" Invalid type: {}", args[2]);
+            println!(" Valid types: i32, i64, f32, f64");
             return;
         }
     };
 
-    println!(" Gathering information for process {}...", pid);
+    let (source, pid) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
+    };
 
-    let mut process = match get_process_by_pid(pid) {
-        Some(p) => p,
+    println!(
+        " Starting unknown value scan of {} ({})",
+        source.describe(),
+        args[2]
+    );
+
+    match ScanSession::start_unknown(source.as_ref(), pid, data_type) {
+        Ok(session) => {
+            println!(" Captured {} candidate addresses", session.count());
+            if session.uses_soft_dirty() {
+                println!(" Soft-dirty tracking active - only written pages will be re-read");
+            } else {
+                println!(" Soft-dirty tracking unavailable - every page will be compared");
+            }
+            println!(" Change the value in the target, then use 'next changed'");
+            *GLOBAL_SCAN_SESSION.lock().unwrap() = Some((source, session));
+        }
+        Err(e) => {
+            report_error("Scan failed", &e);
+        }
+    }
+}
+
+fn cmd_next_scan(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: next <changed|unchanged|increased|decreased|VALUE>");
+        println!(" Narrows the results of the last 'scan' or 'unknown'");
+        return;
+    }
+
+    let mut session_guard = GLOBAL_SCAN_SESSION.lock().unwrap();
+    let (source, session) = match session_guard.as_mut() {
+        Some(s) => s,
         None => {
-            println!(" Process {} not found", pid);
+            println!(" No scan session");
+            println!(" Start one with 'scan <TARGET> <VALUE> <TYPE>' or 'unknown <TARGET> <TYPE>'");
             return;
         }
     };
 
-    if let Err(e) = process.open() {
-        println!(" Failed to open process: {}", e);
-        return;
+    let filter = ScanFilter::parse(&args[1]);
+    let before = session.count();
+
+    match session.narrow(source.as_ref(), &filter) {
+        Ok(stats) => {
+            println!(" {} -> {} candidates", before, stats.remaining);
+            if stats.soft_dirty {
+                println!(
+                    " Re-read {} of {} pages (soft-dirty)",
+                    stats.pages_reread, stats.pages_total
+                );
+            } else {
+                println!(" Compared all {} pages (no soft-dirty)", stats.pages_total);
+            }
+            if stats.remaining > 0 && stats.remaining <= 20 {
+                print_session_results(source.as_ref(), session, 20);
+            } else if stats.remaining == 0 {
+                println!(" No candidates left, start a new scan");
+            }
+        }
+        Err(e) => {
+            report_error("Narrowing failed", &e);
+        }
     }
+}
+
+fn cmd_scan_results(args: &[String]) {
+    let count = if args.len() > 1 {
+        args[1].parse().unwrap_or(20)
+    } else {
+        20
+    };
 
-    let handle = match process.handle {
-        Some(ref h) => h,
+    let session_guard = GLOBAL_SCAN_SESSION.lock().unwrap();
+    match session_guard.as_ref() {
+        Some((source, session)) => {
+            println!(" {} candidates", session.count());
+            print_session_results(source.as_ref(), session, count);
+        }
         None => {
-            println!(" Failed to get process handle");
-            return;
+            println!(" No scan session");
+        }
+    }
+}
+
+fn print_session_results(source: &dyn MemorySource, session: &ScanSession, count: usize) {
+    let symbolizer = Symbolizer::cached(source).ok();
+    println!("{:-<50}", "");
+    for (i, (address, value)) in session.candidates(count).iter().enumerate() {
+        let shown = match session.data_type() {
+            DataType::I32 => i32::from_le_bytes(value[..4].try_into().unwrap()).to_string(),
+            DataType::I64 => i64::from_le_bytes(value[..8].try_into().unwrap()).to_string(),
+            DataType::F32 => f32::from_le_bytes(value[..4].try_into().unwrap()).to_string(),
+            DataType::F64 => f64::from_le_bytes(value[..8].try_into().unwrap()).to_string(),
+            DataType::String(_) => format!("'{}'", String::from_utf8_lossy(value)),
+            DataType::Bytes(_) => hex::encode(value),
+        };
+        match symbolizer.as_ref().and_then(|s| s.describe(*address)) {
+            Some(symbol) => println!("  {:2}. 0x{:x} = {}  {}", i + 1, address, shown, symbol),
+            None => println!("  {:2}. 0x{:x} = {}", i + 1, address, shown),
         }
+    }
+    println!("{:-<50}", "");
+}
+
+fn cmd_process_info(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: info <TARGET> [--heat]");
+        println!(" Example: info 1234");
+        println!(" Example: info 1234 --heat");
+        println!(" Example: info core.1234");
+        return;
+    }
+
+    // the sample resets the target's soft-dirty bits, only on request
+    let heat = args[2..].iter().any(|a| a == "--heat");
+
+    println!(" Gathering information for {}...", args[1]);
+
+    let (source, pid) = match open_target_for(&args[1], false) {
+        Some(t) => t,
+        None => return,
     };
+    let handle = source.as_ref();
 
     println!(" Process Information:");
     println!("{:=<50}", "");
-    println!("Name: {}", process.name);
-    println!("PID:  {}", process.pid);
+    println!("Source: {}", handle.describe());
+    if handle.is_read_only() {
+        println!("Access: read-only");
+    }
+    if let Some(pid) = pid
+        && let Ok(info) = ProcessInfo::read(pid)
+    {
+        print_process_details(&info);
+    }
 
     match handle.get_memory_regions() {
         Ok(regions) => {
@@ -483,134 +1023,324 @@ fn cmd_process_info(args: &[String]) {
                     }
                 );
             }
+
+            if heat && let Some(pid) = pid {
+                print_write_heat(pid, &regions);
+            }
         }
         Err(e) => {
-            println!(" Failed to get memory regions: {}", e);
+            report_error("Failed to get memory regions", &e);
         }
     }
 }
 
-fn cmd_dump_memory(args: &[String]) {
-    if args.len() < 4 {
-        println!(" Usage: dump <PID> <ADDRESS> <SIZE>");
-        println!(" Example: dump 1234 0x7fff12345678 64");
+fn cmd_access_check(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: access <PID>");
+        println!(" Explains whether memscan can read a process's memory, and why not");
         return;
     }
 
-    let pid: u32 = match args[1].parse() {
-        Ok(p) => p,
-        Err(_) => {
-            println!(" Invalid PID: {}", args[1]);
-            return;
-        }
+    let pid = match resolve_pid(&args[1]) {
+        Some(pid) => pid,
+        None => return,
     };
 
-    let address_str = args[2].trim_start_matches("0x");
-    let address = match usize::from_str_radix(address_str, 16) {
-        Ok(addr) => addr,
-        Err(_) => {
-            println!(" Invalid address: {}", args[2]);
+    let report = match preflight(pid) {
+        Ok(report) => report,
+        Err(e) => {
+            report_error(&format!("Cannot check process {}", pid), &e);
             return;
         }
     };
 
-    let size: usize = match args[3].parse() {
-        Ok(s) => s,
-        Err(_) => {
-            println!(" Invalid size: {}", args[3]);
-            return;
-        }
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let user = |id: u32| match user_name(id) {
+        Some(name) => format!("{} ({})", id, name),
+        None => id.to_string(),
     };
 
-    if size > 4096 {
-        println!("  Size limited to 4096 bytes for display purposes");
-        return;
+    println!(" Access check for PID {}:", pid);
+    println!("{:=<50}", "");
+    println!("Our UID:         {}", user(report.our_uid));
+    match report.target_uids {
+        Some((uid, euid, suid)) if uid == euid && euid == suid => {
+            println!("Target UID:      {}", user(uid))
+        }
+        Some((uid, euid, suid)) => println!(
+            "Target UID:      {} (effective {}, saved {})",
+            user(uid),
+            euid,
+            suid
+        ),
+        None => println!("Target UID:      unknown"),
+    }
+    println!("CAP_SYS_PTRACE:  {}", yes_no(report.cap_sys_ptrace));
+    match report.ptrace_scope {
+        Some(scope) => {
+            let meaning = match scope {
+                0 => "classic, same user",
+                1 => "restricted, descendants only",
+                2 => "admin only",
+                _ => "no attach",
+            };
+            println!("ptrace_scope:    {} ({})", scope, meaning);
+        }
+        None => println!("ptrace_scope:    not present (no yama)"),
+    }
+    match report.dumpable {
+        Some(dumpable) => println!("Dumpable:        {}", yes_no(dumpable)),
+        None => println!("Dumpable:        unknown"),
+    }
+    match report.tracer {
+        Some(tracer) if tracer == std::process::id() => println!("Traced By:       memscan"),
+        Some(tracer) => println!("Traced By:       PID {}", tracer),
+        None => println!("Traced By:       nobody"),
+    }
+    println!("Our Descendant:  {}", yes_no(report.descendant));
+    match &report.probe {
+        Some(Ok(address)) => println!("Test Read:       ok (1 byte at 0x{:x})", address),
+        Some(Err(e)) => println!("Test Read:       failed ({})", e),
+        None => {}
     }
+    println!("{:=<50}", "");
 
-    let mut process = match get_process_by_pid(pid) {
-        Some(p) => p,
-        None => {
-            println!(" Process {} not found", pid);
-            return;
+    match report.error() {
+        None => println!(" ✅ Memory of PID {} is accessible", pid),
+        Some(e) => {
+            println!(" ❌ {}", e);
+            if let Some(hint) = e.hint() {
+                println!(" {}", hint);
+            }
         }
-    };
+    }
+}
 
-    if let Err(e) = process.open() {
-        println!(" Failed to open process: {}", e);
+// page write heat map from a short soft-dirty sample
+fn print_write_heat(pid: u32, regions: &[memscan::memory::MemoryRegion]) {
+    const WINDOW_MS: u64 = 500;
+
+    if !memscan::softdirty::is_supported(pid, regions) {
+        println!("\n Page write heat: unavailable (no soft-dirty support)");
         return;
     }
 
-    let handle = match process.handle {
-        Some(ref h) => h,
-        None => {
-            println!(" Failed to get process handle");
-            return;
-        }
-    };
+    let mut heat =
+        match sample_write_heat(pid, regions, std::time::Duration::from_millis(WINDOW_MS)) {
+            Ok(h) => h,
+            Err(e) => {
+                println!("\n Page write heat: unavailable ({})", e);
+                return;
+            }
+        };
 
-    println!(" Reading {} bytes from 0x{:x}...", size, address);
+    heat.retain(|h| h.dirty_pages > 0);
+    heat.sort_by_key(|h| std::cmp::Reverse(h.dirty_pages));
+
+    println!("\n Page Write Heat ({}ms sample):", WINDOW_MS);
+    println!("{:-<70}", "");
+
+    if heat.is_empty() {
+        println!("  No pages were written during the sample");
+        return;
+    }
+
+    for entry in heat.iter().take(10) {
+        let filled = (entry.ratio() * 20.0).ceil() as usize;
+        println!(
+            "0x{:012x} {:>6}/{:<6} [{}{}] {}",
+            entry.region.start_address,
+            entry.dirty_pages,
+            entry.pages,
+            "█".repeat(filled),
+            "░".repeat(20 - filled),
+            entry.region.path.as_deref().unwrap_or("[anon]")
+        );
+    }
+}
+
+fn cmd_dump_memory(args: &[String]) {
+    if args.len() < 4 {
+        println!(" Usage: dump <TARGET> <ADDRESS> <SIZE>");
+        println!(" Example: dump 1234 0x7fff12345678 64");
+        return;
+    }
+
+    let size: usize = match args[3].parse() {
+        Ok(s) => s,
+        Err(_) => {
+            println!(" Invalid size: {}", args[3]);
+            return;
+        }
+    };
+
+    if size > 4096 {
+        println!("  Size limited to 4096 bytes for display purposes");
+        return;
+    }
+
+    let (handle, pid) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
+    };
+
+    let address = match parse_address(&args[2], Some(handle.as_ref()), pid) {
+        Some(a) => a,
+        None => return,
+    };
+
+    println!(
+        " Reading {} bytes from {}...",
+        size,
+        describe_address(handle.as_ref(), address)
+    );
 
     match handle.read_memory(address, size) {
         Ok(data) => {
             println!(" Memory dump:");
             print_hex_dump(&data, address);
             print_ascii_view(&data);
+
+            // a struct from the debug info is also shown field by field
+            if let Some(variable) = lookup_variable(handle.as_ref(), &args[2])
+                && variable.address == address
+            {
+                println!();
+                print_variable(handle.as_ref(), &variable);
+            }
         }
         Err(e) => {
-            println!(" Failed to read memory: {}", e);
+            report_error("Failed to read memory", &e);
         }
     }
 }
 
-fn cmd_write_memory(args: &[String]) {
-    if args.len() < 5 {
-        println!(" Usage: write <PID> <ADDRESS> <VALUE> <TYPE>");
-        println!(" Types: i32, i64, f32, f64, string");
-        println!(" Example: write 1234 0x7fff12345678 999 i32");
+fn cmd_read_value(args: &[String]) {
+    if args.len() < 3 {
+        println!(" Usage: read <TARGET> <ADDRESS> [TYPE]");
+        println!(" Types: i32, i64, f32, f64, ptr, string, string:<size>");
+        println!(" Example: read 1234 0x7fff12345678 f32");
+        println!(" Example: read 1234 test_target!player   (type from debug info)");
         return;
     }
 
-    let pid: u32 = match args[1].parse() {
-        Ok(p) => p,
-        Err(_) => {
-            println!(" Invalid PID: {}", args[1]);
-            return;
-        }
+    let (source, pid) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
     };
 
-    let address_str = args[2].trim_start_matches("0x");
-    let address = match usize::from_str_radix(address_str, 16) {
-        Ok(addr) => addr,
-        Err(_) => {
-            println!(" Invalid address: {}", args[2]);
-            return;
+    // without a type the variable's own type comes from its debug info
+    if args.len() < 4 {
+        match lookup_variable(source.as_ref(), &args[2]) {
+            Some(variable) => print_variable(source.as_ref(), &variable),
+            None => {
+                println!(" No debug info for '{}', give a type", args[2]);
+                println!(" Types: i32, i64, f32, f64, ptr, string, string:<size>");
+            }
         }
-    };
+        return;
+    }
 
-    let value_str = &args[3];
-    let data_type = &args[4];
+    let address = match parse_address(&args[2], Some(source.as_ref()), pid) {
+        Some(a) => a,
+        None => return,
+    };
 
-    let mut process = match get_process_by_pid(pid) {
-        Some(p) => p,
-        None => {
-            println!(" Process {} not found", pid);
+    let result = match args[3].as_str() {
+        "i32" => source.read_i32(address).map(|v| v.to_string()),
+        "i64" => source.read_i64(address).map(|v| v.to_string()),
+        "f32" => source.read_f32(address).map(|v| v.to_string()),
+        "f64" => source.read_f64(address).map(|v| v.to_string()),
+        "ptr" => source.read_pointer(address).map(|v| format!("0x{:x}", v)),
+        "string" => source.read_string(address, 256).map(|v| format!("'{}'", v)),
+        s if s.starts_with("string:") => match s[7..].parse::<usize>() {
+            Ok(size) if size > 0 && size <= 4096 => source
+                .read_string(address, size)
+                .map(|v| format!("'{}'", v)),
+            _ => {
+                println!(" Invalid string size: {}", &s[7..]);
+                return;
+            }
+        },
+        _ => {
+            println!(" Invalid type: {}", args[3]);
+            println!(" Valid types: i32, i64, f32, f64, ptr, string, string:<size>");
             return;
         }
     };
 
-    if let Err(e) = process.open() {
-        println!(" Failed to open process: {}", e);
+    match result {
+        Ok(value) => println!(
+            " {} = {} ({})",
+            describe_address(source.as_ref(), address),
+            value,
+            args[3]
+        ),
+        Err(e) => report_error("Failed to read memory", &e),
+    }
+}
+
+fn cmd_snapshot(args: &[String]) {
+    if args.len() < 3 {
+        println!(" Usage: snapshot <TARGET> <FILE>");
+        println!(" Example: snapshot 1234 before.snap");
+        println!(" The snapshot can be used anywhere a PID is accepted by scan/info/dump/read");
         return;
     }
 
-    let handle = match process.handle {
-        Some(ref h) => h,
-        None => {
-            println!(" Failed to get process handle");
-            return;
+    let (source, pid) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
+    };
+
+    println!(" Saving {} to {}...", source.describe(), args[2]);
+
+    match write_snapshot(source.as_ref(), pid, Path::new(&args[2])) {
+        Ok(summary) => {
+            println!(
+                " Snapshot written: {} regions, {:.2} MB",
+                summary.regions,
+                summary.saved_bytes as f64 / 1024.0 / 1024.0
+            );
+            if summary.unreadable_bytes > 0 {
+                println!(
+                    "  {} bytes could not be read and were zero-filled",
+                    summary.unreadable_bytes
+                );
+            }
+        }
+        Err(e) => {
+            report_error("Failed to write snapshot", &e);
         }
+    }
+}
+
+fn cmd_write_memory(args: &[String]) {
+    if args.len() < 5 {
+        println!(" Usage: write <PID> <ADDRESS> <VALUE> <TYPE>");
+        println!(" Types: i32, i64, f32, f64, string");
+        println!(" Example: write 1234 0x7fff12345678 999 i32");
+        return;
+    }
+
+    let pid = match resolve_pid(&args[1]) {
+        Some(p) => p,
+        None => return,
     };
 
+    let handle = match open_process_handle(pid, true) {
+        Some(h) => h,
+        None => return,
+    };
+
+    let address = match parse_address(&args[2], Some(&handle), Some(pid)) {
+        Some(a) => a,
+        None => return,
+    };
+
+    let value_str = &args[3];
+    let data_type = &args[4];
+
     println!(
         "✏️  Writing '{}' as {} to 0x{:x}...",
         value_str, data_type, address
@@ -689,7 +1419,7 @@ fn cmd_write_memory(args: &[String]) {
             println!(" Successfully wrote {} bytes", bytes_written);
         }
         Err(e) => {
-            println!(" Write failed: {}", e);
+            report_error("Write failed", &e);
         }
     }
 }
@@ -701,46 +1431,23 @@ fn cmd_modify_value(args: &[String]) {
         return;
     }
 
-    let pid: u32 = match args[1].parse() {
-        Ok(p) => p,
-        Err(_) => {
-            println!(" Invalid PID: {}", args[1]);
-            return;
-        }
+    let pid = match resolve_pid(&args[1]) {
+        Some(p) => p,
+        None => return,
     };
 
-    let address_str = args[2].trim_start_matches("0x");
-    let address = match usize::from_str_radix(address_str, 16) {
-        Ok(addr) => addr,
-        Err(_) => {
-            println!(" Invalid address: {}", args[2]);
-            return;
-        }
+    let handle = match open_process_handle(pid, true) {
+        Some(h) => h,
+        None => return,
     };
 
-    let value_str = &args[3];
-    let data_type = &args[4];
-
-    let mut process = match get_process_by_pid(pid) {
-        Some(p) => p,
-        None => {
-            println!(" Process {} not found", pid);
-            return;
-        }
+    let address = match parse_address(&args[2], Some(&handle), Some(pid)) {
+        Some(a) => a,
+        None => return,
     };
 
-    if let Err(e) = process.open() {
-        println!(" Failed to open process: {}", e);
-        return;
-    }
-
-    let handle = match process.handle {
-        Some(ref h) => h,
-        None => {
-            println!(" Failed to get process handle");
-            return;
-        }
-    };
+    let value_str = &args[3];
+    let data_type = &args[4];
 
     // First check if memory is writable
     match handle.is_writable(address) {
@@ -749,7 +1456,7 @@ fn cmd_modify_value(args: &[String]) {
             println!("  Warning: Memory region may not be writable");
         }
         Err(e) => {
-            println!("  Warning: Could not verify memory permissions: {}", e);
+            report_error(" Warning: Could not verify memory permissions", &e);
         }
     }
 
@@ -767,7 +1474,7 @@ fn cmd_modify_value(args: &[String]) {
     let original_data = match handle.read_memory(address, size) {
         Ok(data) => data,
         Err(e) => {
-            println!(" Failed to read original value: {}", e);
+            report_error("Failed to read original value", &e);
             return;
         }
     };
@@ -858,203 +1565,2052 @@ fn cmd_modify_value(args: &[String]) {
                     }
                 }
                 Err(e) => {
-                    println!("  Could not verify write: {}", e);
+                    report_error(" Could not verify write", &e);
                 }
             }
         }
         Err(e) => {
-            println!(" Write failed: {}", e);
+            report_error("Write failed", &e);
         }
     }
 }
 
-fn cmd_start_monitor(args: &[String]) {
+fn cmd_core_dump(args: &[String]) {
     if args.len() < 2 {
-        println!(" Usage: monitor <PID> [update_interval_ms]");
-        println!(" Example: monitor 1234 100  # Monitor every 100ms");
-        println!(" Quick start: Use 'auto-monitor <PID> <VALUE> <TYPE>' for instant results");
+        println!(" Usage: core <PID> [FILE]");
+        println!(" Example: core 1234 game.core");
+        println!(" Open the result with: gdb <program> <FILE>");
         return;
     }
 
-    let pid: u32 = match args[1].parse() {
-        Ok(p) => p,
-        Err(_) => {
-            println!(" Invalid PID: {}", args[1]);
-            return;
-        }
+    let pid = match resolve_pid(&args[1]) {
+        Some(p) => p,
+        None => return,
     };
 
-    let interval_ms = if args.len() > 2 {
-        args[2].parse().unwrap_or(200)
+    let output = if args.len() > 2 {
+        args[2].clone()
     } else {
-        200 // Default 200ms updates (more stable)
+        format!("core.{}", pid)
     };
 
-    // Validate interval and warn about race conditions
-    if interval_ms < 50 {
-        println!("  Warning: Fast intervals may cause race conditions");
-        println!("   You might see partial/garbled string changes");
-        println!("   Recommended: 100ms+ for stable monitoring");
-        println!("   Use 'interval 200' to change after starting");
-    } else if interval_ms >= 100 {
-        println!(" Using stable interval: {}ms", interval_ms);
-    }
+    println!(" Writing core file for process {} to {}...", pid, output);
+    println!(" The process is stopped while its memory is copied");
 
-    // Get process handle
-    let mut process = match get_process_by_pid(pid) {
-        Some(p) => p,
-        None => {
-            println!(" Process {} not found", pid);
-            println!(" Use 'list' to see available processes");
-            return;
+    match write_core_dump(pid, std::path::Path::new(&output)) {
+        Ok(summary) => {
+            println!(" Core file written: {}", summary.path.display());
+            println!(
+                "    {} segments | {:.2} MB dumped",
+                summary.segments,
+                summary.dumped_bytes as f64 / 1024.0 / 1024.0
+            );
+            println!(
+                "    {} threads ({} with registers)",
+                summary.threads, summary.threads_with_registers
+            );
+            if summary.unreadable_bytes > 0 {
+                println!(
+                    "  {} bytes could not be read and were zero-filled",
+                    summary.unreadable_bytes
+                );
+            }
+            if summary.threads_with_registers < summary.threads {
+                println!("  Some threads could not be stopped, their registers are empty");
+                println!(" Try running with sudo");
+            }
         }
-    };
-
-    println!(" Attaching to process {} ({})...", pid, process.name);
+        Err(e) => {
+            report_error("Failed to write core file", &e);
+        }
+    }
+}
 
-    if let Err(e) = process.open() {
-        println!(" Failed to open process {}: {}", pid, e);
-        println!(" Try running with sudo or ./run_memscan.sh");
+fn cmd_list_modules(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: modules <TARGET> [MODULE]");
+        println!(" Example: modules 1234");
+        println!(" Example: modules 1234 libc.so.6");
         return;
     }
 
-    let handle = match process.handle {
-        Some(h) => Arc::new(h),
-        None => {
-            println!(" Failed to get process handle");
+    let (source, _) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
+    };
+
+    let symbolizer = match Symbolizer::cached(source.as_ref()) {
+        Ok(s) => s,
+        Err(e) => {
+            report_error("Failed to list modules", &e);
             return;
         }
     };
+    let modules = symbolizer.modules();
 
-    // Stop existing monitor if running
-    cmd_stop_monitor();
+    if let Some(name) = args.get(2) {
+        let module = match find_module(modules, name) {
+            Some(m) => m,
+            None => {
+                println!(" No module named '{}' in {}", name, source.describe());
+                return;
+            }
+        };
 
-    // Create and start new monitor
-    let monitor = MemoryMonitor::new(interval_ms);
+        println!(" {} ({})", module.name, module.path);
+        println!(
+            " Mapped 0x{:x}-0x{:x}, load bias 0x{:x}",
+            module.start, module.end, module.load_bias
+        );
+        if module.sections.is_empty() {
+            println!(" No section headers available (file missing or stripped)");
+            return;
+        }
 
-    if let Err(e) = monitor.start_monitoring(Arc::clone(&handle)) {
-        println!(" Failed to start monitoring: {}", e);
+        println!("{:-<70}", "");
+        println!(
+            "  {:<24} {:>18} {:>10}  FLAGS",
+            "SECTION", "ADDRESS", "SIZE"
+        );
+        println!("{:-<70}", "");
+        for section in module.sections.iter().filter(|s| s.is_loaded()) {
+            let flags = format!(
+                "{}{}",
+                if section.flags & SHF_WRITE != 0 {
+                    "w"
+                } else {
+                    "-"
+                },
+                if section.flags & SHF_EXECINSTR != 0 {
+                    "x"
+                } else {
+                    "-"
+                }
+            );
+            println!(
+                "  {:<24} 0x{:016x} {:>10}  {}",
+                section.name, section.address, section.size, flags
+            );
+        }
+        println!("{:-<70}", "");
         return;
     }
 
-    // Store in global state
-    {
-        let mut monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-        *monitor_guard = Some(monitor);
-    }
-    {
-        let mut handle_guard = GLOBAL_PROCESS_HANDLE.lock().unwrap();
-        *handle_guard = Some(handle);
-    }
-
-    println!(" Real-time monitoring started");
-    println!(" Process: {} (PID: {})", process.name, pid);
-    println!();
-    println!(" NEXT STEPS:");
-    println!("  1️  Add watch targets: 'watch <address> <type> <name>'");
-    println!("  2  Changes to the watch targets will appear automatically.");
-    println!();
-    println!(" QUICK OPTIONS:");
+    println!(" {} modules in {}:", modules.len(), source.describe());
+    println!("{:-<90}", "");
     println!(
-        "  • Use 'auto-monitor <VALUE> <TYPE>' to automatically find the addresses and auto-watch them"
+        "  {:<28} {:>18} {:>10} {:>18}",
+        "MODULE", "BASE", "SIZE", "LOAD BIAS"
     );
-    println!("  • Live notifications are automatically enabled");
-    println!();
-    println!(" Example: watch 0x7fff12345678 i32 health");
-    println!(" If you see garbled strings, try: interval 300");
-
-    // Automatically enable live notifications for better UX
-    {
-        let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-        if let Some(ref monitor) = *monitor_guard {
-            monitor.enable_live_notifications();
-        }
+    println!("{:-<90}", "");
+    for module in modules {
+        println!(
+            "  {:<28} 0x{:016x} {:>9}K 0x{:016x}",
+            module.name,
+            module.start,
+            module.size() / 1024,
+            module.load_bias
+        );
     }
+    println!("{:-<90}", "");
+    println!(" Use 'modules {} <MODULE>' to see its sections", args[1]);
 }
 
-fn cmd_add_watch(args: &[String]) {
-    if args.len() < 4 {
-        println!(" Usage: watch <address> <type> <name>");
-        println!(" Types: i32, i64, f32, f64, string:<size>");
-        println!(" Examples:");
-        println!("   watch 0x7fff12345678 i32 health");
-        println!("   watch 0x7fff87654321 string:32 username");
-        println!("   watch 0x7fff99999999 f32 player_x");
+fn cmd_list_variables(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: vars <TARGET> [MODULE] [FILTER]");
+        println!(" Example: vars 1234 test_target");
+        println!(" Example: vars 1234 test_target player");
+        println!(" Needs a binary built with debug info (-g)");
         return;
     }
 
-    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-    let monitor = match monitor_guard.as_ref() {
-        Some(m) => {
-            if !m.is_running() {
-                println!(" Monitor exists but is not running");
-                println!(" Start monitoring first with 'monitor <PID>'");
-                return;
-            }
-            m
-        }
-        None => {
-            println!(" No monitor running");
-            println!(" Start monitoring first with 'monitor <PID>' command");
-            return;
-        }
+    let (source, _) = match open_target(&args[1]) {
+        Some(t) => t,
+        None => return,
     };
 
-    // Parse address (support both 0x and plain hex)
-    let address_str = args[1].trim_start_matches("0x");
-    let address = match usize::from_str_radix(address_str, 16) {
-        Ok(addr) => addr,
-        Err(_) => {
-            println!(" Invalid address: {}", args[1]);
-            println!(" Use format: 0x7fff12345678 or 7fff12345678");
+    let symbolizer = match Symbolizer::cached(source.as_ref()) {
+        Ok(s) => s,
+        Err(e) => {
+            report_error("Failed to list modules", &e);
             return;
         }
     };
+    let modules = symbolizer.modules();
+    let filter = args.get(3).map(|f| f.to_lowercase());
 
-    // Parse data type
-    let data_type = match args[2].as_str() {
-        "i32" => DataType::I32,
-        "i64" => DataType::I64,
-        "f32" => DataType::F32,
-        "f64" => DataType::F64,
-        "string" => DataType::String(256), // Default string size
-        s if s.starts_with("string:") => {
-            let size_str = &s[7..];
-            match size_str.parse::<usize>() {
-                Ok(size) if size > 0 && size <= 1024 => DataType::String(size),
-                Ok(_) => {
-                    println!(" String size must be between 1 and 1024 bytes");
+    let mut shown = 0;
+    for module in modules
+        .iter()
+        .filter(|m| args.get(2).is_none_or(|name| m.matches(name)))
+    {
+        let info = match load_debug_info(&module.file) {
+            Ok(info) if !info.is_empty() => info,
+            _ => continue,
+        };
+
+        println!(" {} ({} variables):", module.name, info.variables().len());
+        println!("{:-<80}", "");
+        for variable in info.variables() {
+            if filter
+                .as_ref()
+                .is_some_and(|f| !variable.name.to_lowercase().contains(f))
+            {
+                continue;
+            }
+            println!(
+                "  0x{:016x} {:<24} {:<24} {:>6}",
+                module.load_bias.wrapping_add(variable.address),
+                variable.name,
+                variable.ty.name,
+                variable.ty.size
+            );
+            shown += 1;
+        }
+        println!("{:-<80}", "");
+    }
+
+    if shown == 0 {
+        println!(" No variables with debug info found");
+        println!(" Build the target with -g to get them");
+    } else {
+        println!(
+            " Use 'read {} MODULE!VARIABLE' or 'watch MODULE!VARIABLE'",
+            args[1]
+        );
+    }
+}
+
+fn cmd_list_threads(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: threads <PID>");
+        println!(" Example: threads 1234");
+        return;
+    }
+
+    let pid = match resolve_pid(&args[1]) {
+        Some(p) => p,
+        None => return,
+    };
+
+    match list_threads(pid) {
+        Ok(threads) => {
+            println!(" {} threads in process {}:", threads.len(), pid);
+            println!("{:-<50}", "");
+            println!("  {:>8}  {:<5}  NAME", "TID", "STATE");
+            for thread in &threads {
+                println!(
+                    "  {:>8}  {:<5}  {}{}",
+                    thread.tid,
+                    thread.state,
+                    thread.name,
+                    if thread.tid == pid { " (main)" } else { "" }
+                );
+            }
+            println!("{:-<50}", "");
+            println!(" Use 'regs <TID>' to see a thread's registers");
+        }
+        Err(e) => {
+            report_error("Failed to list threads", &e);
+        }
+    }
+}
+
+fn cmd_show_registers(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: regs <TID> [REG=VALUE ...]");
+        println!(" Example: regs 1234");
+        println!(" Example: regs 1234 rax=0 rip=0x401000");
+        println!(" The thread is stopped briefly while its registers are read");
+        return;
+    }
+
+    // a process name means its main thread
+    let tid = match resolve_pid(&args[1]) {
+        Some(t) => t,
+        None => return,
+    };
+
+    let mut registers = None;
+    for assignment in &args[2..] {
+        let (name, value) = match assignment.split_once('=') {
+            Some((name, value)) => (name, value),
+            None => {
+                println!(" Expected REG=VALUE, got '{}'", assignment);
+                return;
+            }
+        };
+        let parsed = match value.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => value
+                .parse::<u64>()
+                .ok()
+                .or_else(|| value.parse::<i64>().ok().map(|v| v as u64)),
+        };
+        let value = match parsed {
+            Some(v) => v,
+            None => {
+                println!(" Invalid value for {}: {}", name, value);
+                return;
+            }
+        };
+
+        match write_register(tid, name, value) {
+            Ok(regs) => {
+                println!(" {} = 0x{:x}", name, value);
+                registers = Some(regs);
+            }
+            Err(e) => {
+                report_error(&format!("Failed to set {}", name), &e);
+                return;
+            }
+        }
+    }
+
+    let registers = match registers {
+        Some(r) => r,
+        None => match read_registers(tid) {
+            Ok(r) => r,
+            Err(e) => {
+                report_error("Failed to read registers", &e);
+                return;
+            }
+        },
+    };
+
+    println!(" Registers of thread {}:", tid);
+    print_registers(&registers);
+}
+
+fn print_registers(registers: &Registers) {
+    println!("{:-<50}", "");
+    for (i, name) in DISPLAY_ORDER.iter().enumerate() {
+        if let Some(value) = registers.get(name) {
+            print!("  {:<8} 0x{:016x}", name, value);
+            if i % 2 == 1 {
+                println!();
+            }
+        }
+    }
+    println!();
+    println!("{:-<50}", "");
+}
+
+fn cmd_spawn(args: &[String]) {
+    let mut options = SpawnOptions::default();
+    let mut rest = args.iter().skip(1);
+
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--cwd" => match rest.next() {
+                Some(dir) => options.cwd = Some(dir.into()),
+                None => {
+                    println!(" --cwd needs a directory");
+                    return;
+                }
+            },
+            "--env" => match rest.next().and_then(|v| v.split_once('=')) {
+                Some((key, value)) => options.env.push((key.to_string(), value.to_string())),
+                None => {
+                    println!(" --env needs KEY=VALUE");
+                    return;
+                }
+            },
+            "--clear-env" => options.clear_env = true,
+            program => {
+                options.program = program.to_string();
+                options.args = rest.cloned().collect();
+                break;
+            }
+        }
+    }
+
+    if options.program.is_empty() {
+        println!(
+            " Usage: spawn [--cwd DIR] [--env KEY=VALUE]... [--clear-env] <PROGRAM> [ARGS...]"
+        );
+        println!(" Starts the program under memscan and stops it before main");
+        println!(" Example: spawn ./test_target");
+        println!(" Example: spawn --env LEVEL=3 --cwd /tmp ./game --windowed");
+        return;
+    }
+
+    let mut spawned_guard = GLOBAL_SPAWNED.lock().unwrap();
+    if let Some(previous) = spawned_guard.as_mut() {
+        if previous.is_stopped() {
+            println!(
+                " PID {} is still stopped, use 'continue' or 'kill' first",
+                previous.pid()
+            );
+            return;
+        }
+        previous.try_wait();
+    }
+
+    println!(" Launching {}...", options.program);
+
+    match spawn_stopped(&options) {
+        Ok(spawned) => {
+            match spawned.entry() {
+                Some(entry) => println!(
+                    " Started PID {}, stopped at entry point 0x{:x}",
+                    spawned.pid(),
+                    entry
+                ),
+                None => println!(" Started PID {}, stopped after exec", spawned.pid()),
+            }
+            println!(" Scan, write or watch it now, then use 'continue' to let it run");
+            println!(" Example: monitor {}", spawned.pid());
+            *spawned_guard = Some(spawned);
+        }
+        Err(e) => {
+            println!(" Failed to launch {}: {}", options.program, e);
+        }
+    }
+}
+
+fn cmd_continue_spawned() {
+    // stopped at a breakpoint
+    if let Some(tracer) = TRACER.lock().unwrap().as_ref()
+        && tracer.is_paused()
+    {
+        match tracer.resume() {
+            Ok(()) => println!(" PID {} is running", tracer.pid()),
+            Err(e) => report_error("Failed to continue", &e),
+        }
+        return;
+    }
+
+    let mut spawned_guard = GLOBAL_SPAWNED.lock().unwrap();
+    let spawned = match spawned_guard.as_mut() {
+        Some(s) if s.is_stopped() => s,
+        _ => {
+            // a monitored process a 'pause' trigger stopped
+            let handle = GLOBAL_PROCESS_HANDLE.lock().unwrap().clone();
+            match handle {
+                // traced by our watchpoints it shows as a tracing stop
+                Some(handle)
+                    if ProcessInfo::read(handle.pid()).is_ok_and(|i| {
+                        i.state == 'T' || (i.state == 't' && TRACER.lock().unwrap().is_some())
+                    }) =>
+                {
+                    match handle.resume() {
+                        Ok(()) => println!(" PID {} is running", handle.pid()),
+                        Err(e) => report_error("Failed to continue", &e),
+                    }
+                }
+                _ => println!(" No stopped process, start one with 'spawn <PROGRAM>'"),
+            }
+            return;
+        }
+    };
+
+    match spawned.resume() {
+        Ok(()) => println!(" PID {} is running", spawned.pid()),
+        Err(e) => println!(" Failed to continue PID {}: {}", spawned.pid(), e),
+    }
+}
+
+fn cmd_kill_spawned() {
+    let mut spawned_guard = GLOBAL_SPAWNED.lock().unwrap();
+    let spawned = match spawned_guard.as_mut() {
+        Some(s) => s,
+        None => {
+            println!(" No spawned process is running");
+            return;
+        }
+    };
+    if let Some(status) = spawned.try_wait() {
+        println!(
+            " PID {} already exited with status {}",
+            spawned.pid(),
+            status
+        );
+        *spawned_guard = None;
+        return;
+    }
+
+    match spawned.kill() {
+        Ok(()) => {
+            println!(" Killed PID {}", spawned.pid());
+            *spawned_guard = None;
+        }
+        Err(e) => println!(" Failed to kill PID {}: {}", spawned.pid(), e),
+    }
+}
+
+fn cmd_start_monitor(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: monitor <PID> [update_interval_ms]");
+        println!(" Example: monitor 1234 100  # Monitor every 100ms");
+        println!(" Quick start: Use 'auto-monitor <PID> <VALUE> <TYPE>' for instant results");
+        return;
+    }
+
+    let pid = match resolve_pid(&args[1]) {
+        Some(p) => p,
+        None => return,
+    };
+
+    let interval_ms = if args.len() > 2 {
+        args[2].parse().unwrap_or(200)
+    } else {
+        200 // Default 200ms updates (more stable)
+    };
+
+    // Validate interval and warn about race conditions
+    if interval_ms < 50 {
+        println!("  Warning: Fast intervals may cause race conditions");
+        println!("   You might see partial/garbled string changes");
+        println!("   Recommended: 100ms+ for stable monitoring");
+        println!("   Use 'interval 200' to change after starting");
+    } else if interval_ms >= 100 {
+        println!(" Using stable interval: {}ms", interval_ms);
+    }
+
+    println!(" Attaching to process {}...", pid);
+
+    let handle = match open_process_handle(pid, true) {
+        Some(h) => Arc::new(h),
+        None => return,
+    };
+    let description = handle.describe();
+
+    // Stop existing monitor if running
+    cmd_stop_monitor();
+
+    // Create and start new monitor
+    let monitor = MemoryMonitor::new(interval_ms);
+    let events = monitor.subscribe();
+
+    if let Err(e) = monitor.start_monitoring(Arc::clone(&handle)) {
+        report_error("Failed to start monitoring", &e);
+        return;
+    }
+    std::thread::spawn(move || print_notifications(events));
+
+    // Store in global state
+    {
+        let mut monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+        *monitor_guard = Some(monitor);
+    }
+    {
+        let mut handle_guard = GLOBAL_PROCESS_HANDLE.lock().unwrap();
+        *handle_guard = Some(handle);
+    }
+    *GLOBAL_MONITORED_IMAGE.lock().unwrap() = image_range(pid);
+
+    println!(" Real-time monitoring started");
+    println!(" Process: {}", description);
+    println!();
+    println!(" NEXT STEPS:");
+    println!("  1️  Add watch targets: 'watch <address> <type> <name>'");
+    println!("  2  Changes to the watch targets will appear automatically.");
+    println!();
+    println!(" QUICK OPTIONS:");
+    println!(
+        "  • Use 'auto-monitor <VALUE> <TYPE>' to automatically find the addresses and auto-watch them"
+    );
+    println!("  • Live notifications are automatically enabled");
+    println!();
+    println!(" Example: watch 0x7fff12345678 i32 health");
+    println!(" If you see garbled strings, try: interval 300");
+
+    // Automatically enable live notifications for better UX
+    LIVE_NOTIFICATIONS.store(true, Ordering::Relaxed);
+}
+
+// the CLI's subscriber: prints changes while notifications are on, and
+// always says when the process exits. ends with the monitor
+fn print_notifications(events: Receiver<MonitorEvent>) {
+    for event in events {
+        let live = LIVE_NOTIFICATIONS.load(Ordering::Relaxed);
+        match event {
+            MonitorEvent::ValueChanged(change) if live => print_change_notification(&change),
+            MonitorEvent::ReadFailed {
+                target_id,
+                address,
+                name,
+                error,
+            } if live => {
+                println!(
+                    "\n⚠️  READ FAILED: #{} {} (0x{:x}) - {}",
+                    target_id, name, address, error
+                );
+            }
+            MonitorEvent::AddressChanged {
+                target_id,
+                name,
+                old_address,
+                new_address,
+            } if live => {
+                println!(
+                    "\n🔗 MOVED: #{} {} now at 0x{:x} (was 0x{:x})",
+                    target_id, name, new_address, old_address
+                );
+            }
+            MonitorEvent::ProcessExited(exit) => print_exit_notification(&exit),
+            // configured on purpose, so shown even with notifications off
+            MonitorEvent::Triggered(hit) => print_trigger_notification(&hit),
+            // add/remove are reported by the commands themselves
+            _ => continue,
+        }
+        print!("{}", prompt()); // Re-display prompt
+        io::stdout().flush().unwrap();
+    }
+}
+
+fn print_change_notification(change: &MonitorChange) {
+    let elapsed = change.timestamp.elapsed();
+    println!(
+        "\n🔄 CHANGE DETECTED: #{} {} (0x{:x}) - {:.1}s ago",
+        change.target_id,
+        change.name,
+        change.address,
+        elapsed.as_secs_f64()
+    );
+
+    // Display based on the actual data type stored with the target
+    match change.data_type {
+        DataType::I32 => {
+            let old_i32 = i32::from_le_bytes(change.old_value[..4].try_into().unwrap_or([0; 4]));
+            let new_i32 = i32::from_le_bytes(change.new_value[..4].try_into().unwrap_or([0; 4]));
+            println!("   📊 {} → {} (i32)", old_i32, new_i32);
+        }
+        DataType::I64 => {
+            let old_i64 = i64::from_le_bytes(change.old_value[..8].try_into().unwrap_or([0; 8]));
+            let new_i64 = i64::from_le_bytes(change.new_value[..8].try_into().unwrap_or([0; 8]));
+            println!("   📊 {} → {} (i64)", old_i64, new_i64);
+        }
+        DataType::F32 => {
+            let old_f32 = f32::from_le_bytes(change.old_value[..4].try_into().unwrap_or([0; 4]));
+            let new_f32 = f32::from_le_bytes(change.new_value[..4].try_into().unwrap_or([0; 4]));
+            println!("   📊 {:.3} → {:.3} (f32)", old_f32, new_f32);
+        }
+        DataType::F64 => {
+            let old_f64 = f64::from_le_bytes(change.old_value[..8].try_into().unwrap_or([0; 8]));
+            let new_f64 = f64::from_le_bytes(change.new_value[..8].try_into().unwrap_or([0; 8]));
+            println!("   📊 {:.6} → {:.6} (f64)", old_f64, new_f64);
+        }
+        DataType::String(_) => display_string_change_cli(change),
+        DataType::Bytes(size) => println!(
+            "   📊 {} → {} ({} bytes)",
+            hex::encode(&change.old_value),
+            hex::encode(&change.new_value),
+            size
+        ),
+    }
+}
+
+fn print_trigger_notification(hit: &TriggerHit) {
+    println!(
+        "\n🎯 TRIGGER #{}: {} (0x{:x}) {} - {} → {}",
+        hit.trigger_id,
+        hit.name,
+        hit.address,
+        hit.condition,
+        format_watch_value(&hit.data_type, &hit.old_value),
+        format_watch_value(&hit.data_type, &hit.new_value)
+    );
+    match &hit.outcome {
+        Ok(done) => println!("   ⚡ {}", done),
+        Err(e) => println!("   ❌ {} failed: {}", hit.action, e),
+    }
+    if hit.action == TriggerAction::Pause && hit.outcome.is_ok() {
+        println!("   Use 'continue' to let it run again");
+    }
+}
+
+// a watched value for one-line output
+fn format_watch_value(data_type: &DataType, bytes: &[u8]) -> String {
+    match data_type {
+        DataType::String(_) => format!("'{}'", format_value(data_type, bytes)),
+        _ => format_value(data_type, bytes),
+    }
+}
+
+fn print_exit_notification(exit: &MonitorExit) {
+    if exit.replaced {
+        println!(
+            "\n💀 PROCESS EXITED: PID {} now belongs to another program",
+            exit.pid
+        );
+    } else {
+        println!("\n💀 PROCESS EXITED: PID {}", exit.pid);
+    }
+    println!("   Monitoring stopped, 'wait <NAME> --restore' picks it up again");
+}
+
+fn cmd_wait_for_process(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: wait <NAME> [--restore] [--timeout SECONDS]");
+        println!(" Polls until a matching process starts, then starts monitoring it");
+        println!(" --restore re-adds the watch list of the previous monitor");
+        println!(" Example: wait test_target --restore");
+        return;
+    }
+
+    let pattern = &args[1];
+    let mut restore = false;
+    let mut timeout = None;
+
+    let mut options = args.iter().skip(2);
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--restore" | "-r" => restore = true,
+            "--timeout" | "-t" => match options.next().and_then(|v| v.parse::<u64>().ok()) {
+                Some(secs) => timeout = Some(std::time::Duration::from_secs(secs)),
+                None => {
+                    println!(" --timeout needs a number of seconds");
+                    return;
+                }
+            },
+            _ => {
+                println!(" Unknown option '{}'", option);
+                return;
+            }
+        }
+    }
+
+    // the process we're monitoring now is the one that's going away
+    let stale_pid = monitored_pid();
+
+    println!(" Waiting for a process matching '{}'...", pattern);
+    let started = std::time::Instant::now();
+
+    let pid = loop {
+        let found = match match_processes(pattern) {
+            Ok(found) => found,
+            Err(e) => {
+                report_error("Invalid process pattern", &e);
+                return;
+            }
+        };
+
+        // several new matches: take the newest one
+        let newest = found
+            .iter()
+            .filter(|p| Some(p.pid) != stale_pid)
+            .max_by_key(|p| p.info().map(|i| i.start_time).unwrap_or(0));
+
+        if let Some(process) = newest {
+            println!(
+                " Found {} (PID {}) after {:.1}s",
+                process.name,
+                process.pid,
+                started.elapsed().as_secs_f64()
+            );
+            break process.pid;
+        }
+
+        if timeout.is_some_and(|t| started.elapsed() >= t) {
+            println!(" Timed out waiting for '{}'", pattern);
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    };
+
+    cmd_start_monitor(&["monitor".to_string(), pid.to_string()]);
+
+    if restore {
+        restore_watch_list(pid);
+    }
+}
+
+// re-add the last saved watch list to the current monitor. addresses inside
+// the old executable mapping are moved along with its new load address
+fn restore_watch_list(pid: u32) {
+    let saved = match LAST_WATCH_LIST.lock().unwrap().clone() {
+        Some(saved) => saved,
+        None => {
+            println!(" No previous watch list to restore");
+            return;
+        }
+    };
+
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) if m.is_running() => m,
+        _ => {
+            println!(" Monitor is not running, watch list not restored");
+            return;
+        }
+    };
+
+    let new_image = image_range(pid);
+    let rebase = match (&saved.image, &new_image) {
+        (Some(old), Some(new)) if old.path == new.path && old.start != new.start => {
+            println!(
+                " Executable moved from 0x{:x} to 0x{:x}, rebasing its addresses",
+                old.start, new.start
+            );
+            Some((old.clone(), new.start))
+        }
+        _ => None,
+    };
+
+    let rebased = |address: usize| match &rebase {
+        Some((old, new_start)) if (old.start..old.end).contains(&address) => {
+            address - old.start + new_start
+        }
+        _ => address,
+    };
+
+    for target in &saved.targets {
+        let address = rebased(target.address);
+        // a chain's base is looked up again by name, a hex base is rebased
+        // like any other address. the pointers are followed on the next read
+        let chain = target
+            .chain
+            .as_ref()
+            .map(|chain| match parse_hex(&chain.base) {
+                Some(_) => PointerChain {
+                    base_address: rebased(chain.base_address),
+                    ..chain.clone()
+                },
+                None => PointerChain::parse(&chain.to_string(), None, Some(pid))
+                    .unwrap_or_else(|_| chain.clone()),
+            });
+        let id = monitor.add_target(address, target.data_type.clone(), target.name.clone());
+        println!("  📍 #{} {} at 0x{:x}", id, target.name, address);
+        let _ = monitor.set_target_interval(id, target.interval);
+        let _ = monitor.set_group(id, target.group.clone());
+        let _ = monitor.set_pointer_chain(id, chain);
+        if let Some(range) = &target.range {
+            let _ = monitor.set_range_type(id, range.interpret.clone());
+        }
+        if !target.enabled {
+            let _ = monitor.set_enabled(id, false);
+        }
+        for trigger in &target.triggers {
+            let _ = monitor.add_trigger(id, trigger.condition.clone(), trigger.action.clone());
+        }
+    }
+
+    println!(" Restored {} watch targets", saved.targets.len());
+}
+
+// address range covered by a process's main executable
+fn image_range(pid: u32) -> Option<ImageRange> {
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+    let exe = exe.to_string_lossy().to_string();
+    let regions = memscan::memory::get_memory_regions(pid).ok()?;
+
+    let mapped: Vec<_> = regions
+        .iter()
+        .filter(|r| r.path.as_deref() == Some(exe.as_str()))
+        .collect();
+    let start = mapped.iter().map(|r| r.start_address).min()?;
+    let end = mapped.iter().map(|r| r.start_address + r.size).max()?;
+
+    Some(ImageRange {
+        path: exe,
+        start,
+        end,
+    })
+}
+
+// takes "<NAME> <VALUE>" out of the arguments. Err after saying the value
+// is missing
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, ()> {
+    let Some(index) = args.iter().position(|a| a == name) else {
+        return Ok(None);
+    };
+    match args.get(index + 1).cloned() {
+        Some(value) => {
+            args.drain(index..index + 2);
+            Ok(Some(value))
+        }
+        None => {
+            println!(" {} needs a value", name);
+            Err(())
+        }
+    }
+}
+
+fn cmd_add_watch(args: &[String]) {
+    // "--group <NAME>" anywhere puts the new targets in that group, "--as
+    // <TYPE>" reads the changes of a bytes:<SIZE> range as that type
+    let mut args = args.to_vec();
+    let (group, interpret) = match (
+        take_option(&mut args, "--group"),
+        take_option(&mut args, "--as"),
+    ) {
+        (Ok(group), Ok(interpret)) => (
+            group.map(|g| g.trim_start_matches('@').to_string()),
+            interpret,
+        ),
+        _ => return,
+    };
+
+    if args.len() < 2 {
+        println!(" Usage: watch <address> [type] [name] [--group NAME] [--as TYPE]");
+        println!(" Types: i32, i64, f32, f64, string:<size>, bytes:<size> (a range)");
+        println!(" Examples:");
+        println!("   watch 0x7fff12345678 i32 health");
+        println!("   watch counter!score i32");
+        println!("   watch 0x7fff87654321 string:32 username");
+        println!("   watch 0x7fff99999999 f32 player_x");
+        println!("   watch test_target!score        (type from debug info)");
+        println!("   watch test_target!player       (every field of a struct)");
+        println!("   watch test_target!score f32 --group odd  (a second view of the same address)");
+        println!(
+            "   watch 0x7fff12340000 bytes:4096 block --as i32  (changed i32s in a 4KB block)"
+        );
+        return;
+    }
+
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => {
+            if !m.is_running() {
+                println!(" Monitor exists but is not running");
+                println!(" Start monitoring first with 'monitor <PID>'");
+                return;
+            }
+            m
+        }
+        None => {
+            println!(" No monitor running");
+            println!(" Start monitoring first with 'monitor <PID>' command");
+            return;
+        }
+    };
+
+    // without a type the variable's debug info decides, structs become one
+    // target per field
+    let explicit_type = args.get(2).is_some_and(|t| {
+        matches!(t.as_str(), "i32" | "i64" | "f32" | "f64")
+            || t.starts_with("string")
+            || t.starts_with("bytes:")
+    });
+    if !explicit_type {
+        let variable = match GLOBAL_PROCESS_HANDLE.lock().unwrap().as_ref() {
+            Some(handle) => lookup_variable(handle.as_ref(), &args[1]),
+            None => None,
+        };
+        match variable {
+            Some(variable) => {
+                watch_variable(monitor, &variable, args.get(2).map(|s| s.as_str()), group)
+            }
+            None => {
+                println!(" No debug info for '{}', give a type", args[1]);
+                println!(" Types: i32, i64, f32, f64, string, string:<size>");
+            }
+        }
+        return;
+    }
+
+    // a pointer chain is kept and followed again on every read. it may not
+    // lead anywhere yet (the object isn't allocated), the monitor keeps trying
+    let chain = if is_pointer_chain(&args[1]) {
+        match PointerChain::parse(&args[1], None, monitored_pid()) {
+            Ok(chain) => Some(chain),
+            Err(e) => {
+                report_error("Invalid pointer chain", &e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    // hex, a register expression like rsp+0x20 or a symbol in the monitored process
+    let address = match &chain {
+        Some(chain) => {
+            let resolved = GLOBAL_PROCESS_HANDLE
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|handle| chain.resolve(handle.as_ref()));
+            match resolved {
+                Some(Ok(address)) => address,
+                Some(Err(e)) => {
+                    println!("⚠️  {} doesn't lead anywhere yet: {}", chain, e);
+                    chain.base_address
+                }
+                None => chain.base_address,
+            }
+        }
+        None => match parse_address(&args[1], None, monitored_pid()) {
+            Some(a) => a,
+            None => {
+                println!(" Use format: 0x7fff12345678, 7fff12345678, rsp+0x20, module!symbol+0x10");
+                println!("             or a pointer chain: module!symbol->0x30->0x8");
+                return;
+            }
+        },
+    };
+
+    let data_type = match parse_data_type(&args[2]) {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!(" {}", e);
+            if let Some(hint) = e.hint() {
+                println!(" {}", hint);
+            }
+            return;
+        }
+    };
+
+    let interpret = match (&data_type, interpret) {
+        (_, None) => None,
+        (DataType::Bytes(size), Some(type_name)) => {
+            match parse_data_type(&type_name)
+                .and_then(|t| RangeState::check_interpret(&t, *size).map(|_| t))
+            {
+                Ok(interpret) => Some(interpret),
+                Err(e) => {
+                    report_error("Can't read the range that way", &e);
+                    return;
+                }
+            }
+        }
+        (_, Some(_)) => {
+            println!(" --as only applies to bytes:<SIZE> ranges");
+            return;
+        }
+    };
+
+    // the address expression names the target when no name is given
+    let name = args.get(3).unwrap_or(&args[1]).to_string();
+
+    // Add target to monitor
+    let id = match add_watch_target(
+        monitor,
+        address,
+        data_type,
+        interpret.clone(),
+        chain.clone(),
+        &name,
+        group,
+    ) {
+        Some(id) => id,
+        None => return,
+    };
+    println!(" Added '{}' to watch list as #{}", name, id);
+    match GLOBAL_PROCESS_HANDLE.lock().unwrap().as_ref() {
+        Some(handle) => println!("📍 Address: {}", describe_address(handle.as_ref(), address)),
+        None => println!("📍 Address: 0x{:x}", address),
+    }
+    if let Some(chain) = &chain {
+        println!("🔗 Followed on every read: {}", chain);
+    }
+    match &interpret {
+        Some(interpret) => println!(
+            " Type: {}, changes read as {}",
+            args[2],
+            data_type_name(interpret)
+        ),
+        None => println!(" Type: {}", args[2]),
+    }
+    println!("🔴 Live notifications active - changes will appear automatically");
+}
+
+fn watch_variable(
+    monitor: &MemoryMonitor,
+    variable: &TypedAddress,
+    name: Option<&str>,
+    group: Option<String>,
+) {
+    let name = name.unwrap_or(&variable.name);
+
+    if !variable.ty.is_struct() {
+        match variable.ty.data_type() {
+            Some(data_type) => {
+                let id = match add_watch_target(
+                    monitor,
+                    variable.address,
+                    data_type,
+                    None,
+                    None,
+                    name,
+                    group,
+                ) {
+                    Some(id) => id,
+                    None => return,
+                };
+                println!(" Added '{}' to watch list as #{}", name, id);
+                println!("📍 Address: 0x{:x}", variable.address);
+                println!(" Type: {} (from debug info)", variable.ty.name);
+                println!("🔴 Live notifications active - changes will appear automatically");
+            }
+            None => println!(" Can't watch a {} as a single value", variable.ty.name),
+        }
+        return;
+    }
+
+    // the fields of a struct stay together, so they can be switched off as one
+    let group = group.unwrap_or_else(|| name.to_string());
+    let mut added = 0;
+    for (field, offset, ty) in variable.ty.leaves() {
+        let field_name = format!("{}.{}", name, field);
+        match ty.data_type() {
+            Some(data_type) => {
+                let address = variable.address + offset;
+                let Some(id) = add_watch_target(
+                    monitor,
+                    address,
+                    data_type,
+                    None,
+                    None,
+                    &field_name,
+                    Some(group.clone()),
+                ) else {
+                    continue;
+                };
+                println!(
+                    "  📍 #{} {} ({}) at 0x{:x}",
+                    id, field_name, ty.name, address
+                );
+                added += 1;
+            }
+            None => println!("  ⏭️  {} ({}) skipped", field_name, ty.name),
+        }
+    }
+    println!(
+        " Added {} fields of {} to watch list in group @{}",
+        added, variable.ty.name, group
+    );
+    println!("🔴 Live notifications active - changes will appear automatically");
+}
+
+// the same address can be watched as another type, but not twice as the same
+// (a pointer chain and a fixed address that happen to meet are different)
+fn add_watch_target(
+    monitor: &MemoryMonitor,
+    address: usize,
+    data_type: DataType,
+    interpret: Option<DataType>,
+    chain: Option<PointerChain>,
+    name: &str,
+    group: Option<String>,
+) -> Option<usize> {
+    let type_name = view_name(&data_type, interpret.as_ref());
+    if let Some(existing) = monitor
+        .targets_at(address)
+        .into_iter()
+        .find(|t| target_view_name(t) == type_name && t.chain == chain)
+    {
+        println!(
+            " 0x{:x} is already watched as {} by #{} {}",
+            address, type_name, existing.id, existing.name
+        );
+        return None;
+    }
+
+    let id = monitor.add_target(address, data_type, name.to_string());
+    if group.is_some() {
+        let _ = monitor.set_group(id, group);
+    }
+    if interpret.is_some() {
+        let _ = monitor.set_range_type(id, interpret);
+    }
+    if chain.is_some() {
+        let _ = monitor.set_pointer_chain(id, chain);
+    }
+    Some(id)
+}
+
+// "i32", or "bytes:64 as i32" for a range read as numbers
+fn view_name(data_type: &DataType, interpret: Option<&DataType>) -> String {
+    match interpret {
+        Some(interpret) => format!(
+            "{} as {}",
+            data_type_name(data_type),
+            data_type_name(interpret)
+        ),
+        None => data_type_name(data_type),
+    }
+}
+
+fn target_view_name(target: &MonitorTarget) -> String {
+    let interpret = target.range.as_ref().and_then(|r| r.interpret.as_ref());
+    view_name(&target.data_type, interpret)
+}
+
+// "#3" is one target, "@enemy" a group and anything else an address, which
+// means every target watching it. None after saying why nothing matched
+fn resolve_targets(monitor: &MemoryMonitor, reference: &str) -> Option<Vec<MonitorTarget>> {
+    if let Some(id) = reference.strip_prefix('#') {
+        let target = id.parse().ok().and_then(|id| monitor.get_target(id));
+        if target.is_none() {
+            println!(" No watch target {}", reference);
+            println!(" Use 'targets' to see the ids");
+        }
+        return target.map(|target| vec![target]);
+    }
+
+    if let Some(group) = reference.strip_prefix('@') {
+        let targets: Vec<_> = monitor
+            .get_targets()
+            .into_iter()
+            .filter(|t| t.group.as_deref() == Some(group))
+            .collect();
+        if targets.is_empty() {
+            println!(" No group '{}'", group);
+            return None;
+        }
+        return Some(targets);
+    }
+
+    let address = parse_address(reference, None, monitored_pid())?;
+    let targets = monitor.targets_at(address);
+    if targets.is_empty() {
+        println!(" 0x{:x} is not being watched", address);
+        return None;
+    }
+    Some(targets)
+}
+
+// like resolve_targets, but an address watched as several types has to be
+// picked by id
+fn resolve_target(monitor: &MemoryMonitor, reference: &str) -> Option<MonitorTarget> {
+    let mut targets = resolve_targets(monitor, reference)?;
+    if targets.len() > 1 {
+        println!(
+            " '{}' matches {} targets, pick one by id:",
+            reference,
+            targets.len()
+        );
+        for target in &targets {
+            println!("  {}", describe_target(target));
+        }
+        return None;
+    }
+    targets.pop()
+}
+
+// "#3 health (0x7fff1234, i32, @player)"
+fn describe_target(target: &MonitorTarget) -> String {
+    let group = match &target.group {
+        Some(group) => format!(", @{}", group),
+        None => String::new(),
+    };
+    let chain = match &target.chain {
+        Some(chain) => format!(" via {}", chain),
+        None => String::new(),
+    };
+    format!(
+        "#{} {} (0x{:x}, {}{}){}",
+        target.id,
+        target.name,
+        target.address,
+        target_view_name(target),
+        group,
+        chain
+    )
+}
+
+fn cmd_remove_watch(args: &[String]) {
+    if args.len() < 2 {
+        println!(" Usage: unwatch <#ID|ADDRESS|@GROUP>");
+        println!(" Example: unwatch 0x7fff12345678   (every type watched there)");
+        return;
+    }
+
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => m,
+        None => {
+            println!(" No monitor running");
+            return;
+        }
+    };
+
+    let targets = match resolve_targets(monitor, &args[1]) {
+        Some(targets) => targets,
+        None => return,
+    };
+    for target in targets {
+        if monitor.remove_target(target.id).is_some() {
+            println!(" Removed {} from watch list", describe_target(&target));
+        }
+    }
+}
+
+fn cmd_enable_watch(args: &[String], enabled: bool) {
+    if args.len() < 2 {
+        println!(" Usage: {} <#ID|ADDRESS|@GROUP>", args[0]);
+        println!(" Disabled targets stay in the watch list but aren't read");
+        return;
+    }
+
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => m,
+        None => {
+            println!(" No monitor running");
+            return;
+        }
+    };
+
+    let verb = if enabled { "Enabled" } else { "Disabled" };
+    if let Some(group) = args[1].strip_prefix('@') {
+        match monitor.set_group_enabled(group, enabled) {
+            0 => println!(" No group '{}'", group),
+            count => println!(" {} group @{} ({} targets)", verb, group, count),
+        }
+        return;
+    }
+
+    let targets = match resolve_targets(monitor, &args[1]) {
+        Some(targets) => targets,
+        None => return,
+    };
+    for target in targets {
+        match monitor.set_enabled(target.id, enabled) {
+            Ok(()) => println!(" {} {}", verb, describe_target(&target)),
+            Err(e) => report_error("Failed to change target", &e),
+        }
+    }
+}
+
+fn cmd_group(args: &[String]) {
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => m,
+        None => {
+            println!(" No monitor running");
+            return;
+        }
+    };
+
+    if args.len() < 2 {
+        let groups = monitor.groups();
+        if groups.is_empty() {
+            println!(" No groups");
+            println!(" Usage: group <NAME> add <WATCH>...     (or watch ... --group NAME)");
+            println!("        group <NAME> remove <WATCH>...");
+            return;
+        }
+        println!(" Groups ({}):", groups.len());
+        println!("{:-^70}", "");
+        for (group, total, enabled) in groups {
+            let state = match enabled {
+                0 => "disabled".to_string(),
+                n if n == total => "enabled".to_string(),
+                n => format!("{} of {} enabled", n, total),
+            };
+            println!(" @{}: {} targets, {}", group, total, state);
+        }
+        return;
+    }
+
+    let group = args[1].trim_start_matches('@');
+    let add = match args.get(2).map(|s| s.as_str()) {
+        Some("add") => true,
+        Some("remove") | Some("rm") => false,
+        _ => {
+            println!(" Usage: group <NAME> add|remove <#ID|ADDRESS|@GROUP>...");
+            return;
+        }
+    };
+    if args.len() < 4 {
+        println!(" Name the targets to {}", args[2]);
+        return;
+    }
+
+    for reference in &args[3..] {
+        let targets = match resolve_targets(monitor, reference) {
+            Some(targets) => targets,
+            None => continue,
+        };
+        for target in targets {
+            if !add && target.group.as_deref() != Some(group) {
+                println!(" {} is not in @{}", describe_target(&target), group);
+                continue;
+            }
+            let new_group = add.then(|| group.to_string());
+            if monitor.set_group(target.id, new_group).is_ok() {
+                let verb = if add { "Added" } else { "Removed" };
+                let preposition = if add { "to" } else { "from" };
+                println!(
+                    " {} #{} {} {} @{}",
+                    verb, target.id, target.name, preposition, group
+                );
+            }
+        }
+    }
+}
+
+fn cmd_trigger(args: &[String]) {
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => m,
+        None => {
+            println!(" No monitor running");
+            println!(" Start monitoring first with 'monitor <PID>' command");
+            return;
+        }
+    };
+
+    match args.get(1).map(|s| s.as_str()) {
+        None | Some("list") => {
+            let targets = monitor.get_targets();
+            let triggers: Vec<_> = targets
+                .iter()
+                .flat_map(|t| t.triggers.iter().map(move |trigger| (t, trigger)))
+                .collect();
+            if triggers.is_empty() {
+                println!(" No triggers set");
+                println!(" Usage: trigger <#ID|ADDRESS> <CONDITION> [ACTION]");
+                println!(" Example: trigger test_target!score above 1000 pause");
+                return;
+            }
+            println!(" Triggers ({}):", triggers.len());
+            println!("{:-^70}", "");
+            for (target, trigger) in triggers {
+                println!(
+                    " #{} on {}: {} → {} ({} hits)",
+                    trigger.id,
+                    describe_target(target),
+                    trigger.condition,
+                    trigger.action,
+                    trigger.hits
+                );
+            }
+        }
+        Some("markers") => {
+            let markers = monitor.get_markers();
+            if markers.is_empty() {
+                println!(" No markers recorded");
+                println!(" Triggers with the 'mark' action record one each time they fire");
+                return;
+            }
+            println!(" Markers ({}):", markers.len());
+            println!("{:-^70}", "");
+            for marker in markers {
+                println!(
+                    " {:.1}s ago  #{} {} {}: {} → {}",
+                    marker.timestamp.elapsed().as_secs_f64(),
+                    marker.trigger_id,
+                    marker.name,
+                    marker.condition,
+                    format_watch_value(&marker.data_type, &marker.old_value),
+                    format_watch_value(&marker.data_type, &marker.new_value)
+                );
+            }
+        }
+        Some("remove") | Some("rm") => {
+            let id = match args
+                .get(2)
+                .and_then(|v| v.trim_start_matches('#').parse().ok())
+            {
+                Some(id) => id,
+                None => {
+                    println!(" Usage: trigger remove <ID>");
+                    return;
+                }
+            };
+            match monitor.remove_trigger(id) {
+                Some(trigger) => println!(" Removed trigger #{} ({})", id, trigger.condition),
+                None => println!(" No trigger #{}", id),
+            }
+        }
+        Some(reference) => {
+            let target = match resolve_target(monitor, reference) {
+                Some(target) => target,
+                None => return,
+            };
+            let words: Vec<&str> = args[2..].iter().map(|s| s.as_str()).collect();
+            let condition = match parse_condition(&words) {
+                Ok(condition) => condition,
+                Err(e) => {
+                    report_error("Invalid condition", &e);
+                    println!(" Conditions: above N, below N, equals X, changed-by N, rate K, nan");
+                    return;
+                }
+            };
+            let action_words = &words[words.len().min(if words[0] == "nan" { 1 } else { 2 })..];
+
+            let snapshot_address = match action_words {
+                ["snapshot", expr, ..] => match parse_address(expr, None, monitored_pid()) {
+                    Some(a) => Some(a),
+                    None => return,
+                },
+                _ => None,
+            };
+            let target_name = target.name.replace('/', "_");
+            let action = match parse_action(action_words, snapshot_address, &target_name) {
+                Ok(action) => action,
+                Err(e) => {
+                    report_error("Invalid action", &e);
+                    println!(" Actions: notify, pause, mark, snapshot <ADDRESS> <SIZE> [PREFIX]");
+                    return;
+                }
+            };
+
+            let description = format!("{} → {}", condition, action);
+            match monitor.add_trigger(target.id, condition, action) {
+                Ok(id) => {
+                    println!(
+                        " Trigger #{} set on #{} {}: {}",
+                        id, target.id, target.name, description
+                    );
+                }
+                Err(e) => report_error("Failed to set trigger", &e),
+            }
+        }
+    }
+}
+
+fn cmd_record(args: &[String]) {
+    let mut recorder_guard = RECORDER.lock().unwrap();
+
+    match args.get(1).map(|s| s.as_str()) {
+        None => match recorder_guard.as_ref() {
+            Some(recorder) => {
+                let state = match (recorder.is_active(), recorder.error()) {
+                    (true, _) => "recording".to_string(),
+                    (false, Some(e)) => format!("stopped: {}", e),
+                    (false, None) => "stopped with the monitor".to_string(),
+                };
+                println!(
+                    "⏺️  {} ({:?}): {} changes, {}",
+                    recorder.path().display(),
+                    recorder.format(),
+                    recorder.records(),
+                    state
+                );
+            }
+            None => {
+                println!(" Not recording");
+                println!(" Usage: record <FILE>   (JSON Lines, or CSV for *.csv)");
+                println!("        record stop");
+            }
+        },
+        Some("stop") | Some("off") => match recorder_guard.take() {
+            Some(recorder) => {
+                let path = recorder.path().display().to_string();
+                println!("⏹️  Recorded {} changes to {}", recorder.stop(), path);
+            }
+            None => println!(" Not recording"),
+        },
+        Some(file) => {
+            let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+            let monitor = match monitor_guard.as_ref() {
+                Some(m) if m.is_running() => m,
+                _ => {
+                    println!(" No monitor running");
+                    println!(" Start monitoring first with 'monitor <PID>' command");
                     return;
                 }
+            };
+            if let Some(previous) = recorder_guard.take() {
+                let path = previous.path().display().to_string();
+                println!(" Recorded {} changes to {}", previous.stop(), path);
+            }
+            match HistoryRecorder::start(monitor, Path::new(file)) {
+                Ok(recorder) => {
+                    println!(
+                        "⏺️  Recording changes to {} ({:?})",
+                        file,
+                        recorder.format()
+                    );
+                    println!(" Existing recordings are appended to, 'record stop' ends it");
+                    *recorder_guard = Some(recorder);
+                }
+                Err(e) => report_error("Failed to start recording", &e),
+            }
+        }
+    }
+}
+
+// "90", "90s", "5m", "1h" from the start of the recording, or a unix time
+fn parse_history_time(text: &str, start: std::time::SystemTime) -> Option<std::time::SystemTime> {
+    let (number, unit) = match text.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((i, _)) => text.split_at(i),
+        None => (text, "s"),
+    };
+    let seconds = number.parse::<f64>().ok().filter(|s| *s >= 0.0)?
+        * match unit {
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+    let offset = std::time::Duration::try_from_secs_f64(seconds).ok()?;
+    if unit == "s" && seconds > 1e9 {
+        return std::time::UNIX_EPOCH.checked_add(offset);
+    }
+    start.checked_add(offset)
+}
+
+fn format_clock(time: std::time::SystemTime) -> String {
+    let seconds = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as libc::time_t)
+        .unwrap_or(0);
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&seconds, &mut tm) }.is_null() {
+        return format!("{}", seconds);
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+fn print_history_record(record: &HistoryRecord, start: std::time::SystemTime) {
+    let offset = record
+        .time
+        .duration_since(start)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    println!(
+        " +{:>9.3}s  {} (0x{:x})  {} → {}",
+        offset,
+        record.name,
+        record.address,
+        format_watch_value(&record.data_type, &record.old_value),
+        format_watch_value(&record.data_type, &record.new_value)
+    );
+}
+
+// slower than this and a replay would mostly be sleeping
+const MIN_REPLAY_SPEED: f64 = 0.01;
+
+// history and replay: load, filter, then list or play back in real time
+fn cmd_history(args: &[String], replay: bool) {
+    if args.len() < 2 {
+        println!(" Usage: {} <FILE> [OPTIONS]", args[0]);
+        println!("   --target NAME|0xADDR   Only this target");
+        println!("   --from T  --to T       Time window: 90, 90s, 5m, 1h into the recording");
+        println!("   --value V              Changes from or to this value");
+        println!("   --limit N              Show at most N changes (history, default 50)");
+        if replay {
+            println!("   --speed X              Playback speed (default 1, 0 = no delays)");
+        }
+        return;
+    }
+
+    let records = match load_history(Path::new(&args[1])) {
+        Ok(records) => records,
+        Err(e) => {
+            report_error("Failed to load recording", &e);
+            return;
+        }
+    };
+    let start = match records.iter().map(|r| r.time).min() {
+        Some(start) => start,
+        None => {
+            println!(" {} holds no changes", args[1]);
+            return;
+        }
+    };
+    let end = records.iter().map(|r| r.time).max().unwrap_or(start);
+
+    let mut query = HistoryQuery::default();
+    let mut limit = if replay { usize::MAX } else { 50 };
+    let mut speed = 1.0;
+    let mut i = 2;
+    while i < args.len() {
+        let value = match args.get(i + 1) {
+            Some(value) => value,
+            None => {
+                println!(" {} needs a value", args[i]);
+                return;
+            }
+        };
+        let time = || {
+            let parsed = parse_history_time(value, start);
+            if parsed.is_none() {
+                println!(" Invalid time '{}', use 90, 90s, 5m or 1h", value);
+            }
+            parsed
+        };
+        match args[i].as_str() {
+            "--target" => query.target = Some(value.clone()),
+            "--value" => query.value = Some(value.clone()),
+            "--from" => match time() {
+                Some(t) => query.from = Some(t),
+                None => return,
+            },
+            "--to" => match time() {
+                Some(t) => query.to = Some(t),
+                None => return,
+            },
+            "--limit" => match value.parse() {
+                Ok(n) => limit = n,
                 Err(_) => {
-                    println!(" Invalid string size: {}", size_str);
+                    println!(" Invalid limit '{}'", value);
+                    return;
+                }
+            },
+            "--speed" if replay => match value.parse::<f64>() {
+                Ok(x) if x == 0.0 || x >= MIN_REPLAY_SPEED => speed = x,
+                _ => {
+                    println!(
+                        " Invalid speed '{}', use 0 or at least {}",
+                        value, MIN_REPLAY_SPEED
+                    );
                     return;
                 }
+            },
+            other => {
+                println!(" Unknown option '{}'", other);
+                return;
+            }
+        }
+        i += 2;
+    }
+
+    let matching: Vec<&HistoryRecord> = records.iter().filter(|r| query.matches(r)).collect();
+    let mut targets: Vec<(&str, usize)> = Vec::new();
+    for record in &matching {
+        match targets.iter_mut().find(|(name, _)| *name == record.name) {
+            Some((_, count)) => *count += 1,
+            None => targets.push((&record.name, 1)),
+        }
+    }
+
+    println!(
+        " Recording: {} ({} changes, {} to {}, {:.1}s)",
+        args[1],
+        records.len(),
+        format_clock(start),
+        format_clock(end),
+        end.duration_since(start).unwrap_or_default().as_secs_f64()
+    );
+    println!(" Matching: {} changes", matching.len());
+    for (name, count) in &targets {
+        println!("  📍 {}: {} changes", name, count);
+    }
+    println!("{:=^80}", "");
+
+    if !replay {
+        for record in matching.iter().take(limit) {
+            print_history_record(record, start);
+        }
+        if matching.len() > limit {
+            println!(
+                " ... and {} more (use --limit or narrow the query)",
+                matching.len() - limit
+            );
+        }
+        return;
+    }
+
+    // gaps longer than this are cut short, a recording can idle for hours
+    let max_gap = std::time::Duration::from_secs(5);
+    let mut previous: Option<std::time::SystemTime> = None;
+    for record in matching.iter().take(limit) {
+        if let Some(previous) = previous
+            && speed > 0.0
+        {
+            let gap = record.time.duration_since(previous).unwrap_or_default();
+            let wait = std::time::Duration::try_from_secs_f64(gap.as_secs_f64() / speed)
+                .unwrap_or(std::time::Duration::MAX);
+            if wait > max_gap {
+                println!(" ⏩ skipping {:.1}s of quiet", gap.as_secs_f64());
+            }
+            std::thread::sleep(wait.min(max_gap));
+        }
+        previous = Some(record.time);
+        print_history_record(record, start);
+        io::stdout().flush().unwrap();
+    }
+    println!("{:=^80}", "");
+    println!(" Replay finished");
+}
+
+fn cmd_show_changes(args: &[String]) {
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => m,
+        None => {
+            println!(" No monitor running");
+            return;
+        }
+    };
+
+    let count = if args.len() > 1 {
+        args[1].parse().unwrap_or(10)
+    } else {
+        10
+    };
+
+    let changes = monitor.get_recent_changes(count);
+
+    if changes.is_empty() {
+        println!(" No recent changes detected");
+        println!(" Changes will appear here when monitored values change");
+        return;
+    }
+
+    println!(" Recent Memory Changes (last {}):", changes.len());
+    println!("{:=^80}", "");
+
+    for (i, change) in changes.iter().enumerate() {
+        let elapsed = change.timestamp.elapsed();
+        println!(
+            " #{} {} (0x{:x}) - {:.2}s ago",
+            i + 1,
+            change.name,
+            change.address,
+            elapsed.as_secs_f64()
+        );
+
+        // Display based on the actual data type stored with the target
+        match change.data_type {
+            memscan::monitor::DataType::I32 => {
+                let old_i32 = i32::from_le_bytes(change.old_value[..4].try_into().unwrap());
+                let new_i32 = i32::from_le_bytes(change.new_value[..4].try_into().unwrap());
+                println!("    {} → {} (i32)", old_i32, new_i32);
+            }
+            memscan::monitor::DataType::I64 => {
+                let old_i64 = i64::from_le_bytes(change.old_value[..8].try_into().unwrap());
+                let new_i64 = i64::from_le_bytes(change.new_value[..8].try_into().unwrap());
+                println!("    {} → {} (i64)", old_i64, new_i64);
+            }
+            memscan::monitor::DataType::F32 => {
+                let old_f32 = f32::from_le_bytes(change.old_value[..4].try_into().unwrap());
+                let new_f32 = f32::from_le_bytes(change.new_value[..4].try_into().unwrap());
+                println!("    {:.3} → {:.3} (f32)", old_f32, new_f32);
+            }
+            memscan::monitor::DataType::F64 => {
+                let old_f64 = f64::from_le_bytes(change.old_value[..8].try_into().unwrap());
+                let new_f64 = f64::from_le_bytes(change.new_value[..8].try_into().unwrap());
+                println!("    {:.6} → {:.6} (f64)", old_f64, new_f64);
+            }
+            memscan::monitor::DataType::String(_) => {
+                // String or other data - show accurate string changes
+                display_string_change_cli(change);
+            }
+            memscan::monitor::DataType::Bytes(size) => println!(
+                "    {} → {} ({} bytes)",
+                hex::encode(&change.old_value),
+                hex::encode(&change.new_value),
+                size
+            ),
+        }
+        println!();
+    }
+}
+
+fn display_string_change_cli(change: &memscan::monitor::MonitorChange) {
+    // Find the actual null-terminated strings
+    let old_str_end = change
+        .old_value
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(change.old_value.len());
+    let new_str_end = change
+        .new_value
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(change.new_value.len());
+
+    let old_bytes = &change.old_value[..old_str_end];
+    let new_bytes = &change.new_value[..new_str_end];
+
+    // Try to create clean ASCII strings
+    let old_clean: String = old_bytes
+        .iter()
+        .filter(|&&b| b.is_ascii_graphic() || b == b' ')
+        .map(|&b| b as char)
+        .collect();
+    let new_clean: String = new_bytes
+        .iter()
+        .filter(|&&b| b.is_ascii_graphic() || b == b' ')
+        .map(|&b| b as char)
+        .collect();
+
+    // Show the change
+    if !old_clean.is_empty() || !new_clean.is_empty() {
+        println!("    '{}' → '{}'", old_clean, new_clean);
+
+        // Show byte lengths if different
+        if old_str_end != new_str_end {
+            println!("    Length: {} → {} bytes", old_str_end, new_str_end);
+        }
+    } else {
+        // Fallback to hex if no printable characters
+        println!("    {:02x?} → {:02x?}", old_bytes, new_bytes);
+    }
+}
+
+fn cmd_monitor_status() {
+    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let monitor = match monitor_guard.as_ref() {
+        Some(m) => m,
+        None => {
+            println!("⚫ No monitor initialized");
+            println!(" Start monitoring with 'monitor <PID>' command");
+            return;
+        }
+    };
+
+    if !monitor.is_running() {
+        match monitor.exit_event() {
+            Some(exit) => {
+                println!(
+                    "💀 Monitor stopped: PID {} exited {:.1}s ago",
+                    exit.pid,
+                    exit.timestamp.elapsed().as_secs_f64()
+                );
+                if exit.replaced {
+                    println!("  The PID now belongs to another program");
+                }
+                println!(" Use 'wait <NAME> --restore' to follow the next instance");
+            }
+            None => {
+                println!("⚪ Monitor exists but is not running");
+                println!(" Use 'monitor <PID>' to restart");
+            }
+        }
+        return;
+    }
+
+    let targets = monitor.get_targets_status();
+
+    println!("🔴 Monitor Status: ACTIVE");
+    println!("{:=^60}", "");
+    println!(" Monitored Targets: {}", targets.len());
+    println!(" Interval: {}ms", monitor.interval().as_millis());
+    print_loop_timing(monitor);
+    println!();
+
+    if targets.is_empty() {
+        println!("  Monitor is running but no targets are being watched");
+        println!(" No memory changes will be detected until you add targets.");
+        println!();
+        println!(" QUICK START OPTIONS:");
+        println!("  • 'auto-monitor <VALUE> <TYPE>' - Auto-find and watch addresses");
+        println!("  • 'watch <ADDRESS> <TYPE> <NAME>' - Watch specific address");
+        println!();
+        println!(" EXAMPLES:");
+        println!("  auto-monitor 100 i32           # Find all instances of 100");
+        println!("  watch 0x7fff12345678 i32 health # Watch specific address");
+        println!("  notifications on               # Enable auto-notifications");
+        println!();
+        println!(" Then changes will appear automatically");
+        return;
+    }
+
+    println!(" Monitoring Targets:");
+    println!("{:-^60}", "");
+
+    let mut targets = monitor.get_targets();
+    targets.sort_by_key(|t| t.address);
+    for target in targets {
+        let (address, name, change_count) = (target.address, &target.name, target.change_count);
+        let last_change_info = if let Some(timestamp) = target.last_changed {
+            let elapsed = timestamp.elapsed();
+            if elapsed.as_secs() < 60 {
+                format!("{:.1}s ago", elapsed.as_secs_f64())
+            } else {
+                format!("{}m ago", elapsed.as_secs() / 60)
             }
+        } else {
+            "Never".to_string()
+        };
+
+        let state = if target.enabled {
+            ""
+        } else {
+            " ⏸️  disabled"
+        };
+        println!(
+            " #{} {} (0x{:x}, {}){}",
+            target.id,
+            name,
+            address,
+            target_view_name(&target),
+            state
+        );
+        if let Some(group) = &target.group {
+            println!("    👥 @{}", group);
         }
-        _ => {
-            println!(" Invalid type: '{}'", args[2]);
-            println!(" Valid types: i32, i64, f32, f64, string, string:<size>");
-            return;
+        let pace = match target.interval {
+            Some(interval) => format!(" | ⏲️  every {}ms", interval.as_millis()),
+            None => String::new(),
+        };
+        println!(
+            "    {} changes | 🕒 Last: {}{}",
+            change_count, last_change_info, pace
+        );
+        match &target.range {
+            Some(range) => print_hot_spots(&target, range, 5),
+            None => print_target_stats(&target),
         }
-    };
+        println!();
+    }
+}
 
-    let name = args[3].to_string();
+// the offsets of a range target that changed most, with what they hold now
+fn print_hot_spots(target: &MonitorTarget, range: &RangeState, limit: usize) {
+    let Some(block) = &target.last_value else {
+        match &target.last_error {
+            Some(e) => println!("    ⚠️  {}", e),
+            None => println!("    No value read yet"),
+        }
+        return;
+    };
 
-    // Add target to monitor
-    monitor.add_target(address, data_type, name.clone());
-    println!(" Added '{}' to watch list", name);
-    println!("📍 Address: 0x{:x}", address);
-    println!(" Type: {}", args[2]);
-    println!("🔴 Live notifications active - changes will appear automatically");
+    let spots = range.hot_spots();
+    if spots.is_empty() {
+        println!("    🔥 Nothing in the block has changed yet");
+        return;
+    }
+    let unit = match &range.interpret {
+        Some(interpret) => format!("{} fields", data_type_name(interpret)),
+        None => "bytes".to_string(),
+    };
+    println!(
+        "    🔥 {} {} changed, most often:",
+        range.changed_offsets(),
+        unit
+    );
+    for spot in spots.iter().take(limit) {
+        let bytes = block
+            .get(spot.offset..spot.offset + spot.len)
+            .unwrap_or(&[]);
+        let now = match &range.interpret {
+            Some(interpret) => {
+                let size = value_size(interpret);
+                let values: Vec<String> = bytes
+                    .chunks(size)
+                    .take(4)
+                    .map(|field| format_value(interpret, field))
+                    .collect();
+                let more = if bytes.len() > 4 * size { ", ..." } else { "" };
+                format!("{}{}", values.join(", "), more)
+            }
+            None if bytes.len() > 16 => format!("{}...", hex::encode(&bytes[..16])),
+            None => hex::encode(bytes),
+        };
+        println!(
+            "      +0x{:<5x} {:>3} bytes  {:>5} changes  now {}",
+            spot.offset, spot.len, spot.changes, now
+        );
+    }
+    if spots.len() > limit {
+        println!("      (+{} more)", spots.len() - limit);
+    }
 }
 
-fn cmd_remove_watch(args: &[String]) {
+fn cmd_hot_spots(args: &[String]) {
     if args.len() < 2 {
-        println!(" Usage: unwatch <address>");
-        println!(" Example: unwatch 0x7fff12345678");
+        println!(" Usage: hotspots <#ID|ADDRESS|@GROUP> [COUNT]");
+        println!(" Shows which offsets of a bytes:<SIZE> range change most often");
         return;
     }
+    let limit = match args.get(2).map(|n| n.parse::<usize>()) {
+        None => 20,
+        Some(Ok(n)) if n > 0 => n,
+        Some(_) => {
+            println!(" Invalid count: '{}'", args[2]);
+            return;
+        }
+    };
 
     let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
     let monitor = match monitor_guard.as_ref() {
@@ -1065,205 +3621,665 @@ fn cmd_remove_watch(args: &[String]) {
         }
     };
 
-    let address_str = args[1].trim_start_matches("0x");
-    let address = match usize::from_str_radix(address_str, 16) {
-        Ok(addr) => addr,
-        Err(_) => {
-            println!(" Invalid address: {}", args[1]);
-            return;
-        }
+    let targets = match resolve_targets(monitor, &args[1]) {
+        Some(targets) => targets,
+        None => return,
     };
+    let ranges: Vec<_> = targets.iter().filter(|t| t.range.is_some()).collect();
+    if ranges.is_empty() {
+        println!(" '{}' is not a range, watch one as bytes:<SIZE>", args[1]);
+        return;
+    }
+    for target in ranges {
+        println!(
+            " {} - {} changes",
+            describe_target(target),
+            target.change_count
+        );
+        if let Some(range) = &target.range {
+            print_hot_spots(target, range, limit);
+        }
+    }
+}
 
-    monitor.remove_target(address);
-    println!(" Removed address 0x{:x} from watch list", address);
+// 'watchpoint <ADDRESS> ...' attaches a tracer to the monitored process the
+// first time, 'detach' (or stopping the monitor) lets go of it again
+fn cmd_watchpoint(args: &[String]) {
+    match args.get(1).map(|s| s.as_str()) {
+        None | Some("list") => list_watchpoints(),
+        Some("remove") | Some("rm") => match args.get(2) {
+            Some(id) => remove_watchpoint(id),
+            None => println!(" Usage: watchpoint remove <ID>"),
+        },
+        Some(_) => add_watchpoint(&args[1..]),
+    }
 }
 
-fn cmd_show_changes(args: &[String]) {
-    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-    let monitor = match monitor_guard.as_ref() {
-        Some(m) => m,
+fn add_watchpoint(args: &[String]) {
+    let handle = match GLOBAL_PROCESS_HANDLE.lock().unwrap().clone() {
+        Some(handle) => handle,
         None => {
             println!(" No monitor running");
+            println!(" Start monitoring first with 'monitor <PID>'");
             return;
         }
     };
 
-    let count = if args.len() > 1 {
-        args[1].parse().unwrap_or(10)
-    } else {
-        10
+    let mut kind = WatchKind::Write;
+    let mut size = None;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "write" | "w" => kind = WatchKind::Write,
+            "access" | "rw" => kind = WatchKind::Access,
+            other => size = Some(other),
+        }
+    }
+
+    // a size watches raw bytes, a type also says how hits show the value.
+    // without either the variable's debug info decides
+    let (address, len, data_type) = match size {
+        Some(size) => {
+            let address = match resolve_address(&args[0], Some(handle.as_ref()), Some(handle.pid()))
+            {
+                Ok(address) => address,
+                Err(e) => {
+                    report_error("Invalid address", &e);
+                    return;
+                }
+            };
+            match (size.parse::<usize>(), parse_data_type(size)) {
+                (Ok(len), _) => (address, len, None),
+                (
+                    _,
+                    Ok(data_type @ (DataType::I32 | DataType::I64 | DataType::F32 | DataType::F64)),
+                ) => (address, value_size(&data_type), Some(data_type)),
+                _ => {
+                    println!(" Invalid size: '{}'", size);
+                    println!(" Use 1, 2, 4, 8 or a type: i32, i64, f32, f64");
+                    return;
+                }
+            }
+        }
+        None => match lookup_variable(handle.as_ref(), &args[0]) {
+            Some(variable) => match variable.ty.data_type() {
+                Some(data_type) if value_size(&data_type) <= 8 => {
+                    (variable.address, value_size(&data_type), Some(data_type))
+                }
+                _ => {
+                    println!(
+                        " '{}' is a {} ({} bytes), watch one of its fields",
+                        args[0], variable.ty.name, variable.ty.size
+                    );
+                    return;
+                }
+            },
+            None => {
+                println!(" No debug info for '{}', give a type or size", args[0]);
+                println!(" Usage: watchpoint <ADDRESS> [TYPE|SIZE] [write|access]");
+                return;
+            }
+        },
     };
 
-    let changes = monitor.get_recent_changes(count);
+    if held_by_spawn(handle.pid()) {
+        return;
+    }
+    let mut tracer_guard = TRACER.lock().unwrap();
+    let Some(tracer) = attach_tracer(&mut tracer_guard, &handle) else {
+        return;
+    };
 
-    if changes.is_empty() {
-        println!(" No recent changes detected");
-        println!(" Changes will appear here when monitored values change");
+    match tracer.add_watchpoint(address, len, kind) {
+        Ok(id) => {
+            if let Some(data_type) = data_type {
+                WATCHPOINT_TYPES.lock().unwrap().insert(id, data_type);
+            }
+            println!(
+                " 🧷 Watchpoint #{} on {} ({} bytes, {})",
+                id,
+                describe_address(handle.as_ref(), address),
+                len,
+                kind
+            );
+            println!(
+                " Every hit shows the thread and the instruction, 'watchpoint list' sums them up"
+            );
+        }
+        Err(e) => report_error("Failed to set watchpoint", &e),
+    }
+}
+
+// a process spawn still holds at its entry is traced by this thread, the
+// tracer can't take it over until 'continue' lets it go
+fn held_by_spawn(pid: u32) -> bool {
+    let held = GLOBAL_SPAWNED
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|s| s.pid() == pid && s.is_stopped());
+    if held {
+        println!(" PID {} is still held at its entry point", pid);
+        println!(" Run 'continue' first, then set watchpoints and breakpoints");
+    }
+    held
+}
+
+// the tracer on the monitored process, attached on first use
+fn attach_tracer<'a>(
+    tracer: &'a mut Option<Tracer>,
+    handle: &Arc<ProcessHandle>,
+) -> Option<&'a Tracer> {
+    // a tracer left over from another process, or one whose process exited
+    if tracer
+        .as_ref()
+        .is_some_and(|t| t.pid() != handle.pid() || !t.is_attached())
+    {
+        *tracer = None;
+        WATCHPOINT_TYPES.lock().unwrap().clear();
+    }
+    if tracer.is_none() {
+        match Tracer::attach(Arc::clone(handle)) {
+            Ok(attached) => {
+                let events = attached.subscribe();
+                let traced = Arc::clone(handle);
+                std::thread::spawn(move || print_trace_events(events, traced));
+                println!(" Tracing PID {}", handle.pid());
+                *tracer = Some(attached);
+            }
+            Err(e) => {
+                report_error(&format!("Failed to trace PID {}", handle.pid()), &e);
+                return None;
+            }
+        }
+    }
+    tracer.as_ref()
+}
+
+fn remove_watchpoint(id: &str) {
+    let id = match id.trim_start_matches('#').parse::<usize>() {
+        Ok(id) => id,
+        Err(_) => {
+            println!(" Invalid watchpoint ID: '{}'", id);
+            return;
+        }
+    };
+    let tracer_guard = TRACER.lock().unwrap();
+    let Some(tracer) = tracer_guard.as_ref() else {
+        println!(" No watchpoints set");
         return;
+    };
+    match tracer.remove_watchpoint(id) {
+        Ok(watchpoint) => {
+            WATCHPOINT_TYPES.lock().unwrap().remove(&id);
+            println!(
+                " Removed watchpoint #{} on 0x{:x} ({} hits)",
+                id, watchpoint.address, watchpoint.hits
+            );
+        }
+        Err(e) => report_error("Failed to remove watchpoint", &e),
     }
+}
 
-    println!(" Recent Memory Changes (last {}):", changes.len());
-    println!("{:=^80}", "");
+// "find what writes": the instructions that hit each watchpoint, most first
+fn list_watchpoints() {
+    let tracer_guard = TRACER.lock().unwrap();
+    let watchpoints = tracer_guard
+        .as_ref()
+        .map(|t| t.watchpoints())
+        .unwrap_or_default();
+    if watchpoints.is_empty() {
+        println!(" No watchpoints set");
+        println!(" Usage: watchpoint <ADDRESS> [TYPE|SIZE] [write|access]");
+        return;
+    }
 
-    for (i, change) in changes.iter().enumerate() {
-        let elapsed = change.timestamp.elapsed();
+    let handle = GLOBAL_PROCESS_HANDLE.lock().unwrap().clone();
+    let symbolizer = handle
+        .as_ref()
+        .and_then(|h| Symbolizer::cached(h.as_ref()).ok());
+    let describe = |address: usize| match symbolizer.as_ref().and_then(|s| s.describe(address)) {
+        Some(symbol) => format!("0x{:x} ({})", address, symbol),
+        None => format!("0x{:x}", address),
+    };
+
+    if let Some(tracer) = tracer_guard.as_ref() {
         println!(
-            " #{} {} (0x{:x}) - {:.2}s ago",
-            i + 1,
-            change.name,
-            change.address,
-            elapsed.as_secs_f64()
+            " Watchpoints on PID {} ({}/{} debug registers)",
+            tracer.pid(),
+            watchpoints.len(),
+            MAX_WATCHPOINTS
+        );
+    }
+    println!("{:-<70}", "");
+    for watchpoint in &watchpoints {
+        println!(
+            " #{} {} ({} bytes, {}) - {} hits",
+            watchpoint.id,
+            describe(watchpoint.address),
+            watchpoint.len,
+            watchpoint.kind,
+            watchpoint.hits
         );
+        let mut sites: Vec<(usize, u64)> = watchpoint
+            .sites
+            .iter()
+            .map(|(&rip, &count)| (rip, count))
+            .collect();
+        sites.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        for &(rip, count) in sites.iter().take(10) {
+            println!("    {:>6}x  {}", count, describe(rip));
+        }
+        if sites.len() > 10 {
+            println!("    (+{} more)", sites.len() - 10);
+        }
+    }
+    println!(" Locations are the instruction right after the access");
+}
 
-        // Display based on the actual data type stored with the target
-        match change.data_type {
-            memscan::monitor::DataType::I32 => {
-                let old_i32 = i32::from_le_bytes(change.old_value[..4].try_into().unwrap());
-                let new_i32 = i32::from_le_bytes(change.new_value[..4].try_into().unwrap());
-                println!("    {} → {} (i32)", old_i32, new_i32);
-            }
-            memscan::monitor::DataType::I64 => {
-                let old_i64 = i64::from_le_bytes(change.old_value[..8].try_into().unwrap());
-                let new_i64 = i64::from_le_bytes(change.new_value[..8].try_into().unwrap());
-                println!("    {} → {} (i64)", old_i64, new_i64);
-            }
-            memscan::monitor::DataType::F32 => {
-                let old_f32 = f32::from_le_bytes(change.old_value[..4].try_into().unwrap());
-                let new_f32 = f32::from_le_bytes(change.new_value[..4].try_into().unwrap());
-                println!("    {:.3} → {:.3} (f32)", old_f32, new_f32);
-            }
-            memscan::monitor::DataType::F64 => {
-                let old_f64 = f64::from_le_bytes(change.old_value[..8].try_into().unwrap());
-                let new_f64 = f64::from_le_bytes(change.new_value[..8].try_into().unwrap());
-                println!("    {:.6} → {:.6} (f64)", old_f64, new_f64);
+// 'breakpoint <ADDRESS> [--continue]' shares the tracer with the watchpoints
+fn cmd_breakpoint(args: &[String]) {
+    match args.get(1).map(|s| s.as_str()) {
+        None | Some("list") => list_breakpoints(),
+        Some("remove") | Some("rm") => match args.get(2) {
+            Some(id) => remove_breakpoint(id),
+            None => println!(" Usage: breakpoint remove <ID>"),
+        },
+        Some("regs") => match args.get(2) {
+            Some(id) => show_breakpoint_registers(id),
+            None => println!(" Usage: breakpoint regs <ID>"),
+        },
+        Some(_) => add_breakpoint(&args[1..]),
+    }
+}
+
+fn add_breakpoint(args: &[String]) {
+    let handle = match GLOBAL_PROCESS_HANDLE.lock().unwrap().clone() {
+        Some(handle) => handle,
+        None => {
+            println!(" No monitor running");
+            println!(" Start monitoring first with 'monitor <PID>'");
+            return;
+        }
+    };
+
+    let mut auto_continue = false;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--continue" | "-c" => auto_continue = true,
+            other => {
+                println!(" Unknown option: '{}'", other);
+                println!(" Usage: breakpoint <ADDRESS> [--continue]");
+                return;
             }
-            memscan::monitor::DataType::String(_) => {
-                // String or other data - show accurate string changes
-                display_string_change_cli(change);
+        }
+    }
+
+    let address = match resolve_address(&args[0], Some(handle.as_ref()), Some(handle.pid())) {
+        Ok(address) => address,
+        Err(e) => {
+            report_error("Invalid address", &e);
+            return;
+        }
+    };
+
+    if held_by_spawn(handle.pid()) {
+        return;
+    }
+    let mut tracer_guard = TRACER.lock().unwrap();
+    let Some(tracer) = attach_tracer(&mut tracer_guard, &handle) else {
+        return;
+    };
+
+    match tracer.add_breakpoint(address, auto_continue) {
+        Ok(id) => {
+            println!(
+                " 🛑 Breakpoint #{} at {}",
+                id,
+                describe_address(handle.as_ref(), address)
+            );
+            if auto_continue {
+                println!(" Hits are counted and shown, the process keeps running");
+            } else {
+                println!(" A hit stops the process, 'continue' steps over it");
             }
         }
-        println!();
+        Err(e) => report_error("Failed to set breakpoint", &e),
     }
 }
 
-fn display_string_change_cli(change: &memscan::monitor::MonitorChange) {
-    // Find the actual null-terminated strings
-    let old_str_end = change
-        .old_value
-        .iter()
-        .position(|&b| b == 0)
-        .unwrap_or(change.old_value.len());
-    let new_str_end = change
-        .new_value
-        .iter()
-        .position(|&b| b == 0)
-        .unwrap_or(change.new_value.len());
+fn parse_breakpoint_id(id: &str) -> Option<usize> {
+    match id.trim_start_matches('#').parse::<usize>() {
+        Ok(id) => Some(id),
+        Err(_) => {
+            println!(" Invalid breakpoint ID: '{}'", id);
+            None
+        }
+    }
+}
+
+fn remove_breakpoint(id: &str) {
+    let Some(id) = parse_breakpoint_id(id) else {
+        return;
+    };
+    let tracer_guard = TRACER.lock().unwrap();
+    let Some(tracer) = tracer_guard.as_ref() else {
+        println!(" No breakpoints set");
+        return;
+    };
+    match tracer.remove_breakpoint(id) {
+        Ok(breakpoint) => println!(
+            " Removed breakpoint #{} at 0x{:x} ({} hits), original code restored",
+            id, breakpoint.address, breakpoint.hits
+        ),
+        Err(e) => report_error("Failed to remove breakpoint", &e),
+    }
+}
+
+fn list_breakpoints() {
+    let tracer_guard = TRACER.lock().unwrap();
+    let breakpoints = tracer_guard
+        .as_ref()
+        .map(|t| t.breakpoints())
+        .unwrap_or_default();
+    if breakpoints.is_empty() {
+        println!(" No breakpoints set");
+        println!(" Usage: breakpoint <ADDRESS> [--continue]");
+        return;
+    }
+
+    let handle = GLOBAL_PROCESS_HANDLE.lock().unwrap().clone();
+    if let Some(tracer) = tracer_guard.as_ref() {
+        let state = if tracer.is_paused() {
+            "stopped at a breakpoint"
+        } else {
+            "running"
+        };
+        println!(" Breakpoints on PID {} ({})", tracer.pid(), state);
+    }
+    println!("{:-<70}", "");
+    for breakpoint in &breakpoints {
+        let location = match &handle {
+            Some(handle) => describe_address(handle.as_ref(), breakpoint.address),
+            None => format!("0x{:x}", breakpoint.address),
+        };
+        let mode = if breakpoint.auto_continue {
+            ", continue"
+        } else {
+            ""
+        };
+        println!(
+            " #{} {}{} - {} hits",
+            breakpoint.id, location, mode, breakpoint.hits
+        );
+        if !breakpoint.threads.is_empty() {
+            let threads: Vec<String> = breakpoint
+                .threads
+                .iter()
+                .map(|(tid, hits)| format!("{} ({}x)", tid, hits))
+                .collect();
+            println!("    🧵 {}", threads.join(", "));
+        }
+        if let Some(registers) = &breakpoint.last_registers {
+            println!("    🧮 last: {}", format_arguments(registers));
+        }
+    }
+}
 
-    let old_bytes = &change.old_value[..old_str_end];
-    let new_bytes = &change.new_value[..new_str_end];
+fn show_breakpoint_registers(id: &str) {
+    let Some(id) = parse_breakpoint_id(id) else {
+        return;
+    };
+    let breakpoints = TRACER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|t| t.breakpoints())
+        .unwrap_or_default();
+    match breakpoints.iter().find(|b| b.id == id) {
+        Some(breakpoint) => match &breakpoint.last_registers {
+            Some(registers) => {
+                println!(" Registers at the last hit of breakpoint #{}:", id);
+                print_registers(registers);
+            }
+            None => println!(" Breakpoint #{} hasn't been hit yet", id),
+        },
+        None => println!(" No breakpoint #{}", id),
+    }
+}
 
-    // Try to create clean ASCII strings
-    let old_clean: String = old_bytes
-        .iter()
-        .filter(|&&b| b.is_ascii_graphic() || b == b' ')
-        .map(|&b| b as char)
-        .collect();
-    let new_clean: String = new_bytes
+// the first six integer arguments in the System V calling convention
+const ARGUMENT_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+fn format_arguments(registers: &Registers) -> String {
+    ARGUMENT_REGISTERS
         .iter()
-        .filter(|&&b| b.is_ascii_graphic() || b == b' ')
-        .map(|&b| b as char)
-        .collect();
+        .filter_map(|name| registers.get(name).map(|v| format!("{}=0x{:x}", name, v)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-    // Show the change
-    if !old_clean.is_empty() || !new_clean.is_empty() {
-        println!("    '{}' → '{}'", old_clean, new_clean);
+fn print_breakpoint_hit(hit: &BreakpointHit, symbolizer: Option<&Symbolizer>) {
+    let location = describe_with(symbolizer, hit.address);
+    println!(
+        "\n🛑 BREAKPOINT #{} (hit {}): TID {} at {}",
+        hit.breakpoint_id, hit.hits, hit.tid, location
+    );
+    println!("   🧮 {}", format_arguments(&hit.registers));
+    if hit.stopped {
+        print_registers(&hit.registers);
+        println!("   Stopped, 'continue' steps over it and runs on");
+    }
+}
 
-        // Show byte lengths if different
-        if old_str_end != new_str_end {
-            println!("    Length: {} → {} bytes", old_str_end, new_str_end);
+fn cmd_detach() {
+    match TRACER.lock().unwrap().take() {
+        Some(tracer) => {
+            let pid = tracer.pid();
+            let (watchpoints, breakpoints) = tracer.detach();
+            WATCHPOINT_TYPES.lock().unwrap().clear();
+            println!(
+                " Detached from PID {}, {} watchpoints cleared, {} breakpoints restored",
+                pid,
+                watchpoints.len(),
+                breakpoints.len()
+            );
         }
-    } else {
-        // Fallback to hex if no printable characters
-        println!("    {:02x?} → {:02x?}", old_bytes, new_bytes);
+        None => println!(" Not tracing any process"),
     }
 }
 
-fn cmd_monitor_status() {
-    let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-    let monitor = match monitor_guard.as_ref() {
-        Some(m) => m,
+// hits are configured on purpose, so shown even with notifications off
+fn print_trace_events(events: Receiver<TraceEvent>, handle: Arc<ProcessHandle>) {
+    // hits can come in fast, symbolize them all with one module list
+    let mut symbolizer = Symbolizer::cached(handle.as_ref()).ok();
+    for event in events {
+        match event {
+            TraceEvent::WatchpointHit(hit) => {
+                if symbolizer.is_none() {
+                    symbolizer = Symbolizer::cached(handle.as_ref()).ok();
+                }
+                print_watchpoint_hit(&hit, symbolizer.as_deref())
+            }
+            TraceEvent::BreakpointHit(hit) => {
+                if symbolizer.is_none() {
+                    symbolizer = Symbolizer::cached(handle.as_ref()).ok();
+                }
+                print_breakpoint_hit(&hit, symbolizer.as_deref())
+            }
+            TraceEvent::ProcessExited { pid } => {
+                println!("\n🧷 PID {} exited, its watchpoints are gone", pid)
+            }
+            TraceEvent::Exec {
+                pid,
+                breakpoints,
+                watchpoints,
+            } => {
+                println!(
+                    "\n🧷 PID {} started a new program, {} breakpoints and {} watchpoints dropped",
+                    pid, breakpoints, watchpoints
+                );
+                println!(" Addresses from the old program are meaningless now, set them again");
+                WATCHPOINT_TYPES.lock().unwrap().clear();
+                // the new program's modules, looked up at the next hit once
+                // it has loaded its libraries
+                symbolizer = None;
+            }
+            TraceEvent::Detached => continue,
+        }
+        print!("{}", prompt());
+        io::stdout().flush().unwrap();
+    }
+}
+
+fn print_watchpoint_hit(hit: &WatchpointHit, symbolizer: Option<&Symbolizer>) {
+    let location = describe_with(symbolizer, hit.rip);
+    let data_type = WATCHPOINT_TYPES
+        .lock()
+        .unwrap()
+        .get(&hit.watchpoint_id)
+        .cloned();
+    let value = |bytes: &[u8]| match &data_type {
+        Some(data_type) => format_value(data_type, bytes),
         None => {
-            println!("⚫ No monitor initialized");
-            println!(" Start monitoring with 'monitor <PID>' command");
-            return;
+            let mut raw = [0u8; 8];
+            let len = bytes.len().min(8);
+            raw[..len].copy_from_slice(&bytes[..len]);
+            let value = u64::from_le_bytes(raw);
+            format!("{} (0x{:x})", value, value)
         }
     };
 
-    if !monitor.is_running() {
-        println!("⚪ Monitor exists but is not running");
-        println!(" Use 'monitor <PID>' to restart");
-        return;
+    println!(
+        "\n🧷 WATCHPOINT #{}: TID {} at {}",
+        hit.watchpoint_id, hit.tid, location
+    );
+    if hit.old_value == hit.new_value {
+        println!(
+            "   📊 0x{:x} = {} ({}, unchanged)",
+            hit.address,
+            value(&hit.new_value),
+            hit.kind
+        );
+    } else {
+        println!(
+            "   📊 0x{:x}: {} → {}",
+            hit.address,
+            value(&hit.old_value),
+            value(&hit.new_value)
+        );
     }
+}
 
-    let targets = monitor.get_targets_status();
-    let memory_usage = get_allocated_bytes() as f64 / 1024.0 / 1024.0;
-
-    println!("🔴 Monitor Status: ACTIVE");
-    println!("{:=^60}", "");
-    println!(" Monitored Targets: {}", targets.len());
-    println!();
-
-    if targets.is_empty() {
-        println!("  Monitor is running but no targets are being watched");
-        println!(" No memory changes will be detected until you add targets.");
-        println!();
-        println!(" QUICK START OPTIONS:");
-        println!("  • 'auto-monitor <VALUE> <TYPE>' - Auto-find and watch addresses");
-        println!("  • 'watch <ADDRESS> <TYPE> <NAME>' - Watch specific address");
-        println!();
-        println!(" EXAMPLES:");
-        println!("  auto-monitor 100 i32           # Find all instances of 100");
-        println!("  watch 0x7fff12345678 i32 health # Watch specific address");
-        println!("  notifications on               # Enable auto-notifications");
-        println!();
-        println!(" Then changes will appear automatically");
+fn print_target_stats(target: &MonitorTarget) {
+    let stats = &target.stats;
+    if stats.samples == 0 {
+        match &target.last_error {
+            Some(e) => println!("    ⚠️  {}", e),
+            None => println!("    No value read yet"),
+        }
         return;
     }
 
-    println!(" Monitoring Targets:");
-    println!("{:-^60}", "");
+    if let Some(numeric) = &stats.numeric {
+        println!(
+            "    📈 min {} | max {} | mean {:.3} | stddev {:.3}",
+            numeric.min,
+            numeric.max,
+            numeric.mean,
+            numeric.stddev()
+        );
+    }
 
-    for (address, name, change_count, last_changed) in targets {
-        let last_change_info = if let Some(timestamp) = last_changed {
-            let elapsed = timestamp.elapsed();
-            if elapsed.as_secs() < 60 {
-                format!("{:.1}s ago", elapsed.as_secs_f64())
-            } else {
-                format!("{}m ago", elapsed.as_secs() / 60)
-            }
-        } else {
-            "Never".to_string()
+    let rates: Vec<String> = RATE_WINDOWS
+        .iter()
+        .map(|window| {
+            let label = match window.as_secs() {
+                s if s >= 60 => format!("{}m", s / 60),
+                s => format!("{}s", s),
+            };
+            format!("{:.2}/s ({})", stats.change_rate(*window), label)
+        })
+        .collect();
+    println!("    ⚡ {}", rates.join(" | "));
+
+    let held = stats.time_in_value();
+    let total: f64 = held.iter().map(|(_, d)| d.as_secs_f64()).sum();
+    if total > 0.0 {
+        let top: Vec<String> = held
+            .iter()
+            .take(3)
+            .map(|(value, duration)| {
+                format!(
+                    "{} {:.0}%",
+                    quote_if_string(&target.data_type, value),
+                    duration.as_secs_f64() / total * 100.0
+                )
+            })
+            .collect();
+        let more = match held.len().saturating_sub(3) {
+            0 => String::new(),
+            n => format!(" (+{} more)", n),
         };
+        println!("    ⏱️  Time in value: {}{}", top.join(" | "), more);
+    }
 
-        println!(" {} (0x{:x})", name, address);
+    if let DataType::String(_) = target.data_type {
+        let distinct = stats.distinct_values();
+        let shown: Vec<String> = distinct
+            .iter()
+            .take(8)
+            .map(|v| format!("'{}'", v))
+            .collect();
+        let more = match distinct.len().saturating_sub(8) {
+            0 => String::new(),
+            n => format!(", +{} more", n),
+        };
         println!(
-            "    {} changes | 🕒 Last: {}",
-            change_count, last_change_info
+            "    🔤 {} distinct: {}{}",
+            distinct.len(),
+            shown.join(", "),
+            more
         );
-        println!();
+    }
+}
+
+fn quote_if_string(data_type: &DataType, value: &str) -> String {
+    match data_type {
+        DataType::String(_) => format!("'{}'", value),
+        _ => value.to_string(),
     }
 }
 
 fn cmd_stop_monitor() {
+    // watchpoints belong to the monitored process
+    if TRACER.lock().unwrap().is_some() {
+        cmd_detach();
+    }
+
     let mut monitor_guard = GLOBAL_MONITOR.lock().unwrap();
+    let recorder = RECORDER.lock().unwrap().take();
 
     if let Some(ref monitor) = *monitor_guard {
         monitor.stop_monitoring();
+        LIVE_NOTIFICATIONS.store(false, Ordering::Relaxed);
         println!("  Monitor stopped");
+
+        let targets = monitor.get_targets();
+        if !targets.is_empty() {
+            *LAST_WATCH_LIST.lock().unwrap() = Some(SavedWatchList {
+                image: GLOBAL_MONITORED_IMAGE.lock().unwrap().take(),
+                targets,
+            });
+        }
     }
 
     *monitor_guard = None;
 
+    if let Some(recorder) = recorder {
+        let path = recorder.path().display().to_string();
+        println!(" Recorded {} changes to {}", recorder.stop(), path);
+    }
+
     // Clear process handle too
     {
         let mut handle_guard = GLOBAL_PROCESS_HANDLE.lock().unwrap();
@@ -1281,7 +4297,7 @@ fn cmd_list_targets() {
         }
     };
 
-    let targets = monitor.get_targets_status();
+    let targets = monitor.get_targets();
 
     if targets.is_empty() {
         println!(" No targets are being monitored");
@@ -1292,8 +4308,14 @@ fn cmd_list_targets() {
     println!(" Monitored Targets ({}):", targets.len());
     println!("{:-^70}", "");
 
-    for (address, name, change_count, _) in targets {
-        println!("📍 {} at 0x{:x} ({} changes)", name, address, change_count);
+    for target in targets {
+        let state = if target.enabled { "" } else { ", disabled" };
+        println!(
+            "📍 {} ({} changes{})",
+            describe_target(&target),
+            target.change_count,
+            state
+        );
     }
 }
 
@@ -1331,11 +4353,11 @@ fn cmd_auto_scan_and_monitor(args: &[String]) {
 
     // Perform scan using the monitored process handle
     let scan_result = match data_type.as_str() {
-        "i32" => scan_process_for_i32(&handle, value_str),
-        "i64" => scan_process_for_i64(&handle, value_str),
-        "f32" => scan_process_for_f32(&handle, value_str),
-        "f64" => scan_process_for_f64(&handle, value_str),
-        "string" => scan_process_for_string(&handle, value_str),
+        "i32" => scan_process_for_i32(handle.as_ref(), value_str),
+        "i64" => scan_process_for_i64(handle.as_ref(), value_str),
+        "f32" => scan_process_for_f32(handle.as_ref(), value_str),
+        "f64" => scan_process_for_f64(handle.as_ref(), value_str),
+        "string" => scan_process_for_string(handle.as_ref(), value_str),
         _ => {
             println!(" Invalid type: {}", data_type);
             println!(" Valid types: i32, i64, f32, f64, string");
@@ -1388,7 +4410,7 @@ fn cmd_auto_scan_and_monitor(args: &[String]) {
                     println!(" Auto-monitoring setup complete");
 
                     // Automatically enable notifications for better UX
-                    monitor.enable_live_notifications();
+                    LIVE_NOTIFICATIONS.store(true, Ordering::Relaxed);
 
                     println!(" Use 'status' to see monitoring overview");
                     println!(" Use 'notifications off' to disable auto-notifications");
@@ -1396,7 +4418,7 @@ fn cmd_auto_scan_and_monitor(args: &[String]) {
             }
         }
         Err(e) => {
-            println!(" Scan failed: {}", e);
+            report_error("Scan failed", &e);
         }
     }
 }
@@ -1511,7 +4533,7 @@ fn cmd_live_monitor() {
 
 fn cmd_toggle_notifications(args: &[String]) {
     let monitor_guard = GLOBAL_MONITOR.lock().unwrap();
-    let monitor = match monitor_guard.as_ref() {
+    let _monitor = match monitor_guard.as_ref() {
         Some(m) => {
             if !m.is_running() {
                 println!(" Monitor is not running");
@@ -1529,7 +4551,11 @@ fn cmd_toggle_notifications(args: &[String]) {
 
     if args.len() < 2 {
         // Show current status
-        let status = if monitor.is_live_mode() { "ON" } else { "OFF" };
+        let status = if LIVE_NOTIFICATIONS.load(Ordering::Relaxed) {
+            "ON"
+        } else {
+            "OFF"
+        };
         println!(" Live notifications are currently: {}", status);
         println!(" Usage: notifications <on|off>");
         return;
@@ -1537,10 +4563,11 @@ fn cmd_toggle_notifications(args: &[String]) {
 
     match args[1].to_lowercase().as_str() {
         "on" | "enable" | "true" | "1" => {
-            monitor.enable_live_notifications();
+            LIVE_NOTIFICATIONS.store(true, Ordering::Relaxed);
+            println!("🔴 Live notifications enabled - changes will appear automatically");
         }
         "off" | "disable" | "false" | "0" => {
-            monitor.disable_live_notifications();
+            LIVE_NOTIFICATIONS.store(false, Ordering::Relaxed);
             println!("⚫ Live notifications disabled");
             println!(" Use 'changes' command to view changes manually");
         }
@@ -1568,36 +4595,111 @@ fn cmd_set_interval(args: &[String]) {
         }
     };
 
-    if args.len() < 2 {
-        println!(" Usage: interval <milliseconds>");
-        println!(" Example: interval 50  # 50ms updates");
-        println!(" Current interval: Check with 'status'");
-        println!("  Note: Requires restarting monitor to take effect");
-        return;
-    }
-
-    match args[1].parse::<u64>() {
-        Ok(ms) if ms >= 10 && ms <= 5000 => {
-            println!(" Interval set to {}ms", ms);
-            println!("  Restart monitoring for changes to take effect");
-            if ms < 100 {
-                println!(" Fast intervals may cause race conditions");
-                println!(" You might see partial/garbled string changes");
-                println!(" Try 200ms+ if you see strange results");
-            } else {
-                println!(" Good interval for stable monitoring");
+    match args.len() {
+        1 => {
+            println!(" Interval: {}ms", monitor.interval().as_millis());
+            for target in monitor.get_targets() {
+                if let Some(interval) = target.interval {
+                    println!(
+                        "  ⏲️  #{} {} (0x{:x}): every {}ms",
+                        target.id,
+                        target.name,
+                        target.address,
+                        interval.as_millis()
+                    );
+                }
             }
+            print_loop_timing(monitor);
+            println!();
+            println!(" Usage: interval <ms>                      Change the monitor's interval");
+            println!("        interval <WATCH> <ms|default>      Poll targets at their own pace");
         }
-        Ok(ms) => {
-            println!(" Interval must be between 10ms and 5000ms, got {}ms", ms);
-        }
-        Err(_) => {
-            println!(" Invalid interval: '{}'", args[1]);
-            println!(" Use a number in milliseconds");
+        2 => match args[1].parse::<u64>() {
+            Ok(ms) if (10..=5000).contains(&ms) => {
+                monitor.set_interval(ms);
+                println!(" Interval set to {}ms", ms);
+                if ms < 100 {
+                    println!(" Fast intervals may cause race conditions");
+                    println!(" You might see partial/garbled string changes");
+                    println!(" Try 200ms+ if you see strange results");
+                } else {
+                    println!(" Good interval for stable monitoring");
+                }
+            }
+            Ok(ms) => {
+                println!(" Interval must be between 10ms and 5000ms, got {}ms", ms);
+            }
+            Err(_) => {
+                println!(" Invalid interval: '{}'", args[1]);
+                println!(" Use a number in milliseconds");
+            }
+        },
+        _ => {
+            let targets = match resolve_targets(monitor, &args[1]) {
+                Some(targets) => targets,
+                None => return,
+            };
+            let interval = match args[2].as_str() {
+                "default" | "off" => None,
+                value => match value.parse::<u64>() {
+                    Ok(ms) if (10..=60000).contains(&ms) => {
+                        Some(std::time::Duration::from_millis(ms))
+                    }
+                    _ => {
+                        println!(" Invalid interval: '{}'", value);
+                        println!(" Use 10-60000 milliseconds, or 'default'");
+                        return;
+                    }
+                },
+            };
+            for target in targets {
+                match monitor.set_target_interval(target.id, interval) {
+                    Ok(()) => match interval {
+                        Some(interval) => println!(
+                            " #{} {} polled every {}ms",
+                            target.id,
+                            target.name,
+                            interval.as_millis()
+                        ),
+                        None => println!(
+                            " #{} {} back to the monitor's interval ({}ms)",
+                            target.id,
+                            target.name,
+                            monitor.interval().as_millis()
+                        ),
+                    },
+                    Err(e) => report_error("Failed to set interval", &e),
+                }
+            }
         }
     }
 }
 
+// what the loop measured, and a warning when it can't keep up
+fn print_loop_timing(monitor: &MemoryMonitor) {
+    let timing = monitor.loop_timing();
+    if timing.ticks == 0 {
+        return;
+    }
+    println!(
+        " Loop: {} ticks | aiming for {}ms, actually {:.1}ms | reads {:.1}ms avg, {:.1}ms max",
+        timing.ticks,
+        timing.tick.as_millis(),
+        timing.average_period.as_secs_f64() * 1000.0,
+        timing.average_work.as_secs_f64() * 1000.0,
+        timing.max_work.as_secs_f64() * 1000.0
+    );
+    if !timing.keeping_up() {
+        println!(
+            " ⚠️  The monitor can't keep up: reads take longer than the interval ({} overruns)",
+            timing.overruns
+        );
+        println!(" Raise the interval or give slow targets their own: interval <WATCH> <ms>");
+    } else if timing.overruns > 0 {
+        println!(" {} ticks ran over the interval", timing.overruns);
+    }
+}
+
 fn cmd_debug_monitoring(args: &[String]) {
     if args.len() < 2 {
         println!(" Usage: debug <on|off>");
@@ -1621,12 +4723,267 @@ fn cmd_debug_monitoring(args: &[String]) {
     }
 }
 
-// Helper function to get process by PID
-fn get_process_by_pid(pid: u32) -> Option<Process> {
-    match enumerate_processes() {
-        Ok(processes) => processes.into_iter().find(|p| p.pid == pid),
+// Open a scan target: a PID or process name, a core file or snapshot, or
+// FILE@0xBASE for raw dumps. The PID is returned for live processes
+fn open_target(target: &str) -> Option<(Box<dyn MemorySource>, Option<u32>)> {
+    open_target_for(target, true)
+}
+
+// needs_memory = false skips the access preflight, for commands that get by
+// with what /proc shows anyone (info)
+fn open_target_for(
+    target: &str,
+    needs_memory: bool,
+) -> Option<(Box<dyn MemorySource>, Option<u32>)> {
+    let raw_dump = target
+        .rsplit_once('@')
+        .filter(|(_, base)| base.starts_with("0x"));
+
+    if raw_dump.is_none() && !Path::new(target).is_file() {
+        return open_process_target(target, needs_memory);
+    }
+
+    let result = match raw_dump {
+        Some((path, base)) => {
+            let base_str = base.trim_start_matches("0x");
+            match usize::from_str_radix(base_str, 16) {
+                Ok(base) => RawDump::open(Path::new(path), base)
+                    .map(|d| Box::new(d) as Box<dyn MemorySource>),
+                Err(_) => {
+                    println!(" Invalid base address: {}", base);
+                    return None;
+                }
+            }
+        }
+        None => open_memory_file(Path::new(target)),
+    };
+
+    match result {
+        Ok(source) => {
+            println!(" Opened {} (read-only)", source.describe());
+            Some((source, None))
+        }
+        // a bare name that happens to be a file here (like the program
+        // itself) is still a process name
+        Err(_) if raw_dump.is_none() && !target.contains('/') => {
+            open_process_target(target, needs_memory)
+        }
+        Err(e) => {
+            report_error(&format!("Failed to open {}", target), &e);
+            None
+        }
+    }
+}
+
+fn open_process_target(
+    target: &str,
+    needs_memory: bool,
+) -> Option<(Box<dyn MemorySource>, Option<u32>)> {
+    let pid = resolve_pid(target)?;
+    let handle = open_process_handle(pid, needs_memory)?;
+    Some((Box::new(handle), Some(pid)))
+}
+
+// every command that opens a live process goes through here, so the access
+// preflight runs before anything touches its memory
+fn open_process_handle(pid: u32, needs_memory: bool) -> Option<ProcessHandle> {
+    let mut process = match get_process(pid) {
+        Some(p) => p,
+        None => {
+            println!(" Process {} not found", pid);
+            println!(" Use 'list' to see available processes");
+            return None;
+        }
+    };
+
+    if needs_memory && !check_access(pid) {
+        return None;
+    }
+
+    if let Err(e) = process.open() {
+        report_error(&format!("Failed to open process {}", pid), &e);
+        return None;
+    }
+
+    match process.handle {
+        Some(h) => Some(h),
+        None => {
+            println!(" Failed to get process handle");
+            None
+        }
+    }
+}
+
+// preflight before attaching, says exactly why a PID can't be read
+fn check_access(pid: u32) -> bool {
+    let error = match preflight(pid) {
+        Ok(report) => match report.error() {
+            None => return true,
+            Some(e) => e,
+        },
+        Err(e) => e,
+    };
+    report_error(&format!("Cannot attach to process {}", pid), &error);
+    if error.is_permission_denied() {
+        println!(" Run 'access {}' for the full picture", pid);
+    }
+    false
+}
+
+// Parse an address argument: hex, or a register expression like rsp+0x20
+fn parse_address(expr: &str, source: Option<&dyn MemorySource>, pid: Option<u32>) -> Option<usize> {
+    match resolve_address(expr, source, pid) {
+        Ok(address) => Some(address),
+        Err(e) => {
+            println!(" {}", e);
+            if let Some(hint) = e.hint() {
+                println!(" {}", hint);
+            }
+            None
+        }
+    }
+}
+
+// an error plus what to do about it, when the error knows
+fn report_error(context: &str, error: &MemscanError) {
+    println!(" {}: {}", context, error);
+    if let Some(hint) = error.hint() {
+        println!(" {}", hint);
+    }
+}
+
+// a "module!variable[.field]" expression with debug info, None for anything else
+fn lookup_variable(source: &dyn MemorySource, expr: &str) -> Option<TypedAddress> {
+    let (module, path) = expr.trim().split_once('!')?;
+    let symbolizer = Symbolizer::cached(source).ok()?;
+    find_variable(&symbolizer, module, path).ok()
+}
+
+fn print_variable(source: &dyn MemorySource, variable: &TypedAddress) {
+    let data = match source.read_memory(variable.address, variable.ty.read_size()) {
+        Ok(d) => d,
+        Err(e) => {
+            report_error("Failed to read memory", &e);
+            return;
+        }
+    };
+
+    if !variable.ty.is_struct() {
+        println!(
+            " {} = {} ({})",
+            describe_address(source, variable.address),
+            variable.ty.format(&data),
+            variable.ty.name
+        );
+        return;
+    }
+
+    println!(
+        " {} at 0x{:x}: {} ({} bytes)",
+        variable.name, variable.address, variable.ty.name, variable.ty.size
+    );
+    println!("{:-<70}", "");
+    for (name, offset, ty) in variable.ty.leaves() {
+        let value = data
+            .get(offset..offset.saturating_add(ty.size))
+            .map(|bytes| ty.format(bytes))
+            .unwrap_or_else(|| "?".to_string());
+        println!("  +0x{:<4x} {:<20} {:<16} {}", offset, name, ty.name, value);
+    }
+    println!("{:-<70}", "");
+}
+
+// "0x55d0c0a04028 (counter!counter)", just the hex outside any module
+fn describe_address(source: &dyn MemorySource, address: usize) -> String {
+    describe_with(Symbolizer::cached(source).ok().as_deref(), address)
+}
+
+// the same with a symbolizer the caller holds on to
+fn describe_with(symbolizer: Option<&Symbolizer>, address: usize) -> String {
+    match symbolizer.and_then(|s| s.describe(address)) {
+        Some(symbol) => format!("0x{:x} ({})", address, symbol),
+        None => format!("0x{:x}", address),
+    }
+}
+
+fn monitored_pid() -> Option<u32> {
+    GLOBAL_PROCESS_HANDLE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|h| h.pid())
+}
+
+// Turn a PID or a process name/pattern into a single PID, asking which one
+// when several processes match
+fn resolve_pid(target: &str) -> Option<u32> {
+    if let Ok(pid) = target.parse::<u32>() {
+        let ns_of = *NS_OF.lock().unwrap();
+        return match ns_of {
+            Some(ns_of) => match resolve_ns_pid(pid, ns_of) {
+                Ok(host_pid) => {
+                    println!(
+                        " PID {} in the namespace of {} is PID {}",
+                        pid, ns_of, host_pid
+                    );
+                    Some(host_pid)
+                }
+                Err(e) => {
+                    report_error("Cannot resolve namespace PID", &e);
+                    None
+                }
+            },
+            None => Some(pid),
+        };
+    }
+
+    let matches = match match_processes(target) {
+        Ok(m) => m,
         Err(e) => {
-            eprintln!(" Error enumerating processes: {}", e);
+            report_error("Invalid process pattern", &e);
+            return None;
+        }
+    };
+
+    match matches.len() {
+        0 => {
+            println!(" No process matches '{}'", target);
+            println!(
+                " Use 'list' to see available processes or 'wait {}'",
+                target
+            );
+            None
+        }
+        1 => {
+            println!(" Using {} (PID {})", matches[0].name, matches[0].pid);
+            Some(matches[0].pid)
+        }
+        _ => choose_process(target, &matches),
+    }
+}
+
+fn choose_process(target: &str, matches: &[Process]) -> Option<u32> {
+    println!(" {} processes match '{}':", matches.len(), target);
+    for (i, process) in matches.iter().enumerate() {
+        let command = process
+            .info()
+            .map(|info| info.command_line())
+            .unwrap_or_else(|| process.name.clone());
+        println!("  {:3}. PID {:<7} {}", i + 1, process.pid, command);
+    }
+
+    print!(" Select [1-{}] (enter to cancel): ", matches.len());
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_err() {
+        return None;
+    }
+
+    match input.trim().parse::<usize>() {
+        Ok(n) if (1..=matches.len()).contains(&n) => Some(matches[n - 1].pid),
+        _ => {
+            println!(" Cancelled - pass a PID to pick a process directly");
             None
         }
     }
@@ -0,0 +1,427 @@
+// ELF core dump generation for live processes
+// built by the goat (danielscos)
+//
+// writes the same layout the kernel produces on a crash: one PT_NOTE segment
+// (prpsinfo, per-thread prstatus/fpregs, auxv, file mappings) followed by a
+// PT_LOAD segment per mapping, so gdb and our own core reader can open it
+
+use crate::elf::{
+    self, EHDR_SIZE, EM_HOST, ET_CORE, ElfHeader, NT_AUXV, NT_FILE, NT_FPREGSET, NT_PRPSINFO,
+    NT_PRSTATUS, PF_R, PF_W, PF_X, PHDR_SIZE, PN_XNUM, PT_LOAD, PT_NOTE, ProgramHeader,
+};
+use crate::error::MemscanError;
+use crate::memory::{MemoryReader, MemoryRegion, get_memory_regions, read_zero_filled};
+use crate::threads::{self, list_tasks};
+use nix::sys::ptrace;
+use nix::sys::wait::{WaitPidFlag, waitpid};
+use nix::unistd::Pid;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const PAGE_SIZE: u64 = 4096;
+const COPY_CHUNK: usize = 1024 * 1024;
+
+// number of general purpose registers in elf_gregset_t
+#[cfg(target_arch = "x86_64")]
+const ELF_NGREG: usize = 27;
+#[cfg(target_arch = "aarch64")]
+const ELF_NGREG: usize = 34;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const ELF_NGREG: usize = 0;
+
+#[derive(Debug, Clone)]
+pub struct CoreDumpSummary {
+    pub path: PathBuf,
+    pub segments: usize,
+    pub dumped_bytes: u64,
+    pub unreadable_bytes: u64,
+    pub threads: usize,
+    pub threads_with_registers: usize,
+}
+
+// threads held in a ptrace stop for the duration of the dump, released on drop
+struct StoppedThreads {
+    tids: Vec<(i32, bool)>,
+}
+
+impl StoppedThreads {
+    fn stop_all(pid: u32) -> Self {
+        let mut tids = Vec::new();
+
+        for tid in list_tasks(pid) {
+            let target = Pid::from_raw(tid);
+            // seize + interrupt stops the thread without queueing a SIGSTOP
+            let stopped = ptrace::seize(target, ptrace::Options::empty()).is_ok()
+                && ptrace::interrupt(target).is_ok()
+                && waitpid(target, Some(WaitPidFlag::__WALL)).is_ok();
+            tids.push((tid, stopped));
+        }
+
+        Self { tids }
+    }
+}
+
+impl Drop for StoppedThreads {
+    fn drop(&mut self) {
+        for &(tid, stopped) in &self.tids {
+            if stopped {
+                let _ = ptrace::detach(Pid::from_raw(tid), None);
+            }
+        }
+    }
+}
+
+pub fn write_core_dump(pid: u32, output: &Path) -> Result<CoreDumpSummary, MemscanError> {
+    let proc_path = format!("/proc/{}", pid);
+    if !Path::new(&proc_path).exists() {
+        return Err(MemscanError::ProcessNotFound { pid });
+    }
+
+    let threads = StoppedThreads::stop_all(pid);
+    let regions = get_memory_regions(pid)?;
+    // one PT_LOAD per mapping plus the notes. the count has to fit e_phnum,
+    // past that it moves to section 0 (PN_XNUM), which we don't write
+    if regions.len() + 1 >= PN_XNUM {
+        return Err(MemscanError::unsupported(format!(
+            "PID {} has {} mappings, too many program headers for a core file",
+            pid,
+            regions.len()
+        )));
+    }
+    let reader = MemoryReader::new(pid);
+
+    // build the note segment first, its size decides where memory starts
+    let mut notes = Vec::new();
+    let stat = read_stat(pid);
+    elf::push_note(&mut notes, "CORE", NT_PRPSINFO, &build_prpsinfo(pid, &stat));
+
+    let mut threads_with_registers = 0;
+    // the main thread goes first, gdb treats the first prstatus as current
+    let mut ordered: Vec<(i32, bool)> = threads.tids.clone();
+    ordered.sort_by_key(|&(tid, _)| tid != pid as i32);
+
+    for &(tid, stopped) in &ordered {
+        let registers = if stopped {
+            read_gp_registers(tid)
+        } else {
+            None
+        };
+        if registers.is_some() {
+            threads_with_registers += 1;
+        }
+        let registers = registers.unwrap_or_else(|| vec![0u8; ELF_NGREG * 8]);
+        let fp_registers = if stopped {
+            read_fp_registers(tid)
+        } else {
+            None
+        };
+
+        let prstatus = build_prstatus(tid, &stat, &registers, fp_registers.is_some());
+        elf::push_note(&mut notes, "CORE", NT_PRSTATUS, &prstatus);
+        if let Some(fp) = fp_registers {
+            elf::push_note(&mut notes, "CORE", NT_FPREGSET, &fp);
+        }
+    }
+
+    if let Ok(auxv) = std::fs::read(format!("/proc/{}/auxv", pid)) {
+        elf::push_note(&mut notes, "CORE", NT_AUXV, &auxv);
+    }
+    elf::push_note(&mut notes, "CORE", NT_FILE, &build_file_note(&regions));
+
+    // lay out the file: headers, notes, then page aligned memory contents
+    let phnum = regions.len() + 1;
+    let notes_offset = (EHDR_SIZE + PHDR_SIZE * phnum) as u64;
+    let mut data_offset = elf::align_up(notes_offset + notes.len() as u64, PAGE_SIZE);
+
+    let mut headers = vec![ProgramHeader {
+        p_type: PT_NOTE,
+        p_flags: 0,
+        p_offset: notes_offset,
+        p_vaddr: 0,
+        p_paddr: 0,
+        p_filesz: notes.len() as u64,
+        p_memsz: 0,
+        p_align: 4,
+    }];
+
+    for region in &regions {
+        let filesz = if should_dump(region) {
+            region.size as u64
+        } else {
+            0
+        };
+
+        let mut flags = 0;
+        if region.readable {
+            flags |= PF_R;
+        }
+        if region.writable {
+            flags |= PF_W;
+        }
+        if region.executable {
+            flags |= PF_X;
+        }
+
+        headers.push(ProgramHeader {
+            p_type: PT_LOAD,
+            p_flags: flags,
+            p_offset: data_offset,
+            p_vaddr: region.start_address as u64,
+            p_paddr: 0,
+            p_filesz: filesz,
+            p_memsz: region.size as u64,
+            p_align: PAGE_SIZE,
+        });
+        data_offset += filesz;
+    }
+
+    let header = ElfHeader {
+        e_type: ET_CORE,
+        e_machine: EM_HOST,
+        e_entry: 0,
+        e_phoff: EHDR_SIZE as u64,
+        e_shoff: 0,
+        e_flags: 0,
+        e_phnum: phnum as u16,
+        e_shnum: 0,
+        e_shstrndx: 0,
+    };
+
+    let file =
+        File::create(output).map_err(|e| MemscanError::io(output.display().to_string(), e))?;
+    let mut out = BufWriter::new(file);
+    header.write_to(&mut out)?;
+    for ph in &headers {
+        ph.write_to(&mut out)?;
+    }
+    out.write_all(&notes)?;
+
+    let mut written = notes_offset + notes.len() as u64;
+    let mut dumped_bytes = 0u64;
+    let mut unreadable_bytes = 0u64;
+
+    for (region, ph) in regions.iter().zip(headers.iter().skip(1)) {
+        if ph.p_filesz == 0 {
+            continue;
+        }
+
+        write_zeros(&mut out, ph.p_offset - written)?;
+
+        let mut address = region.start_address;
+        let end = region.start_address + region.size;
+        while address < end {
+            let size = COPY_CHUNK.min(end - address);
+            // pages we cannot read are kept as zeros so offsets stay valid
+            let (chunk, missing) =
+                read_zero_filled(address, size, |at, len| reader.read_memory(at, len));
+            out.write_all(&chunk)?;
+            unreadable_bytes += missing;
+            dumped_bytes += size as u64 - missing;
+
+            address += size;
+        }
+
+        written = ph.p_offset + ph.p_filesz;
+    }
+
+    out.flush()?;
+
+    Ok(CoreDumpSummary {
+        path: output.to_path_buf(),
+        segments: regions.len(),
+        dumped_bytes,
+        unreadable_bytes,
+        threads: threads.tids.len(),
+        threads_with_registers,
+    })
+}
+
+// the kernel skips these too: vvar has no readable backing and vsyscall is
+// emulated, reading either just fails
+fn should_dump(region: &MemoryRegion) -> bool {
+    if !region.readable {
+        return false;
+    }
+    !matches!(
+        region.path.as_deref(),
+        Some("[vvar]") | Some("[vvar_vclock]") | Some("[vsyscall]")
+    )
+}
+
+fn write_zeros<W: Write>(out: &mut W, mut count: u64) -> io::Result<()> {
+    let zeros = [0u8; 4096];
+    while count > 0 {
+        let n = count.min(zeros.len() as u64) as usize;
+        out.write_all(&zeros[..n])?;
+        count -= n as u64;
+    }
+    Ok(())
+}
+
+// fields from /proc/PID/stat that end up in the notes
+#[derive(Debug, Default)]
+struct ProcStat {
+    state: u8,
+    ppid: i32,
+    pgrp: i32,
+    session: i32,
+    nice: i8,
+    flags: u64,
+}
+
+fn read_stat(pid: u32) -> ProcStat {
+    let content = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+    // comm may contain spaces and parens, everything after the last ')' is fixed
+    let rest = match content.rfind(')') {
+        Some(pos) => &content[pos + 1..],
+        None => return ProcStat::default(),
+    };
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let field = |i: usize| fields.get(i).copied().unwrap_or("0");
+
+    ProcStat {
+        state: field(0).bytes().next().unwrap_or(b'R'),
+        ppid: field(1).parse().unwrap_or(0),
+        pgrp: field(2).parse().unwrap_or(0),
+        session: field(3).parse().unwrap_or(0),
+        flags: field(6).parse().unwrap_or(0),
+        nice: field(16).parse().unwrap_or(0),
+    }
+}
+
+fn read_ids(pid: u32) -> (u32, u32) {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).unwrap_or_default();
+    let first_id = |prefix: &str| {
+        status
+            .lines()
+            .find(|l| l.starts_with(prefix))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    };
+    (first_id("Uid:"), first_id("Gid:"))
+}
+
+// struct elf_prpsinfo (136 bytes on 64-bit linux)
+fn build_prpsinfo(pid: u32, stat: &ProcStat) -> Vec<u8> {
+    let mut desc = vec![0u8; 136];
+    let (uid, gid) = read_ids(pid);
+
+    let sname = stat.state;
+    let state = match sname {
+        b'R' => 0u8,
+        b'S' => 1,
+        b'D' => 2,
+        b'T' | b't' => 3,
+        b'Z' => 4,
+        _ => 5,
+    };
+    desc[0] = state;
+    desc[1] = sname;
+    desc[2] = (sname == b'Z') as u8;
+    desc[3] = stat.nice as u8;
+    desc[8..16].copy_from_slice(&stat.flags.to_le_bytes());
+    desc[16..20].copy_from_slice(&uid.to_le_bytes());
+    desc[20..24].copy_from_slice(&gid.to_le_bytes());
+    desc[24..28].copy_from_slice(&(pid as i32).to_le_bytes());
+    desc[28..32].copy_from_slice(&stat.ppid.to_le_bytes());
+    desc[32..36].copy_from_slice(&stat.pgrp.to_le_bytes());
+    desc[36..40].copy_from_slice(&stat.session.to_le_bytes());
+
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).unwrap_or_default();
+    let fname = comm.trim().as_bytes();
+    let len = fname.len().min(15);
+    desc[40..40 + len].copy_from_slice(&fname[..len]);
+
+    // psargs is the command line with NULs turned into spaces
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    let args: Vec<u8> = cmdline
+        .iter()
+        .map(|&b| if b == 0 { b' ' } else { b })
+        .collect();
+    let args = args.trim_ascii_end();
+    let len = args.len().min(79);
+    desc[56..56 + len].copy_from_slice(&args[..len]);
+
+    desc
+}
+
+// struct elf_prstatus (336 bytes on x86_64)
+fn build_prstatus(tid: i32, stat: &ProcStat, registers: &[u8], fpvalid: bool) -> Vec<u8> {
+    let regs_offset = 112;
+    let mut desc = vec![0u8; regs_offset + ELF_NGREG * 8 + 8];
+
+    let signal = libc::SIGSTOP;
+    desc[0..4].copy_from_slice(&signal.to_le_bytes());
+    desc[12..14].copy_from_slice(&(signal as i16).to_le_bytes());
+    desc[32..36].copy_from_slice(&tid.to_le_bytes());
+    desc[36..40].copy_from_slice(&stat.ppid.to_le_bytes());
+    desc[40..44].copy_from_slice(&stat.pgrp.to_le_bytes());
+    desc[44..48].copy_from_slice(&stat.session.to_le_bytes());
+
+    let len = registers.len().min(ELF_NGREG * 8);
+    desc[regs_offset..regs_offset + len].copy_from_slice(&registers[..len]);
+
+    let fpvalid_offset = regs_offset + ELF_NGREG * 8;
+    desc[fpvalid_offset..fpvalid_offset + 4].copy_from_slice(&(fpvalid as i32).to_le_bytes());
+
+    desc
+}
+
+// NT_FILE: count, page size, (start, end, page offset) triples, then names
+fn build_file_note(regions: &[MemoryRegion]) -> Vec<u8> {
+    let files: Vec<&MemoryRegion> = regions
+        .iter()
+        .filter(|r| r.path.as_deref().is_some_and(|p| p.starts_with('/')))
+        .collect();
+
+    let mut desc = Vec::new();
+    desc.extend_from_slice(&(files.len() as u64).to_le_bytes());
+    desc.extend_from_slice(&PAGE_SIZE.to_le_bytes());
+
+    for region in &files {
+        desc.extend_from_slice(&(region.start_address as u64).to_le_bytes());
+        desc.extend_from_slice(&((region.start_address + region.size) as u64).to_le_bytes());
+        desc.extend_from_slice(&(region.offset / PAGE_SIZE).to_le_bytes());
+    }
+
+    for region in &files {
+        if let Some(path) = &region.path {
+            desc.extend_from_slice(path.as_bytes());
+        }
+        desc.push(0);
+    }
+
+    desc
+}
+
+fn read_gp_registers(tid: i32) -> Option<Vec<u8>> {
+    threads::getregs(tid as u32).ok().map(|r| r.to_bytes())
+}
+
+#[cfg(target_arch = "x86_64")]
+fn read_fp_registers(tid: i32) -> Option<Vec<u8>> {
+    let mut fpregs: libc::user_fpregs_struct = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::ptrace(
+            libc::PTRACE_GETFPREGS,
+            tid,
+            std::ptr::null_mut::<libc::c_void>(),
+            &mut fpregs as *mut libc::user_fpregs_struct as *mut libc::c_void,
+        )
+    };
+    if result < 0 {
+        return None;
+    }
+
+    let size = std::mem::size_of::<libc::user_fpregs_struct>();
+    let bytes =
+        unsafe { std::slice::from_raw_parts(&fpregs as *const _ as *const u8, size) }.to_vec();
+    Some(bytes)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn read_fp_registers(_tid: i32) -> Option<Vec<u8>> {
+    None
+}
@@ -0,0 +1,151 @@
+// ELF core files as an offline memory source
+// built by the goat (danielscos)
+//
+// reads cores written by `core`, gcore or the kernel. PT_LOAD segments become
+// memory regions, NT_FILE gives them their backing paths
+
+use crate::elf::{
+    self, ET_CORE, ElfHeader, NT_AUXV, NT_FILE, NT_PRPSINFO, NT_PRSTATUS, PF_R, PF_W, PF_X,
+    PHDR_SIZE, PT_LOAD, PT_NOTE, ProgramHeader,
+};
+use crate::error::MemscanError;
+use crate::memory::MemoryRegion;
+use crate::source::{MemorySource, OfflineImage, Segment, display_name};
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct CoreThread {
+    pub tid: i32,
+    pub signal: i32,
+    // raw elf_gregset_t words in user_regs_struct order
+    pub registers: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub struct CoreFile {
+    name: String,
+    image: OfflineImage,
+    pub pid: Option<u32>,
+    pub command: Option<String>,
+    pub threads: Vec<CoreThread>,
+    pub auxv: Vec<u8>,
+}
+
+fn invalid(message: &str) -> MemscanError {
+    MemscanError::invalid_data(message)
+}
+
+impl CoreFile {
+    pub fn open(path: &Path) -> Result<Self, MemscanError> {
+        let file = File::open(path).map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+
+        let mut header_bytes = [0u8; elf::EHDR_SIZE];
+        file.read_exact_at(&mut header_bytes, 0)?;
+        let header = ElfHeader::parse(&header_bytes).ok_or_else(|| invalid("Not an ELF64 file"))?;
+        if header.e_type != ET_CORE {
+            return Err(invalid("ELF file is not a core file"));
+        }
+
+        let file_len = file.metadata()?.len();
+        let table_size = (header.e_phnum as u64)
+            .checked_mul(PHDR_SIZE as u64)
+            .ok_or_else(|| invalid("Program header table is too large"))?;
+        let table_range = elf::file_range(header.e_phoff, table_size, file_len)?;
+        let mut table = vec![0u8; table_range.end];
+        file.read_exact_at(&mut table[table_range], header.e_phoff)?;
+        let program_headers = ProgramHeader::parse_table(&table, &header);
+
+        let mut core = Self {
+            name: display_name(path),
+            image: OfflineImage::new(file.try_clone()?, Vec::new()),
+            pid: None,
+            command: None,
+            threads: Vec::new(),
+            auxv: Vec::new(),
+        };
+
+        let mut mapped_files = Vec::new();
+        for ph in program_headers.iter().filter(|ph| ph.p_type == PT_NOTE) {
+            let range = elf::file_range(ph.p_offset, ph.p_filesz, file_len)?;
+            let mut data = vec![0u8; range.len()];
+            file.read_exact_at(&mut data, ph.p_offset)?;
+
+            for note in elf::parse_notes(&data) {
+                match note.note_type {
+                    NT_PRSTATUS => core.threads.push(parse_prstatus(&note.desc)),
+                    NT_PRPSINFO if note.desc.len() >= 56 => {
+                        core.pid = elf::u32_at(&note.desc, 24);
+                        let fname = &note.desc[40..56];
+                        let end = fname.iter().position(|&b| b == 0).unwrap_or(16);
+                        core.command = Some(String::from_utf8_lossy(&fname[..end]).to_string());
+                    }
+                    NT_AUXV => core.auxv = note.desc,
+                    NT_FILE => mapped_files = elf::parse_file_note(&note.desc),
+                    _ => {}
+                }
+            }
+        }
+
+        let segments = program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD && ph.p_memsz > 0)
+            .map(|ph| {
+                let mapping = mapped_files
+                    .iter()
+                    .find(|(start, _, _, _)| *start == ph.p_vaddr);
+
+                Segment::new(
+                    MemoryRegion {
+                        start_address: ph.p_vaddr as usize,
+                        size: ph.p_memsz as usize,
+                        readable: ph.p_flags & PF_R != 0,
+                        writable: ph.p_flags & PF_W != 0,
+                        executable: ph.p_flags & PF_X != 0,
+                        offset: mapping.map(|m| m.2).unwrap_or(0),
+                        path: mapping.map(|m| m.3.clone()),
+                    },
+                    ph.p_offset,
+                    ph.p_filesz.min(ph.p_memsz),
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        core.image = OfflineImage::new(file, segments);
+        Ok(core)
+    }
+}
+
+fn parse_prstatus(desc: &[u8]) -> CoreThread {
+    // pr_reg starts at offset 112 and runs up to pr_fpvalid (last 8 bytes)
+    let registers = desc
+        .get(112..desc.len().saturating_sub(8))
+        .unwrap_or_default()
+        .chunks_exact(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+
+    CoreThread {
+        tid: elf::u32_at(desc, 32).unwrap_or(0) as i32,
+        signal: elf::u16_at(desc, 12).unwrap_or(0) as i32,
+        registers,
+    }
+}
+
+impl MemorySource for CoreFile {
+    fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        self.image.read(address, size)
+    }
+
+    fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+        Ok(self.image.regions())
+    }
+
+    fn describe(&self) -> String {
+        match self.pid {
+            Some(pid) => format!("core {} (PID {})", self.name, pid),
+            None => format!("core {}", self.name),
+        }
+    }
+}
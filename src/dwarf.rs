@@ -0,0 +1,562 @@
+// DWARF debug info: global and static variables with their types
+// built by the goat (danielscos)
+//
+// only what's needed to watch variables by name: each variable with a fixed
+// address (DW_OP_addr) and its type, resolved through typedefs/const down to
+// base types, pointers, arrays, enums and structs. needs a binary built with -g
+
+use crate::elf::ElfFile;
+use crate::error::MemscanError;
+use crate::monitor::DataType;
+use crate::symbols::{CacheKey, Symbolizer, file_key};
+use gimli::{AttributeValue, EndianSlice, LittleEndian, Operation, UnitOffset};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+type Reader<'a> = EndianSlice<'a, LittleEndian>;
+type Unit<'a> = gimli::Unit<Reader<'a>>;
+
+// nested structs deeper than this are shown as opaque
+const MAX_TYPE_DEPTH: usize = 8;
+
+// most bytes read for one variable. sizes come straight from the debug info
+// and a broken or hostile file can claim anything
+pub const MAX_VARIABLE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub enum TypeKind {
+    Signed,
+    Unsigned,
+    Float,
+    Bool,
+    Char,
+    // name of the pointed-to type
+    Pointer(String),
+    Struct(Vec<Field>),
+    Array(Box<TypeInfo>, usize),
+    Enum(Vec<(String, i64)>),
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeInfo {
+    pub name: String,
+    pub size: usize,
+    pub kind: TypeKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub offset: usize,
+    pub ty: TypeInfo,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    // link-time address
+    pub address: usize,
+    pub ty: TypeInfo,
+}
+
+// a variable (or one of its fields) at its runtime address
+#[derive(Debug, Clone)]
+pub struct TypedAddress {
+    pub name: String,
+    pub address: usize,
+    pub ty: TypeInfo,
+}
+
+impl TypeInfo {
+    fn unknown(name: &str, size: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            size,
+            kind: TypeKind::Other,
+        }
+    }
+
+    // bytes to read for a value of this type, at least one and capped
+    pub fn read_size(&self) -> usize {
+        self.size.clamp(1, MAX_VARIABLE_SIZE)
+    }
+
+    pub fn is_struct(&self) -> bool {
+        matches!(self.kind, TypeKind::Struct(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(&self.kind, TypeKind::Array(element, _) if matches!(element.kind, TypeKind::Char))
+    }
+
+    // the monitor type for this, None for things it can't watch as one value
+    pub fn data_type(&self) -> Option<DataType> {
+        match (&self.kind, self.size) {
+            (TypeKind::Signed | TypeKind::Unsigned | TypeKind::Enum(_), 4) => Some(DataType::I32),
+            (TypeKind::Signed | TypeKind::Unsigned | TypeKind::Pointer(_), 8) => {
+                Some(DataType::I64)
+            }
+            (TypeKind::Float, 4) => Some(DataType::F32),
+            (TypeKind::Float, 8) => Some(DataType::F64),
+            _ if self.is_string() && self.size > 0 => Some(DataType::String(self.size.min(1024))),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        match &self.kind {
+            TypeKind::Struct(fields) => fields.iter().find(|f| f.name == name),
+            _ => None,
+        }
+    }
+
+    // the non-struct members, nested structs flattened to "outer.inner"
+    pub fn leaves(&self) -> Vec<(String, usize, &TypeInfo)> {
+        let mut leaves = Vec::new();
+        self.collect_leaves("", 0, &mut leaves);
+        leaves
+    }
+
+    fn collect_leaves<'a>(
+        &'a self,
+        prefix: &str,
+        offset: usize,
+        leaves: &mut Vec<(String, usize, &'a TypeInfo)>,
+    ) {
+        match &self.kind {
+            TypeKind::Struct(fields) => {
+                for field in fields {
+                    let name = if prefix.is_empty() {
+                        field.name.clone()
+                    } else {
+                        format!("{}.{}", prefix, field.name)
+                    };
+                    field
+                        .ty
+                        .collect_leaves(&name, offset.saturating_add(field.offset), leaves);
+                }
+            }
+            _ => leaves.push((prefix.to_string(), offset, self)),
+        }
+    }
+
+    // render raw bytes as this type
+    pub fn format(&self, data: &[u8]) -> String {
+        let data = &data[..data.len().min(self.size)];
+        let unsigned = || {
+            let mut bytes = [0u8; 8];
+            bytes[..data.len().min(8)].copy_from_slice(&data[..data.len().min(8)]);
+            u64::from_le_bytes(bytes)
+        };
+        let signed = || {
+            let bits = data.len().min(8) * 8;
+            if bits == 0 || bits == 64 {
+                unsigned() as i64
+            } else {
+                ((unsigned() << (64 - bits)) as i64) >> (64 - bits)
+            }
+        };
+
+        match &self.kind {
+            TypeKind::Signed => signed().to_string(),
+            TypeKind::Unsigned => unsigned().to_string(),
+            TypeKind::Bool => (unsigned() != 0).to_string(),
+            TypeKind::Char => match data.first() {
+                Some(&c) if c.is_ascii_graphic() || c == b' ' => format!("'{}'", c as char),
+                Some(&c) => c.to_string(),
+                None => String::new(),
+            },
+            TypeKind::Float if data.len() == 4 => {
+                f32::from_le_bytes(data.try_into().unwrap()).to_string()
+            }
+            TypeKind::Float if data.len() == 8 => {
+                f64::from_le_bytes(data.try_into().unwrap()).to_string()
+            }
+            TypeKind::Pointer(_) => format!("0x{:x}", unsigned()),
+            TypeKind::Enum(values) => {
+                let value = signed();
+                match values.iter().find(|(_, v)| *v == value) {
+                    Some((name, _)) => format!("{} ({})", name, value),
+                    None => value.to_string(),
+                }
+            }
+            TypeKind::Array(_, count) if self.is_string() => {
+                let text = &data[..data.len().min(*count)];
+                let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+                format!("'{}'", String::from_utf8_lossy(&text[..end]))
+            }
+            TypeKind::Array(element, count) if element.size > 0 => {
+                let shown: Vec<String> = data
+                    .chunks(element.size)
+                    .take((*count).min(8))
+                    .map(|chunk| element.format(chunk))
+                    .collect();
+                let more = if *count > 8 { ", ..." } else { "" };
+                format!("[{}{}]", shown.join(", "), more)
+            }
+            TypeKind::Struct(_) => format!("{{...}} ({} bytes)", self.size),
+            _ => hex::encode(data),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DebugInfo {
+    variables: Vec<Variable>,
+    by_name: HashMap<String, usize>,
+}
+
+impl DebugInfo {
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Variable> {
+        self.by_name.get(name).map(|&i| &self.variables[i])
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DEBUG_INFO_CACHE: Mutex<HashMap<CacheKey, Arc<DebugInfo>>> = Mutex::new(HashMap::new());
+}
+
+// parse (or fetch from the cache) the debug info of an ELF file. files
+// without .debug_info give an empty result rather than an error
+pub fn load_debug_info(path: &Path) -> Result<Arc<DebugInfo>, MemscanError> {
+    let key = file_key(path)?;
+    if let Some(info) = DEBUG_INFO_CACHE.lock().unwrap().get(&key) {
+        return Ok(Arc::clone(info));
+    }
+
+    let file = ElfFile::open(path)?;
+    let info = Arc::new(parse_debug_info(&file).map_err(|e| {
+        MemscanError::invalid_data(format!("Bad debug info in {}: {}", path.display(), e))
+    })?);
+    DEBUG_INFO_CACHE
+        .lock()
+        .unwrap()
+        .insert(key, Arc::clone(&info));
+    Ok(info)
+}
+
+fn parse_debug_info(file: &ElfFile) -> Result<DebugInfo, gimli::Error> {
+    let dwarf = gimli::Dwarf::load(|id| -> Result<Reader<'_>, gimli::Error> {
+        let data = file
+            .section(id.name())
+            .map(|sh| file.section_data(sh))
+            .unwrap_or(&[]);
+        Ok(EndianSlice::new(data, LittleEndian))
+    })?;
+
+    let mut info = DebugInfo::default();
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut entries = unit.entries();
+
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_variable {
+                continue;
+            }
+
+            let address = match entry.attr_value(gimli::DW_AT_location)? {
+                Some(AttributeValue::Exprloc(expression)) => {
+                    let mut operations = expression.operations(unit.encoding());
+                    match operations.next()? {
+                        Some(Operation::Address { address }) => address,
+                        Some(Operation::AddressIndex { index }) => dwarf.address(&unit, index)?,
+                        // registers, TLS, computed locations
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            // definitions of declared variables keep their name/type on the
+            // declaration
+            let declaration = match entry.attr_value(gimli::DW_AT_specification)? {
+                Some(AttributeValue::UnitRef(offset)) => Some(unit.entry(offset)?),
+                _ => None,
+            };
+            let described = declaration.as_ref().unwrap_or(entry);
+
+            let name = match described.attr_value(gimli::DW_AT_name)? {
+                Some(value) => dwarf
+                    .attr_string(&unit, value)?
+                    .to_string_lossy()
+                    .to_string(),
+                None => continue,
+            };
+            let ty = match described.attr_value(gimli::DW_AT_type)? {
+                Some(AttributeValue::UnitRef(offset)) => read_type(&dwarf, &unit, offset, 0)?,
+                _ => TypeInfo::unknown("?", 0),
+            };
+
+            if !info.by_name.contains_key(&name) {
+                info.by_name.insert(name.clone(), info.variables.len());
+            }
+            info.variables.push(Variable {
+                name,
+                address: address as usize,
+                ty,
+            });
+        }
+    }
+
+    Ok(info)
+}
+
+fn entry_name(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    unit: &Unit<'_>,
+    entry: &gimli::DebuggingInformationEntry<Reader<'_>>,
+) -> Result<Option<String>, gimli::Error> {
+    Ok(match entry.attr_value(gimli::DW_AT_name)? {
+        Some(value) => Some(
+            dwarf
+                .attr_string(unit, value)?
+                .to_string_lossy()
+                .to_string(),
+        ),
+        None => None,
+    })
+}
+
+fn read_type(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    unit: &Unit<'_>,
+    offset: UnitOffset,
+    depth: usize,
+) -> Result<TypeInfo, gimli::Error> {
+    let entry = unit.entry(offset)?;
+    let name = entry_name(dwarf, unit, &entry)?;
+    let size = entry
+        .attr_value(gimli::DW_AT_byte_size)?
+        .and_then(|v| v.udata_value())
+        .unwrap_or(0) as usize;
+    let inner = match entry.attr_value(gimli::DW_AT_type)? {
+        Some(AttributeValue::UnitRef(offset)) => Some(offset),
+        _ => None,
+    };
+
+    if depth > MAX_TYPE_DEPTH {
+        return Ok(TypeInfo::unknown(name.as_deref().unwrap_or("?"), size));
+    }
+
+    let tag = entry.tag();
+    let ty = match tag {
+        gimli::DW_TAG_base_type => {
+            let kind = match entry.attr_value(gimli::DW_AT_encoding)? {
+                Some(AttributeValue::Encoding(encoding)) => match encoding {
+                    gimli::DW_ATE_signed => TypeKind::Signed,
+                    gimli::DW_ATE_unsigned => TypeKind::Unsigned,
+                    gimli::DW_ATE_float => TypeKind::Float,
+                    gimli::DW_ATE_boolean => TypeKind::Bool,
+                    gimli::DW_ATE_signed_char | gimli::DW_ATE_unsigned_char => TypeKind::Char,
+                    _ => TypeKind::Other,
+                },
+                _ => TypeKind::Other,
+            };
+            TypeInfo {
+                name: name.unwrap_or_default(),
+                size,
+                kind,
+            }
+        }
+
+        // these only rename or qualify the type underneath
+        gimli::DW_TAG_typedef
+        | gimli::DW_TAG_const_type
+        | gimli::DW_TAG_volatile_type
+        | gimli::DW_TAG_restrict_type
+        | gimli::DW_TAG_atomic_type => {
+            let mut underlying = match inner {
+                Some(offset) => read_type(dwarf, unit, offset, depth + 1)?,
+                None => TypeInfo::unknown("void", 0),
+            };
+            match (tag, name) {
+                (gimli::DW_TAG_typedef, Some(name)) => underlying.name = name,
+                (gimli::DW_TAG_const_type, _) => {
+                    underlying.name = format!("const {}", underlying.name)
+                }
+                _ => {}
+            }
+            underlying
+        }
+
+        // only the target's name, following it could loop (linked lists)
+        gimli::DW_TAG_pointer_type | gimli::DW_TAG_reference_type => {
+            let target = match inner {
+                Some(offset) => {
+                    let target = unit.entry(offset)?;
+                    entry_name(dwarf, unit, &target)?.unwrap_or_else(|| "?".to_string())
+                }
+                None => "void".to_string(),
+            };
+            TypeInfo {
+                name: format!("{} *", target),
+                size: if size == 0 { 8 } else { size },
+                kind: TypeKind::Pointer(target),
+            }
+        }
+
+        gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type | gimli::DW_TAG_class_type => {
+            let mut fields = Vec::new();
+            let mut tree = unit.entries_tree(Some(offset))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let member = child.entry();
+                if member.tag() != gimli::DW_TAG_member {
+                    continue;
+                }
+                let field_offset = member
+                    .attr_value(gimli::DW_AT_data_member_location)?
+                    .and_then(|v| v.udata_value())
+                    .unwrap_or(0) as usize;
+                let field_type = match member.attr_value(gimli::DW_AT_type)? {
+                    Some(AttributeValue::UnitRef(offset)) => {
+                        read_type(dwarf, unit, offset, depth + 1)?
+                    }
+                    _ => TypeInfo::unknown("?", 0),
+                };
+                fields.push(Field {
+                    name: entry_name(dwarf, unit, member)?.unwrap_or_default(),
+                    offset: field_offset,
+                    ty: field_type,
+                });
+            }
+
+            let keyword = match tag {
+                gimli::DW_TAG_union_type => "union",
+                gimli::DW_TAG_class_type => "class",
+                _ => "struct",
+            };
+            TypeInfo {
+                name: format!("{} {}", keyword, name.as_deref().unwrap_or("<anonymous>")),
+                size,
+                kind: TypeKind::Struct(fields),
+            }
+        }
+
+        gimli::DW_TAG_array_type => {
+            let element = match inner {
+                Some(offset) => read_type(dwarf, unit, offset, depth + 1)?,
+                None => TypeInfo::unknown("?", 0),
+            };
+
+            // one subrange per dimension, flattened into a single count
+            let mut count = 1usize;
+            let mut tree = unit.entries_tree(Some(offset))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let range = child.entry();
+                if range.tag() != gimli::DW_TAG_subrange_type {
+                    continue;
+                }
+                let length = match range.attr_value(gimli::DW_AT_count)? {
+                    Some(v) => v.udata_value().unwrap_or(0),
+                    None => range
+                        .attr_value(gimli::DW_AT_upper_bound)?
+                        .and_then(|v| v.udata_value())
+                        .map(|upper| upper + 1)
+                        .unwrap_or(0),
+                };
+                count = count.saturating_mul(length as usize);
+            }
+
+            let name = format!("{}[{}]", element.name, count);
+            match element.size.checked_mul(count) {
+                Some(size) => TypeInfo {
+                    name,
+                    size,
+                    kind: TypeKind::Array(Box::new(element), count),
+                },
+                None => TypeInfo::unknown(&name, 0),
+            }
+        }
+
+        gimli::DW_TAG_enumeration_type => {
+            let mut values = Vec::new();
+            let mut tree = unit.entries_tree(Some(offset))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let enumerator = child.entry();
+                if enumerator.tag() != gimli::DW_TAG_enumerator {
+                    continue;
+                }
+                let value = enumerator
+                    .attr_value(gimli::DW_AT_const_value)?
+                    .and_then(|v| {
+                        v.sdata_value()
+                            .or_else(|| v.udata_value().map(|u| u as i64))
+                    })
+                    .unwrap_or(0);
+                if let Some(name) = entry_name(dwarf, unit, enumerator)? {
+                    values.push((name, value));
+                }
+            }
+            TypeInfo {
+                name: format!("enum {}", name.as_deref().unwrap_or("<anonymous>")),
+                size,
+                kind: TypeKind::Enum(values),
+            }
+        }
+
+        _ => TypeInfo::unknown(name.as_deref().unwrap_or("?"), size),
+    };
+
+    Ok(ty)
+}
+
+// "score" or "player.health" in a module ("" searches all of them), at its
+// runtime address
+pub fn find_variable(
+    symbolizer: &Symbolizer,
+    module_name: &str,
+    path: &str,
+) -> Result<TypedAddress, MemscanError> {
+    let not_found =
+        || MemscanError::not_found(format!("Debug info for '{}!{}'", module_name, path));
+
+    let mut parts = path.split('.');
+    let variable_name = parts.next().unwrap_or_default();
+
+    let module = symbolizer
+        .modules()
+        .iter()
+        .filter(|m| module_name.is_empty() || m.matches(module_name))
+        .find_map(|module| {
+            let info = load_debug_info(&module.file).ok()?;
+            let variable = info.variable(variable_name)?.clone();
+            Some((module, variable))
+        });
+    let (module, variable) = module.ok_or_else(not_found)?;
+
+    let mut address = module.load_bias.wrapping_add(variable.address);
+    let mut ty = variable.ty;
+    for field_name in parts {
+        let field = ty.field(field_name).cloned().ok_or_else(|| {
+            MemscanError::not_found(format!("Field '{}' of {}", field_name, ty.name))
+        })?;
+        // the offset comes from debug info
+        address = address.checked_add(field.offset).ok_or_else(|| {
+            MemscanError::invalid_data(format!(
+                "Field '{}' of {} is past the end of the address space",
+                field_name, ty.name
+            ))
+        })?;
+        ty = field.ty;
+    }
+
+    Ok(TypedAddress {
+        name: path.to_string(),
+        address,
+        ty,
+    })
+}
@@ -0,0 +1,536 @@
+// ELF64 structures and constants
+// built by the goat (danielscos)
+//
+// only the little-endian 64-bit layout is handled, which covers every target
+// memscan can attach to on linux
+
+use crate::error::MemscanError;
+use std::io::{self, Write};
+use std::path::Path;
+
+pub const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+pub const ELFCLASS64: u8 = 2;
+pub const ELFDATA2LSB: u8 = 1;
+pub const EV_CURRENT: u8 = 1;
+pub const ELFOSABI_NONE: u8 = 0;
+
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+pub const ET_CORE: u16 = 4;
+
+pub const EM_X86_64: u16 = 62;
+pub const EM_AARCH64: u16 = 183;
+
+#[cfg(target_arch = "x86_64")]
+pub const EM_HOST: u16 = EM_X86_64;
+#[cfg(target_arch = "aarch64")]
+pub const EM_HOST: u16 = EM_AARCH64;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub const EM_HOST: u16 = 0;
+
+// e_phnum at this value means the real count is elsewhere, in section 0
+pub const PN_XNUM: usize = 0xffff;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_NOTE: u32 = 4;
+
+pub const SHT_SYMTAB: u32 = 2;
+pub const SHT_STRTAB: u32 = 3;
+pub const SHT_NOBITS: u32 = 8;
+pub const SHT_DYNSYM: u32 = 11;
+
+pub const SHF_WRITE: u64 = 1;
+pub const SHF_ALLOC: u64 = 2;
+pub const SHF_EXECINSTR: u64 = 4;
+
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+// note types written into core files
+pub const NT_PRSTATUS: u32 = 1;
+pub const NT_FPREGSET: u32 = 2;
+pub const NT_PRPSINFO: u32 = 3;
+pub const NT_AUXV: u32 = 6;
+pub const NT_FILE: u32 = 0x4649_4c45;
+
+pub const EHDR_SIZE: usize = 64;
+pub const PHDR_SIZE: usize = 56;
+pub const SHDR_SIZE: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct ElfHeader {
+    pub e_type: u16,
+    pub e_machine: u16,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_flags: u32,
+    pub e_phnum: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl ElfHeader {
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut ident = [0u8; 16];
+        ident[..4].copy_from_slice(&ELF_MAGIC);
+        ident[4] = ELFCLASS64;
+        ident[5] = ELFDATA2LSB;
+        ident[6] = EV_CURRENT;
+        ident[7] = ELFOSABI_NONE;
+
+        out.write_all(&ident)?;
+        out.write_all(&self.e_type.to_le_bytes())?;
+        out.write_all(&self.e_machine.to_le_bytes())?;
+        out.write_all(&(EV_CURRENT as u32).to_le_bytes())?;
+        out.write_all(&self.e_entry.to_le_bytes())?;
+        out.write_all(&self.e_phoff.to_le_bytes())?;
+        out.write_all(&self.e_shoff.to_le_bytes())?;
+        out.write_all(&self.e_flags.to_le_bytes())?;
+        out.write_all(&(EHDR_SIZE as u16).to_le_bytes())?;
+        out.write_all(&(PHDR_SIZE as u16).to_le_bytes())?;
+        out.write_all(&self.e_phnum.to_le_bytes())?;
+        out.write_all(&(SHDR_SIZE as u16).to_le_bytes())?;
+        out.write_all(&self.e_shnum.to_le_bytes())?;
+        out.write_all(&self.e_shstrndx.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+impl ProgramHeader {
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&self.p_type.to_le_bytes())?;
+        out.write_all(&self.p_flags.to_le_bytes())?;
+        out.write_all(&self.p_offset.to_le_bytes())?;
+        out.write_all(&self.p_vaddr.to_le_bytes())?;
+        out.write_all(&self.p_paddr.to_le_bytes())?;
+        out.write_all(&self.p_filesz.to_le_bytes())?;
+        out.write_all(&self.p_memsz.to_le_bytes())?;
+        out.write_all(&self.p_align.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+// append one note record (name and descriptor padded to 4 bytes, as the
+// kernel does for core files)
+pub fn push_note(buffer: &mut Vec<u8>, name: &str, note_type: u32, desc: &[u8]) {
+    let name_len = name.len() + 1;
+    buffer.extend_from_slice(&(name_len as u32).to_le_bytes());
+    buffer.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&note_type.to_le_bytes());
+    buffer.extend_from_slice(name.as_bytes());
+    buffer.push(0);
+    pad_to(buffer, 4);
+    buffer.extend_from_slice(desc);
+    pad_to(buffer, 4);
+}
+
+pub fn pad_to(buffer: &mut Vec<u8>, align: usize) {
+    while !buffer.len().is_multiple_of(align) {
+        buffer.push(0);
+    }
+}
+
+pub fn align_up(value: u64, align: u64) -> u64 {
+    value.div_ceil(align) * align
+}
+
+// parsing helpers, shared by the core reader and module/symbol code
+
+pub(crate) fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+pub(crate) fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+pub(crate) fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+impl ElfHeader {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < EHDR_SIZE
+            || data[..4] != ELF_MAGIC
+            || data[4] != ELFCLASS64
+            || data[5] != ELFDATA2LSB
+        {
+            return None;
+        }
+
+        Some(Self {
+            e_type: u16_at(data, 16)?,
+            e_machine: u16_at(data, 18)?,
+            e_entry: u64_at(data, 24)?,
+            e_phoff: u64_at(data, 32)?,
+            e_shoff: u64_at(data, 40)?,
+            e_flags: u32_at(data, 48)?,
+            e_phnum: u16_at(data, 56)?,
+            e_shnum: u16_at(data, 60)?,
+            e_shstrndx: u16_at(data, 62)?,
+        })
+    }
+}
+
+impl ProgramHeader {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            p_type: u32_at(data, 0)?,
+            p_flags: u32_at(data, 4)?,
+            p_offset: u64_at(data, 8)?,
+            p_vaddr: u64_at(data, 16)?,
+            p_paddr: u64_at(data, 24)?,
+            p_filesz: u64_at(data, 32)?,
+            p_memsz: u64_at(data, 40)?,
+            p_align: u64_at(data, 48)?,
+        })
+    }
+
+    // parse the whole program header table out of a buffer holding it
+    pub fn parse_table(data: &[u8], header: &ElfHeader) -> Vec<Self> {
+        (0..header.e_phnum as usize)
+            .filter_map(|i| {
+                let start = (header.e_phoff as usize).checked_add(i * PHDR_SIZE)?;
+                Self::parse(data.get(start..start.checked_add(PHDR_SIZE)?)?)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub name: String,
+    pub note_type: u32,
+    pub desc: Vec<u8>,
+}
+
+pub fn parse_notes(data: &[u8]) -> Vec<Note> {
+    let mut notes = Vec::new();
+    let mut offset = 0;
+
+    while offset + 12 <= data.len() {
+        let (Some(name_len), Some(desc_len), Some(note_type)) = (
+            u32_at(data, offset),
+            u32_at(data, offset + 4),
+            u32_at(data, offset + 8),
+        ) else {
+            break;
+        };
+
+        let name_start = offset + 12;
+        let desc_start = name_start + align_up(name_len as u64, 4) as usize;
+        let desc_end = desc_start + desc_len as usize;
+        if desc_end > data.len() {
+            break;
+        }
+
+        let name = &data[name_start..name_start + name_len as usize];
+        notes.push(Note {
+            name: String::from_utf8_lossy(name)
+                .trim_end_matches('\0')
+                .to_string(),
+            note_type,
+            desc: data[desc_start..desc_end].to_vec(),
+        });
+
+        offset = desc_start + align_up(desc_len as u64, 4) as usize;
+    }
+
+    notes
+}
+
+// decode an NT_FILE descriptor into (start, end, file offset, path) entries
+pub fn parse_file_note(desc: &[u8]) -> Vec<(u64, u64, u64, String)> {
+    let (Some(count), Some(page_size)) = (u64_at(desc, 0), u64_at(desc, 8)) else {
+        return Vec::new();
+    };
+
+    // the count comes from the file, no more entries than the note can hold
+    let count = (count as usize).min(desc.len().saturating_sub(16) / 24);
+    let names_start = 16 + count * 24;
+    let mut names = desc
+        .get(names_start..)
+        .unwrap_or_default()
+        .split(|&b| b == 0)
+        .map(|n| String::from_utf8_lossy(n).to_string());
+
+    (0..count)
+        .filter_map(|i| {
+            let base = 16 + i * 24;
+            let start = u64_at(desc, base)?;
+            let end = u64_at(desc, base + 8)?;
+            let page_offset = u64_at(desc, base + 16)?;
+            let offset = page_offset.checked_mul(page_size)?;
+            Some((start, end, offset, names.next()?))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct SectionHeader {
+    pub sh_name: u32,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+impl SectionHeader {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            sh_name: u32_at(data, 0)?,
+            sh_type: u32_at(data, 4)?,
+            sh_flags: u64_at(data, 8)?,
+            sh_addr: u64_at(data, 16)?,
+            sh_offset: u64_at(data, 24)?,
+            sh_size: u64_at(data, 32)?,
+            sh_link: u32_at(data, 40)?,
+            sh_info: u32_at(data, 44)?,
+            sh_addralign: u64_at(data, 48)?,
+            sh_entsize: u64_at(data, 56)?,
+        })
+    }
+}
+
+// [offset, offset + size) of a file that is `file_len` bytes long, refusing
+// anything that overflows or runs past the end of the file
+pub(crate) fn file_range(
+    offset: u64,
+    size: u64,
+    file_len: u64,
+) -> Result<std::ops::Range<usize>, MemscanError> {
+    match offset.checked_add(size) {
+        Some(end) if end <= file_len => Ok(offset as usize..end as usize),
+        _ => Err(MemscanError::invalid_data(format!(
+            "{} bytes at offset {:#x} run past the end of the file",
+            size, offset
+        ))),
+    }
+}
+
+// NUL terminated string at an offset into a string table
+pub(crate) fn str_at(table: &[u8], offset: usize) -> Option<&str> {
+    let bytes = table.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    std::str::from_utf8(&bytes[..end]).ok()
+}
+
+pub fn link_base(program_headers: &[ProgramHeader]) -> Option<u64> {
+    program_headers
+        .iter()
+        .filter(|ph| ph.p_type == PT_LOAD)
+        .map(|ph| ph.p_vaddr & !0xfff)
+        .min()
+}
+
+// an ELF file from disk with its section headers resolved to names
+#[derive(Debug)]
+pub struct ElfFile {
+    pub header: ElfHeader,
+    pub program_headers: Vec<ProgramHeader>,
+    pub sections: Vec<(String, SectionHeader)>,
+    data: Vec<u8>,
+}
+
+impl ElfFile {
+    pub fn open(path: &Path) -> Result<Self, MemscanError> {
+        let data =
+            std::fs::read(path).map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+        Self::parse(data)
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self, MemscanError> {
+        let header = ElfHeader::parse(&data)
+            .ok_or_else(|| MemscanError::invalid_data("Not an ELF64 file"))?;
+        let program_headers = ProgramHeader::parse_table(&data, &header);
+
+        let headers: Vec<SectionHeader> = (0..header.e_shnum as usize)
+            .filter_map(|i| {
+                let start = (header.e_shoff as usize).checked_add(i * SHDR_SIZE)?;
+                SectionHeader::parse(data.get(start..start.checked_add(SHDR_SIZE)?)?)
+            })
+            .collect();
+
+        // every section has to lie inside the file so section_data can't
+        // be handed a range that overflows
+        for sh in headers.iter().filter(|sh| sh.sh_type != SHT_NOBITS) {
+            file_range(sh.sh_offset, sh.sh_size, data.len() as u64)?;
+        }
+
+        let names = match headers.get(header.e_shstrndx as usize) {
+            Some(strtab) if strtab.sh_type != SHT_NOBITS => {
+                &data[file_range(strtab.sh_offset, strtab.sh_size, data.len() as u64)?]
+            }
+            _ => &[],
+        };
+
+        let sections = headers
+            .into_iter()
+            .map(|sh| {
+                let name = str_at(names, sh.sh_name as usize).unwrap_or("").to_string();
+                (name, sh)
+            })
+            .collect();
+
+        Ok(Self {
+            header,
+            program_headers,
+            sections,
+            data,
+        })
+    }
+
+    pub fn section(&self, name: &str) -> Option<&SectionHeader> {
+        self.sections
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, sh)| sh)
+    }
+
+    // file contents of a section, empty for .bss-style sections
+    pub fn section_data(&self, section: &SectionHeader) -> &[u8] {
+        if section.sh_type == SHT_NOBITS {
+            return &[];
+        }
+        file_range(section.sh_offset, section.sh_size, self.data.len() as u64)
+            .ok()
+            .and_then(|range| self.data.get(range))
+            .unwrap_or_default()
+    }
+
+    // page of the lowest PT_LOAD, where the loader puts the start of the file
+    pub fn link_base(&self) -> Option<u64> {
+        link_base(&self.program_headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an NT_FILE descriptor: count, page size, (start, end, page offset)
+    // entries and then the NUL separated paths
+    fn file_note(count: u64, entries: &[(u64, u64, u64)], names: &[&str]) -> Vec<u8> {
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&count.to_le_bytes());
+        desc.extend_from_slice(&4096u64.to_le_bytes());
+        for (start, end, page_offset) in entries {
+            desc.extend_from_slice(&start.to_le_bytes());
+            desc.extend_from_slice(&end.to_le_bytes());
+            desc.extend_from_slice(&page_offset.to_le_bytes());
+        }
+        for name in names {
+            desc.extend_from_slice(name.as_bytes());
+            desc.push(0);
+        }
+        desc
+    }
+
+    fn note(note_type: u32, name: &str, desc: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+        data.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+        data.extend_from_slice(&note_type.to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        data.resize(align_up(data.len() as u64, 4) as usize, 0);
+        data.extend_from_slice(desc);
+        data.resize(align_up(data.len() as u64, 4) as usize, 0);
+        data
+    }
+
+    #[test]
+    fn parses_file_note() {
+        let desc = file_note(
+            2,
+            &[(0x400000, 0x401000, 0), (0x7f0000000000, 0x7f0000002000, 3)],
+            &["/usr/bin/game", "/usr/lib/libc.so.6"],
+        );
+        assert_eq!(
+            parse_file_note(&desc),
+            vec![
+                (0x400000, 0x401000, 0, "/usr/bin/game".to_string()),
+                (
+                    0x7f0000000000,
+                    0x7f0000002000,
+                    3 * 4096,
+                    "/usr/lib/libc.so.6".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn hostile_file_note_count() {
+        // a count that would overflow the entry table size or loop forever
+        for count in [u64::MAX, u64::MAX / 24, 1 << 40] {
+            let desc = file_note(count, &[(0x1000, 0x2000, 0)], &["/bin/a"]);
+            let files = parse_file_note(&desc);
+            assert!(files.len() <= 1, "count {count:#x} gave {files:?}");
+        }
+    }
+
+    #[test]
+    fn truncated_file_note() {
+        let desc = file_note(2, &[(0x1000, 0x2000, 0), (0x3000, 0x4000, 1)], &["/bin/a"]);
+        // paths run out before the entries do
+        let files = parse_file_note(&desc);
+        assert_eq!(files[0].3, "/bin/a");
+        assert!(files.get(1).is_none_or(|f| f.3.is_empty()));
+        // entries cut off midway
+        assert!(parse_file_note(&desc[..16 + 20]).is_empty());
+        assert!(parse_file_note(&desc[..10]).is_empty());
+        assert!(parse_file_note(&[]).is_empty());
+
+        // a page offset that overflows drops only that entry
+        let desc = file_note(1, &[(0x1000, 0x2000, u64::MAX)], &["/bin/a"]);
+        assert!(parse_file_note(&desc).is_empty());
+    }
+
+    #[test]
+    fn truncated_notes() {
+        let desc = file_note(1, &[(0x1000, 0x2000, 0)], &["/bin/a"]);
+        let mut data = note(NT_AUXV, "CORE", &[1, 2, 3, 4]);
+        data.extend(note(NT_FILE, "CORE", &desc));
+
+        let notes = parse_notes(&data);
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[1].note_type, NT_FILE);
+        assert_eq!(notes[1].desc, desc);
+
+        // a descriptor running past the end stops the walk, earlier notes stay
+        let notes = parse_notes(&data[..data.len() - 8]);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].note_type, NT_AUXV);
+
+        // a descriptor length from a hostile header
+        let mut data = note(NT_FILE, "CORE", &desc);
+        data[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_notes(&data).is_empty());
+    }
+}
@@ -0,0 +1,332 @@
+// one error type for the whole library
+// built by the goat (danielscos)
+//
+// every public API returns MemscanError so callers can tell "the process is
+// gone" from "that address isn't mapped" from "ptrace_scope says no" and say
+// something useful about it. plain io errors (files, /proc) are wrapped in Io
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum MemscanError {
+    ProcessNotFound {
+        pid: u32,
+    },
+    // the process went away, or its PID now belongs to another program
+    ProcessExited {
+        pid: u32,
+        replaced: bool,
+    },
+    PermissionDenied {
+        pid: Option<u32>,
+        reason: DenialReason,
+    },
+    UnmappedAddress {
+        pid: Option<u32>,
+        address: usize,
+    },
+    PartialRead {
+        address: usize,
+        requested: usize,
+        read: usize,
+        // the region the read started in, when known
+        region: Option<(usize, usize)>,
+    },
+    InvalidValue {
+        value: String,
+        expected: String,
+    },
+    InvalidType {
+        name: String,
+    },
+    InvalidAddress {
+        expression: String,
+        reason: String,
+    },
+    // a module, symbol, thread, ... that doesn't exist
+    NotFound {
+        what: String,
+    },
+    // a file that isn't what it claims to be (ELF, core, snapshot)
+    InvalidData {
+        message: String,
+    },
+    NoMemoryRegions,
+    ReadOnly,
+    Unsupported {
+        message: String,
+    },
+    // something that's already the case (monitor running, process resumed)
+    InvalidState {
+        message: String,
+    },
+    Io {
+        context: String,
+        source: io::Error,
+    },
+}
+
+// why the kernel refused access to a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DenialReason {
+    // kernel.yama.ptrace_scope level
+    PtraceScope(u32),
+    MissingCapability(&'static str),
+    UidMismatch { ours: u32, theirs: u32 },
+    // already being traced by another process
+    Traced { tracer: u32 },
+    // the target made itself non-dumpable (setuid, prctl) and only
+    // CAP_SYS_PTRACE gets in
+    NotDumpable,
+    // the address is in a mapping without write permission
+    ReadOnlyMapping { address: usize },
+    Unknown,
+}
+
+impl MemscanError {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound { what: what.into() }
+    }
+
+    pub fn invalid_data(message: impl Into<String>) -> Self {
+        Self::InvalidData {
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_address(expression: &str, reason: impl Into<String>) -> Self {
+        Self::InvalidAddress {
+            expression: expression.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid_value(value: &str, expected: impl Into<String>) -> Self {
+        Self::InvalidValue {
+            value: value.to_string(),
+            expected: expected.into(),
+        }
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::Unsupported {
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_state(message: impl Into<String>) -> Self {
+        Self::InvalidState {
+            message: message.into(),
+        }
+    }
+
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    // an errno from a syscall against a process. EPERM/EACCES get diagnosed,
+    // ESRCH means the process is gone, EFAULT an unmapped address
+    pub fn from_errno(errno: nix::errno::Errno, pid: u32, address: Option<usize>) -> Self {
+        use nix::errno::Errno;
+        match errno {
+            Errno::ESRCH => Self::ProcessNotFound { pid },
+            Errno::EPERM | Errno::EACCES => Self::PermissionDenied {
+                pid: Some(pid),
+                reason: DenialReason::diagnose(pid),
+            },
+            Errno::EFAULT | Errno::EIO if address.is_some() => Self::UnmappedAddress {
+                pid: Some(pid),
+                address: address.unwrap_or(0),
+            },
+            _ => Self::io(format!("PID {}", pid), io::Error::from(errno)),
+        }
+    }
+
+    // an io error from a /proc/PID file: missing means the process is gone,
+    // denied gets diagnosed like EPERM
+    pub fn from_proc_io(error: io::Error, pid: u32, context: impl Into<String>) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::ProcessNotFound { pid },
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied {
+                pid: Some(pid),
+                reason: DenialReason::diagnose(pid),
+            },
+            _ => Self::io(context, error),
+        }
+    }
+
+    pub fn is_process_gone(&self) -> bool {
+        matches!(
+            self,
+            Self::ProcessNotFound { .. } | Self::ProcessExited { .. }
+        )
+    }
+
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, Self::PermissionDenied { .. } | Self::ReadOnly)
+    }
+
+    // what to do about it, for the CLI
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::ProcessNotFound { .. } => Some("Use 'list' to see running processes".into()),
+            Self::ProcessExited { .. } => {
+                Some("Use 'wait <NAME> --restore' to follow the next instance".into())
+            }
+            Self::PermissionDenied { reason, .. } => reason.hint(),
+            Self::UnmappedAddress { .. } => {
+                Some("Use 'info <PID>' or 'modules <PID>' to see what's mapped".into())
+            }
+            Self::PartialRead { .. } => {
+                Some("The read ran past the end of a mapping, try a smaller size".into())
+            }
+            Self::InvalidType { .. } => {
+                Some("Valid types: i32, i64, f32, f64, string, string:<size>".into())
+            }
+            Self::ReadOnly => Some("Core files and snapshots can't be written to".into()),
+            _ => None,
+        }
+    }
+}
+
+impl DenialReason {
+    // best guess at why we can't touch pid, from what /proc says about us
+    // and the target (see access::inspect)
+    pub fn diagnose(pid: u32) -> Self {
+        let report = match crate::access::inspect(pid) {
+            Ok(report) => report,
+            Err(_) => return Self::Unknown,
+        };
+        // memory access looks fine, so it was something that needs to attach
+        match (report.denial, report.tracer) {
+            (Some(reason), _) => reason,
+            (None, Some(tracer)) if tracer != std::process::id() => Self::Traced { tracer },
+            (None, _) => Self::Unknown,
+        }
+    }
+
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::PtraceScope(3) => {
+                Some("ptrace_scope is 3, attaching is disabled until reboot".into())
+            }
+            Self::PtraceScope(2) => Some("ptrace_scope is 2, run memscan with sudo".into()),
+            Self::PtraceScope(_) => Some(
+                "ptrace_scope only allows tracing children: use sudo, or 'spawn' the target".into(),
+            ),
+            Self::MissingCapability(cap) => Some(format!("Grant {} or run with sudo", cap)),
+            Self::UidMismatch { .. } => {
+                Some("The process belongs to another user, run memscan as that user or root".into())
+            }
+            Self::Traced { tracer } => Some(format!(
+                "Process {} is already tracing it (a debugger?), detach it first",
+                tracer
+            )),
+            Self::NotDumpable => Some(
+                "The process is not dumpable (setuid or PR_SET_DUMPABLE), run with sudo".into(),
+            ),
+            Self::ReadOnlyMapping { .. } => None,
+            Self::Unknown => Some("Try running with sudo".into()),
+        }
+    }
+}
+
+impl fmt::Display for MemscanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProcessNotFound { pid } => write!(f, "Process {} not found", pid),
+            Self::ProcessExited {
+                pid,
+                replaced: true,
+            } => write!(
+                f,
+                "Process {} exited, the PID now belongs to another program",
+                pid
+            ),
+            Self::ProcessExited { pid, .. } => write!(f, "Process {} exited", pid),
+            Self::PermissionDenied { pid, reason } => {
+                match pid {
+                    Some(pid) => write!(f, "Permission denied for process {}", pid)?,
+                    None => write!(f, "Permission denied")?,
+                }
+                match reason {
+                    DenialReason::PtraceScope(scope) => {
+                        write!(f, " (ptrace_scope is {})", scope)
+                    }
+                    DenialReason::MissingCapability(cap) => write!(f, " (missing {})", cap),
+                    DenialReason::UidMismatch { ours, theirs } => {
+                        write!(f, " (it runs as uid {}, we're uid {})", theirs, ours)
+                    }
+                    DenialReason::Traced { tracer } => {
+                        write!(f, " (already traced by PID {})", tracer)
+                    }
+                    DenialReason::NotDumpable => write!(f, " (not dumpable)"),
+                    DenialReason::ReadOnlyMapping { address } => {
+                        write!(f, " (0x{:x} is mapped read-only)", address)
+                    }
+                    DenialReason::Unknown => Ok(()),
+                }
+            }
+            Self::UnmappedAddress { address, .. } => {
+                write!(f, "Address 0x{:x} is not mapped", address)
+            }
+            Self::PartialRead {
+                address,
+                requested,
+                read,
+                region,
+            } => {
+                write!(
+                    f,
+                    "Partial read at 0x{:x}: {} of {} bytes",
+                    address, read, requested
+                )?;
+                if let Some((start, end)) = region {
+                    write!(f, " (region 0x{:x}-0x{:x})", start, end)?;
+                }
+                Ok(())
+            }
+            Self::InvalidValue { value, expected } => {
+                write!(f, "Invalid value '{}', expected {}", value, expected)
+            }
+            Self::InvalidType { name } => write!(f, "Invalid type '{}'", name),
+            Self::InvalidAddress { expression, reason } => {
+                write!(f, "Invalid address '{}': {}", expression, reason)
+            }
+            Self::NotFound { what } => write!(f, "{} not found", what),
+            Self::InvalidData { message } => write!(f, "{}", message),
+            Self::NoMemoryRegions => write!(f, "No scannable memory regions found"),
+            Self::ReadOnly => write!(f, "Memory source is read-only"),
+            Self::Unsupported { message } | Self::InvalidState { message } => {
+                write!(f, "{}", message)
+            }
+            Self::Io { context, source } if context.is_empty() => write!(f, "{}", source),
+            Self::Io { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl std::error::Error for MemscanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MemscanError {
+    fn from(error: io::Error) -> Self {
+        Self::io("", error)
+    }
+}
+
+impl From<nix::errno::Errno> for MemscanError {
+    fn from(errno: nix::errno::Errno) -> Self {
+        Self::io("", io::Error::from(errno))
+    }
+}
@@ -0,0 +1,647 @@
+// change history recordings
+// built by the goat (danielscos)
+//
+// a recorder subscribes to a monitor and appends every change to a file as it
+// happens, one record per line: JSON Lines, or CSV when the file ends in .csv.
+// old/new are the decoded values for people (and jq, spreadsheets), old_bytes
+// and new_bytes the raw bytes, which is what load_history reads back
+
+use crate::error::MemscanError;
+use crate::monitor::{
+    DataType, MemoryMonitor, MonitorChange, MonitorEvent, data_type_name, format_value,
+    parse_data_type,
+};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+const CSV_HEADER: &str = "time,target,address,type,old,new,old_bytes,new_bytes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    JsonLines,
+    Csv,
+}
+
+// one recorded change
+#[derive(Debug, Clone)]
+pub struct HistoryRecord {
+    pub time: SystemTime,
+    pub name: String,
+    pub address: usize,
+    pub data_type: DataType,
+    pub old_value: Vec<u8>,
+    pub new_value: Vec<u8>,
+}
+
+// what to pick out of a recording, every field that is set has to match
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    // a target name or its address (hex)
+    pub target: Option<String>,
+    pub from: Option<SystemTime>,
+    pub to: Option<SystemTime>,
+    // the old or the new value
+    pub value: Option<String>,
+}
+
+pub struct HistoryRecorder {
+    path: PathBuf,
+    format: HistoryFormat,
+    records: Arc<AtomicU64>,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    error: Arc<Mutex<Option<String>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HistoryFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::JsonLines,
+        }
+    }
+}
+
+impl From<&MonitorChange> for HistoryRecord {
+    fn from(change: &MonitorChange) -> Self {
+        Self {
+            time: change.time,
+            name: change.name.clone(),
+            address: change.address,
+            data_type: change.data_type.clone(),
+            old_value: change.old_value.clone(),
+            new_value: change.new_value.clone(),
+        }
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> f64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+// a decoded value as a JSON value: numbers stay numbers (NaN and infinity
+// can't, they become strings)
+fn json_value(data_type: &DataType, text: &str) -> String {
+    match data_type {
+        DataType::String(_) | DataType::Bytes(_) => json_string(text),
+        _ if text.parse::<f64>().is_ok_and(f64::is_finite) => text.to_string(),
+        _ => json_string(text),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+impl HistoryRecord {
+    pub fn old_text(&self) -> String {
+        format_value(&self.data_type, &self.old_value)
+    }
+
+    pub fn new_text(&self) -> String {
+        format_value(&self.data_type, &self.new_value)
+    }
+
+    pub fn to_line(&self, format: HistoryFormat) -> String {
+        let old = self.old_text();
+        let new = self.new_text();
+        match format {
+            HistoryFormat::JsonLines => format!(
+                "{{\"time\":{:.6},\"target\":{},\"address\":\"0x{:x}\",\"type\":\"{}\",\"old\":{},\"new\":{},\"old_bytes\":\"{}\",\"new_bytes\":\"{}\"}}",
+                unix_seconds(self.time),
+                json_string(&self.name),
+                self.address,
+                data_type_name(&self.data_type),
+                json_value(&self.data_type, &old),
+                json_value(&self.data_type, &new),
+                hex::encode(&self.old_value),
+                hex::encode(&self.new_value)
+            ),
+            HistoryFormat::Csv => [
+                format!("{:.6}", unix_seconds(self.time)),
+                csv_field(&self.name),
+                format!("0x{:x}", self.address),
+                data_type_name(&self.data_type),
+                csv_field(&old),
+                csv_field(&new),
+                hex::encode(&self.old_value),
+                hex::encode(&self.new_value),
+            ]
+            .join(","),
+        }
+    }
+
+    // back from the fields of one line, by name
+    fn from_fields(fields: &HashMap<String, String>) -> Result<Self, String> {
+        let field = |name: &str| {
+            fields
+                .get(name)
+                .map(|v| v.as_str())
+                .ok_or_else(|| format!("missing '{}'", name))
+        };
+        let time = field("time")?
+            .parse::<f64>()
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .and_then(|offset| SystemTime::UNIX_EPOCH.checked_add(offset))
+            .ok_or("bad time")?;
+        let address = field("address")?;
+        let address = usize::from_str_radix(address.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("bad address '{}'", address))?;
+        let data_type = parse_data_type(field("type")?).map_err(|e| e.to_string())?;
+        let bytes =
+            |name: &str| hex::decode(field(name)?).map_err(|_| format!("bad hex in '{}'", name));
+
+        Ok(Self {
+            time,
+            name: field("target")?.to_string(),
+            address,
+            data_type,
+            old_value: bytes("old_bytes")?,
+            new_value: bytes("new_bytes")?,
+        })
+    }
+}
+
+impl HistoryQuery {
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        if let Some(target) = &self.target {
+            let by_address = usize::from_str_radix(target.trim_start_matches("0x"), 16)
+                .is_ok_and(|a| target.starts_with("0x") && a == record.address);
+            if !by_address && record.name != *target {
+                return false;
+            }
+        }
+        if self.from.is_some_and(|from| record.time < from)
+            || self.to.is_some_and(|to| record.time > to)
+        {
+            return false;
+        }
+        match &self.value {
+            Some(value) => {
+                value_equals(&record.old_text(), value) || value_equals(&record.new_text(), value)
+            }
+            None => true,
+        }
+    }
+}
+
+// numbers compare as numbers ("5" finds 5.0), everything else as text
+fn value_equals(text: &str, wanted: &str) -> bool {
+    match (text.parse::<f64>(), wanted.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => text == wanted,
+    }
+}
+
+// read a whole recording, either format
+pub fn load_history(path: &Path) -> Result<Vec<HistoryRecord>, MemscanError> {
+    let file = File::open(path).map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+    let mut records = Vec::new();
+
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+        let line = line.trim();
+        if line.is_empty() || line == CSV_HEADER {
+            continue;
+        }
+        let fields = if line.starts_with('{') {
+            parse_json_object(line)
+        } else {
+            parse_csv_line(line)
+        };
+        let record = fields.and_then(|fields| HistoryRecord::from_fields(&fields));
+        match record {
+            Ok(record) => records.push(record),
+            Err(reason) => {
+                return Err(MemscanError::invalid_data(format!(
+                    "{} line {}: {}",
+                    path.display(),
+                    number + 1,
+                    reason
+                )));
+            }
+        }
+    }
+    Ok(records)
+}
+
+// a flat object of strings and numbers, which is all a recording holds
+fn parse_json_object(line: &str) -> Result<HashMap<String, String>, String> {
+    let mut chars = line.trim().chars().peekable();
+    let mut fields = HashMap::new();
+    if chars.next() != Some('{') {
+        return Err("expected '{'".into());
+    }
+
+    let skip_spaces = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    };
+    let string = |chars: &mut std::iter::Peekable<std::str::Chars>| -> Result<String, String> {
+        let mut out = String::new();
+        loop {
+            match chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(out),
+                '\\' => match chars.next().ok_or("unterminated string")? {
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'u' => {
+                        let code: String = chars.by_ref().take(4).collect();
+                        let code = u32::from_str_radix(&code, 16).map_err(|_| "bad \\u escape")?;
+                        out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    };
+
+    loop {
+        skip_spaces(&mut chars);
+        match chars.next() {
+            Some('}') => return Ok(fields),
+            Some('"') => {}
+            _ => return Err("expected a key".into()),
+        }
+        let key = string(&mut chars)?;
+        skip_spaces(&mut chars);
+        if chars.next() != Some(':') {
+            return Err(format!("expected ':' after '{}'", key));
+        }
+        skip_spaces(&mut chars);
+        let value = if chars.peek() == Some(&'"') {
+            chars.next();
+            string(&mut chars)?
+        } else {
+            let mut raw = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c == '}' || c.is_whitespace() {
+                    break;
+                }
+                raw.push(c);
+                chars.next();
+            }
+            raw
+        };
+        fields.insert(key, value);
+        skip_spaces(&mut chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => return Ok(fields),
+            _ => return Err("expected ',' or '}'".into()),
+        }
+    }
+}
+
+fn parse_csv_line(line: &str) -> Result<HashMap<String, String>, String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => values.push(std::mem::take(&mut current)),
+            (c, _) => current.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".into());
+    }
+    values.push(current);
+
+    let names: Vec<&str> = CSV_HEADER.split(',').collect();
+    if values.len() != names.len() {
+        return Err(format!(
+            "expected {} columns, got {}",
+            names.len(),
+            values.len()
+        ));
+    }
+    Ok(names.into_iter().map(str::to_string).zip(values).collect())
+}
+
+impl HistoryRecorder {
+    // append every change the monitor sees from now on to path. the file is
+    // flushed after each record so a crash loses nothing
+    pub fn start(monitor: &MemoryMonitor, path: &Path) -> Result<Self, MemscanError> {
+        let format = HistoryFormat::from_path(path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+        let empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        let mut writer = BufWriter::new(file);
+        if format == HistoryFormat::Csv && empty {
+            writeln!(writer, "{}", CSV_HEADER)
+                .and_then(|_| writer.flush())
+                .map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+        }
+
+        let records = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(AtomicBool::new(false));
+        let error = Arc::new(Mutex::new(None));
+        let events = monitor.subscribe();
+
+        let thread = {
+            let (records, stop, finished, error) = (
+                Arc::clone(&records),
+                Arc::clone(&stop),
+                Arc::clone(&finished),
+                Arc::clone(&error),
+            );
+            thread::spawn(move || {
+                if let Err(e) = Self::record_loop(events, writer, format, &records, &stop) {
+                    *error.lock().unwrap() = Some(e.to_string());
+                }
+                finished.store(true, Ordering::Relaxed);
+            })
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            records,
+            stop,
+            finished,
+            error,
+            thread: Some(thread),
+        })
+    }
+
+    fn record_loop(
+        events: Receiver<MonitorEvent>,
+        mut writer: BufWriter<File>,
+        format: HistoryFormat,
+        records: &AtomicU64,
+        stop: &AtomicBool,
+    ) -> std::io::Result<()> {
+        loop {
+            // on stop, write out what's already queued and leave
+            let event = if stop.load(Ordering::Relaxed) {
+                match events.try_recv() {
+                    Ok(event) => event,
+                    Err(_) => return Ok(()),
+                }
+            } else {
+                match events.recv_timeout(Duration::from_millis(200)) {
+                    Ok(event) => event,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            };
+
+            match event {
+                MonitorEvent::ValueChanged(change) => {
+                    let line = HistoryRecord::from(&change).to_line(format);
+                    writeln!(writer, "{}", line)?;
+                    writer.flush()?;
+                    records.fetch_add(1, Ordering::Relaxed);
+                }
+                MonitorEvent::Stopped => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> HistoryFormat {
+        self.format
+    }
+
+    pub fn records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    // false once the monitor stopped or writing failed
+    pub fn is_active(&self) -> bool {
+        !self.finished.load(Ordering::Relaxed)
+    }
+
+    // why writing stopped, if it failed
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    // finish writing, returns how many records were written
+    pub fn stop(mut self) -> u64 {
+        self.finish();
+        self.records()
+    }
+
+    fn finish(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for HistoryRecorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        name: &str,
+        data_type: DataType,
+        old_value: &[u8],
+        new_value: &[u8],
+    ) -> HistoryRecord {
+        HistoryRecord {
+            time: SystemTime::UNIX_EPOCH + Duration::from_micros(1_792_367_869_432_317),
+            name: name.to_string(),
+            address: 0x5614ca106028,
+            data_type,
+            old_value: old_value.to_vec(),
+            new_value: new_value.to_vec(),
+        }
+    }
+
+    fn parse(line: &str) -> Result<HistoryRecord, String> {
+        let fields = if line.starts_with('{') {
+            parse_json_object(line)
+        } else {
+            parse_csv_line(line)
+        };
+        fields.and_then(|fields| HistoryRecord::from_fields(&fields))
+    }
+
+    fn assert_round_trip(original: &HistoryRecord) {
+        for format in [HistoryFormat::JsonLines, HistoryFormat::Csv] {
+            let line = original.to_line(format);
+            let parsed = parse(&line).unwrap_or_else(|e| panic!("{}: {}", line, e));
+            // times are written with microseconds, the f64 in between can be
+            // a few nanoseconds off
+            let drift = match parsed.time.duration_since(original.time) {
+                Ok(d) => d,
+                Err(e) => e.duration(),
+            };
+            assert!(drift < Duration::from_micros(1), "{}", line);
+            assert_eq!(parsed.name, original.name, "{}", line);
+            assert_eq!(parsed.address, original.address, "{}", line);
+            assert_eq!(
+                data_type_name(&parsed.data_type),
+                data_type_name(&original.data_type)
+            );
+            assert_eq!(parsed.old_value, original.old_value, "{}", line);
+            assert_eq!(parsed.new_value, original.new_value, "{}", line);
+        }
+    }
+
+    #[test]
+    fn numbers_round_trip() {
+        assert_round_trip(&record(
+            "score",
+            DataType::I32,
+            &999i32.to_le_bytes(),
+            &1000i32.to_le_bytes(),
+        ));
+        assert_round_trip(&record(
+            "speed",
+            DataType::F32,
+            &f32::NAN.to_le_bytes(),
+            &f32::INFINITY.to_le_bytes(),
+        ));
+        assert_round_trip(&record(
+            "gold",
+            DataType::F64,
+            &0.1f64.to_le_bytes(),
+            &(-2.5f64).to_le_bytes(),
+        ));
+    }
+
+    #[test]
+    fn awkward_strings_round_trip() {
+        assert_round_trip(&record(
+            "name, \"quoted\"\n\tand\u{1}",
+            DataType::String(8),
+            b"a,\"b\"\\\n\0\0",
+            b"\xff\xfe\r\n\0",
+        ));
+        assert_round_trip(&record("", DataType::Bytes(3), &[0, 1, 2], &[]));
+    }
+
+    #[test]
+    fn reads_the_documented_line() {
+        let line = r#"{"time":1792367869.432317,"target":"score","address":"0x5614ca106028","type":"i32","old":999,"new":1000,"old_bytes":"e7030000","new_bytes":"e8030000"}"#;
+        let parsed = parse(line).unwrap();
+        assert_eq!(parsed.name, "score");
+        assert_eq!(parsed.new_text(), "1000");
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let good = record("score", DataType::I32, &[1, 0, 0, 0], &[2, 0, 0, 0]);
+        let json = good.to_line(HistoryFormat::JsonLines);
+        let csv = good.to_line(HistoryFormat::Csv);
+
+        let broken = [
+            // cut short anywhere
+            json[..json.len() - 1].to_string(),
+            json[..json.find(':').unwrap()].to_string(),
+            "{\"target\":\"unterminated}".to_string(),
+            "{\"time\" 1}".to_string(),
+            "{\"time\":1,}".to_string(),
+            "{\"bad\\u12\":1}".to_string(),
+            // missing or bad fields
+            json.replace("\"type\":\"i32\",", ""),
+            json.replace("\"i32\"", "\"int\""),
+            json.replace("02000000", "02zz"),
+            json.replace("0x5614ca106028", "nowhere"),
+            // times that don't fit in a SystemTime, or aren't times at all
+            json.replace("1792367869.432317", "-1"),
+            json.replace("1792367869.432317", "1e300"),
+            json.replace("1792367869.432317", "NaN"),
+            json.replace("1792367869.432317", "inf"),
+            csv.replace("1792367869.432317", "1e300"),
+            // wrong column counts and quoting
+            csv.replacen(',', "", 1),
+            format!("{},extra", csv),
+            csv.replace("score", "\"score"),
+        ];
+        for line in &broken {
+            assert!(parse(line).is_err(), "accepted: {}", line);
+        }
+    }
+
+    #[test]
+    fn load_history_names_the_bad_line() {
+        let path =
+            std::env::temp_dir().join(format!("memscan-history-{}.jsonl", std::process::id()));
+        let good = record("score", DataType::I32, &[1, 0, 0, 0], &[2, 0, 0, 0]);
+        let json = good.to_line(HistoryFormat::JsonLines);
+        std::fs::write(
+            &path,
+            format!(
+                "{}\n\n{}\n",
+                json,
+                json.replace("1792367869.432317", "1e300")
+            ),
+        )
+        .unwrap();
+
+        let result = load_history(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(MemscanError::InvalidData { message }) => {
+                assert!(message.contains("line 3"), "{}", message)
+            }
+            other => panic!("expected invalid data, got {:?}", other.map(|r| r.len())),
+        }
+    }
+}
@@ -1,9 +1,29 @@
 // Memscan library - exposing modules for use in binaries and tests
 
+pub mod access;
+pub mod address;
+pub mod coredump;
+pub mod corefile;
+pub mod dwarf;
+pub mod elf;
+pub mod error;
+pub mod history;
 pub mod memory;
 pub mod memory_optimization;
+pub mod modules;
 pub mod monitor;
 pub mod process;
+pub mod range;
 pub mod scanner;
+pub mod session;
+pub mod snapshot;
+pub mod softdirty;
+pub mod source;
+pub mod spawn;
+pub mod stats;
+pub mod symbols;
+pub mod threads;
+pub mod tracer;
+pub mod trigger;
 
 pub mod utils;
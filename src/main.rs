@@ -97,7 +97,7 @@ fn start_gui() -> Result<(), Box<dyn std::error::Error>> {
 
     // Use std::process to run the GUI binary
     let status = process::Command::new("cargo")
-        .args(&["run", "--bin", "memscan-gui"])
+        .args(["run", "--bin", "memscan-gui"])
         .status()?;
 
     if !status.success() {
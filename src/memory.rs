@@ -13,9 +13,10 @@
 //
 // built by the goat (danielcos)
 
+use crate::error::MemscanError;
 use nix::sys::uio::{RemoteIoVec, process_vm_readv, process_vm_writev};
 use nix::unistd::Pid;
-use std::io::{self, IoSlice, IoSliceMut};
+use std::io::{IoSlice, IoSliceMut};
 
 #[derive(Debug, Clone)]
 pub struct MemoryRegion {
@@ -24,6 +25,8 @@ pub struct MemoryRegion {
     pub readable: bool,
     pub writable: bool,
     pub executable: bool,
+    pub offset: u64,
+    pub path: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,7 +42,7 @@ impl MemoryReader {
     }
 
     // read memory from target process
-    pub fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, io::Error> {
+    pub fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
         let mut buffer = vec![0u8; size];
 
         let mut ioslice = IoSliceMut::new(&mut buffer);
@@ -56,11 +59,15 @@ impl MemoryReader {
                 buffer.truncate(bytes_read);
                 Ok(buffer)
             }
-            Err(e) => Err(io::Error::from(e)),
+            Err(e) => Err(MemscanError::from_errno(
+                e,
+                self.pid.as_raw() as u32,
+                Some(address),
+            )),
         }
     }
 
-    pub fn write_memory(&self, address: usize, data: &[u8]) -> Result<usize, io::Error> {
+    pub fn write_memory(&self, address: usize, data: &[u8]) -> Result<usize, MemscanError> {
         let ioslice = IoSlice::new(data);
         let local_iov = std::slice::from_ref(&ioslice);
 
@@ -71,36 +78,40 @@ impl MemoryReader {
 
         match process_vm_writev(self.pid, local_iov, &remote_iov) {
             Ok(bytes_written) => Ok(bytes_written),
-            Err(e) => Err(io::Error::from(e)),
+            Err(e) => Err(MemscanError::from_errno(
+                e,
+                self.pid.as_raw() as u32,
+                Some(address),
+            )),
         }
     }
 
-    pub fn write_i32(&self, address: usize, value: i32) -> Result<usize, io::Error> {
+    pub fn write_i32(&self, address: usize, value: i32) -> Result<usize, MemscanError> {
         let bytes = value.to_le_bytes();
         self.write_memory(address, &bytes)
     }
 
-    pub fn write_i64(&self, address: usize, value: i64) -> Result<usize, io::Error> {
+    pub fn write_i64(&self, address: usize, value: i64) -> Result<usize, MemscanError> {
         let bytes = value.to_le_bytes();
         self.write_memory(address, &bytes)
     }
 
-    pub fn write_f32(&self, address: usize, value: f32) -> Result<usize, io::Error> {
+    pub fn write_f32(&self, address: usize, value: f32) -> Result<usize, MemscanError> {
         let bytes = value.to_le_bytes();
         self.write_memory(address, &bytes)
     }
 
-    pub fn write_f64(&self, address: usize, value: f64) -> Result<usize, io::Error> {
+    pub fn write_f64(&self, address: usize, value: f64) -> Result<usize, MemscanError> {
         let bytes = value.to_le_bytes();
         self.write_memory(address, &bytes)
     }
 
-    pub fn write_string(&self, address: usize, value: &str) -> Result<usize, io::Error> {
+    pub fn write_string(&self, address: usize, value: &str) -> Result<usize, MemscanError> {
         let bytes = value.as_bytes();
         self.write_memory(address, bytes)
     }
 
-    pub fn write_cstring(&self, address: usize, value: &str) -> Result<usize, io::Error> {
+    pub fn write_cstring(&self, address: usize, value: &str) -> Result<usize, MemscanError> {
         let mut bytes = value.as_bytes().to_vec();
         bytes.push(0);
         self.write_memory(address, &bytes)
@@ -111,7 +122,7 @@ impl MemoryReader {
         address: usize,
         size: usize,
         modifier: F,
-    ) -> Result<Vec<u8>, io::Error>
+    ) -> Result<Vec<u8>, MemscanError>
     where
         F: FnOnce(&mut [u8]),
     {
@@ -125,9 +136,45 @@ impl MemoryReader {
     }
 }
 
-pub fn get_memory_regions(pid: u32) -> Result<Vec<MemoryRegion>, io::Error> {
+// unreadable memory is skipped a page at a time
+const PAGE_SIZE: usize = 4096;
+
+// reads [address, address + size) through `read`, which may come back short
+// when it runs into a page it can't read. only the pages that really can't
+// be read are zero-filled, returns the data and how many bytes that was
+pub fn read_zero_filled<F>(address: usize, size: usize, mut read: F) -> (Vec<u8>, u64)
+where
+    F: FnMut(usize, usize) -> Result<Vec<u8>, MemscanError>,
+{
+    let end = address + size;
+    let mut data = Vec::with_capacity(size);
+    let mut unreadable = 0u64;
+    let mut cursor = address;
+
+    while cursor < end {
+        match read(cursor, end - cursor) {
+            Ok(chunk) if !chunk.is_empty() => {
+                let len = chunk.len().min(end - cursor);
+                data.extend_from_slice(&chunk[..len]);
+                cursor += len;
+            }
+            _ => {
+                let next = ((cursor / PAGE_SIZE + 1) * PAGE_SIZE).min(end);
+                data.resize(data.len() + (next - cursor), 0);
+                unreadable += (next - cursor) as u64;
+                cursor = next;
+            }
+        }
+    }
+
+    (data, unreadable)
+}
+
+pub fn get_memory_regions(pid: u32) -> Result<Vec<MemoryRegion>, MemscanError> {
     let maps_path = format!("/proc/{}/maps", pid);
-    let maps_content = std::fs::read_to_string(maps_path)?;
+    let maps_content = std::fs::read_to_string(maps_path).map_err(|e| {
+        MemscanError::from_proc_io(e, pid, format!("Reading memory map of {}", pid))
+    })?;
 
     let mut regions = Vec::new();
 
@@ -156,15 +203,28 @@ fn parse_maps_line(line: &str) -> Option<MemoryRegion> {
 
     // parse permissions (e.g., "rwxp")
     let perms = parts[1];
-    let readable = perms.chars().nth(0) == Some('r');
+    let readable = perms.starts_with('r');
     let writable = perms.chars().nth(1) == Some('w');
     let executable = perms.chars().nth(2) == Some('x');
 
+    // file offset and backing path (pseudo paths like [heap] are kept as-is)
+    let offset = parts
+        .get(2)
+        .and_then(|o| u64::from_str_radix(o, 16).ok())
+        .unwrap_or(0);
+    let path = if parts.len() > 5 {
+        Some(parts[5..].join(" "))
+    } else {
+        None
+    };
+
     Some(MemoryRegion {
         start_address: start,
         size: end - start,
         readable,
         writable,
         executable,
+        offset,
+        path,
     })
 }
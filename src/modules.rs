@@ -0,0 +1,179 @@
+// loaded modules: the executable and shared libraries of a process
+// built by the goat (danielscos)
+//
+// file-backed regions are grouped per path. the load bias comes from the ELF
+// header mapped at the start of each module, section headers aren't loaded so
+// they're read from the file on disk and shifted by the bias
+
+use crate::elf::{self, ElfFile, ElfHeader, PHDR_SIZE, ProgramHeader, SHF_ALLOC};
+use crate::error::MemscanError;
+use crate::memory::MemoryRegion;
+use crate::source::MemorySource;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub name: String,
+    // runtime address, 0 for sections that aren't loaded
+    pub address: usize,
+    pub size: usize,
+    pub flags: u64,
+}
+
+impl Section {
+    pub fn is_loaded(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    // as the process sees it
+    pub path: String,
+    // where we open it, see MemorySource::file_path
+    pub file: PathBuf,
+    pub start: usize,
+    pub end: usize,
+    pub regions: Vec<MemoryRegion>,
+    // runtime address minus link-time address (0 for non-PIE executables)
+    pub load_bias: usize,
+    pub elf_type: Option<u16>,
+    pub sections: Vec<Section>,
+}
+
+impl Module {
+    pub fn contains(&self, address: usize) -> bool {
+        address >= self.start && address < self.end
+    }
+
+    pub fn size(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    pub fn section_at(&self, address: usize) -> Option<&Section> {
+        self.sections
+            .iter()
+            .filter(|s| s.is_loaded())
+            .find(|s| address >= s.address && address < s.address + s.size)
+    }
+
+    // matches the file name exactly, or without its version suffix
+    // ("libc" and "libc.so" both find libc.so.6)
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name
+            || self.path == name
+            || self
+                .name
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('-'))
+    }
+}
+
+pub fn list_modules(source: &dyn MemorySource) -> Result<Vec<Module>, MemscanError> {
+    let regions = source.get_memory_regions()?;
+    let mut modules: Vec<Module> = Vec::new();
+
+    for region in regions {
+        let path = match &region.path {
+            Some(p) if p.starts_with('/') => p.clone(),
+            _ => continue,
+        };
+
+        match modules.iter_mut().find(|m| m.path == path) {
+            Some(module) => {
+                module.start = module.start.min(region.start_address);
+                module.end = module.end.max(region.start_address + region.size);
+                module.regions.push(region);
+            }
+            None => modules.push(Module {
+                name: path.rsplit('/').next().unwrap_or(&path).to_string(),
+                start: region.start_address,
+                end: region.start_address + region.size,
+                file: source.file_path(&path),
+                path,
+                regions: vec![region],
+                load_bias: 0,
+                elf_type: None,
+                sections: Vec::new(),
+            }),
+        }
+    }
+
+    // plain data files mapped into memory aren't modules
+    modules.retain_mut(|module| load_elf_layout(source, module));
+    modules.sort_by_key(|m| m.start);
+
+    Ok(modules)
+}
+
+pub fn find_module<'a>(modules: &'a [Module], name: &str) -> Option<&'a Module> {
+    modules.iter().find(|m| m.matches(name))
+}
+
+pub fn module_at(modules: &[Module], address: usize) -> Option<&Module> {
+    modules.iter().find(|m| m.contains(address))
+}
+
+// fill in the bias and sections, false if this isn't an ELF module
+fn load_elf_layout(source: &dyn MemorySource, module: &mut Module) -> bool {
+    let header_region = match module.regions.iter().find(|r| r.offset == 0) {
+        Some(r) => r.start_address,
+        None => return false,
+    };
+
+    let file = ElfFile::open(&module.file).ok();
+
+    // prefer the headers in memory, they're what was actually loaded
+    let header = source
+        .read_memory(header_region, elf::EHDR_SIZE)
+        .ok()
+        .and_then(|data| ElfHeader::parse(&data));
+    let program_headers = header.as_ref().and_then(|h| {
+        // the header comes from the target, don't trust its offset
+        let table_address = header_region.checked_add(usize::try_from(h.e_phoff).ok()?)?;
+        let table = source
+            .read_memory(table_address, h.e_phnum as usize * PHDR_SIZE)
+            .ok()?;
+        // the table starts at 0 of what we read
+        let in_table = ElfHeader {
+            e_phoff: 0,
+            ..h.clone()
+        };
+        Some(ProgramHeader::parse_table(&table, &in_table))
+    });
+
+    let (elf_type, program_headers) = match (header, program_headers, &file) {
+        (Some(h), Some(phdrs), _) if !phdrs.is_empty() => (h.e_type, phdrs),
+        (_, _, Some(file)) => (file.header.e_type, file.program_headers.clone()),
+        _ => return false,
+    };
+
+    let link_base = elf::link_base(&program_headers).unwrap_or(0) as usize;
+    module.load_bias = header_region.wrapping_sub(link_base);
+    module.elf_type = Some(elf_type);
+
+    if let Some(file) = file {
+        module.sections = file
+            .sections
+            .iter()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, sh)| Section {
+                name: name.clone(),
+                address: if sh.sh_flags & SHF_ALLOC != 0 {
+                    module.load_bias.wrapping_add(sh.sh_addr as usize)
+                } else {
+                    0
+                },
+                size: sh.sh_size as usize,
+                flags: sh.sh_flags,
+            })
+            .collect();
+    }
+
+    true
+}
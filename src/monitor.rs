@@ -1,4 +1,7 @@
 // real time memory monitoring with live notifications
+//
+// the monitor thread doesn't print anything, it publishes MonitorEvents to
+// whoever subscribed (the CLI's live notifications are one subscriber)
 // built by:
 // =================================================================================================
 // =================================================================================================
@@ -18,23 +21,47 @@
 //=================================================================================================
 // =================================================================================================
 
+use crate::address::PointerChain;
+use crate::error::MemscanError;
 use crate::process::ProcessHandle;
-use std::collections::HashMap;
+use crate::range::{MAX_RANGE, RangeState};
+use crate::stats::TargetStats;
+use crate::trigger::{Trigger, TriggerAction, TriggerCondition, TriggerHit};
+use std::collections::BTreeMap;
 use std::sync::{
     Arc, Mutex,
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    mpsc::{Receiver, Sender, channel},
 };
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug, Clone)]
 pub struct MonitorTarget {
+    // targets are told apart by id, the same address can be watched as
+    // several types at once
+    pub id: usize,
     pub address: usize,
     pub data_type: DataType,
     pub name: String,
     pub last_value: Option<Vec<u8>>,
     pub change_count: u64,
     pub last_changed: Option<Instant>,
+    // why the last read failed, cleared by the next good one
+    pub last_error: Option<String>,
+    pub triggers: Vec<Trigger>,
+    pub stats: TargetStats,
+    // poll this target at its own pace instead of the monitor's interval
+    pub interval: Option<Duration>,
+    pub last_polled: Option<Instant>,
+    // targets in a group are enabled and disabled together
+    pub group: Option<String>,
+    // disabled targets stay in the list but aren't read
+    pub enabled: bool,
+    // set for DataType::Bytes targets, which report the parts that changed
+    pub range: Option<RangeState>,
+    // followed again before every read, address is where it pointed last
+    pub chain: Option<PointerChain>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,123 +71,499 @@ pub enum DataType {
     F32,
     F64,
     String(usize),
+    // a raw block, see range.rs
+    Bytes(usize),
 }
 
 #[derive(Debug, Clone)]
 pub struct MonitorChange {
+    pub target_id: usize,
     pub address: usize,
     pub name: String,
     pub old_value: Vec<u8>,
     pub new_value: Vec<u8>,
     pub timestamp: Instant,
+    // wall clock time of the same moment, for recordings
+    pub time: SystemTime,
     pub data_type: DataType,
 }
 
+// the monitored process went away, the monitor stops itself after this
+#[derive(Debug, Clone)]
+pub struct MonitorExit {
+    pub pid: u32,
+    // the PID was reused (or the process exec'd something else)
+    pub replaced: bool,
+    pub timestamp: Instant,
+}
+
+// what subscribers get, in the order it happened
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    ValueChanged(MonitorChange),
+    // sent once when a target stops being readable, not on every tick
+    ReadFailed {
+        target_id: usize,
+        address: usize,
+        name: String,
+        error: String,
+    },
+    // a pointer chain target now points somewhere else
+    AddressChanged {
+        target_id: usize,
+        name: String,
+        old_address: usize,
+        new_address: usize,
+    },
+    TargetAdded(MonitorTarget),
+    TargetRemoved(MonitorTarget),
+    Triggered(TriggerHit),
+    ProcessExited(MonitorExit),
+    // always the last event, the channel is closed after it
+    Stopped,
+}
+
+type Subscribers = Arc<Mutex<Vec<Sender<MonitorEvent>>>>;
+
+// what the monitor loop measured about itself
+#[derive(Debug, Clone, Default)]
+pub struct LoopTiming {
+    pub ticks: u64,
+    // the interval the loop aims for: the fastest of the monitor's and the
+    // targets' own intervals
+    pub tick: Duration,
+    // how long reading the due targets took
+    pub last_work: Duration,
+    pub average_work: Duration,
+    pub max_work: Duration,
+    // time between tick starts, what the interval really turned out to be
+    pub average_period: Duration,
+    // ticks whose reads took longer than the tick
+    pub overruns: u64,
+}
+
+impl LoopTiming {
+    pub fn keeping_up(&self) -> bool {
+        self.average_work < self.tick
+    }
+
+    // moving average, recent ticks count the most
+    fn smooth(average: Duration, sample: Duration, ticks: u64) -> Duration {
+        if ticks <= 1 {
+            sample
+        } else {
+            average.mul_f64(0.9) + sample.mul_f64(0.1)
+        }
+    }
+}
+
+// longest nap between checks for stop and interval changes
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+// what the monitor thread shares with the MemoryMonitor that started it
+struct LoopState {
+    targets: Arc<Mutex<BTreeMap<usize, MonitorTarget>>>,
+    changes: Arc<Mutex<Vec<MonitorChange>>>,
+    exit: Arc<Mutex<Option<MonitorExit>>>,
+    running: Arc<AtomicBool>,
+    subscribers: Subscribers,
+    markers: Arc<Mutex<Vec<TriggerHit>>>,
+    update_interval: Arc<AtomicU64>,
+    timing: Arc<Mutex<LoopTiming>>,
+    max_changes: usize,
+}
+
 pub struct MemoryMonitor {
-    targets: Arc<Mutex<HashMap<usize, MonitorTarget>>>,
+    targets: Arc<Mutex<BTreeMap<usize, MonitorTarget>>>,
     changes: Arc<Mutex<Vec<MonitorChange>>>,
+    exit: Arc<Mutex<Option<MonitorExit>>>,
     running: Arc<AtomicBool>,
-    update_interval: Duration,
+    subscribers: Subscribers,
+    // hits of 'mark' triggers
+    markers: Arc<Mutex<Vec<TriggerHit>>>,
+    next_target_id: AtomicUsize,
+    next_trigger_id: AtomicUsize,
+    // milliseconds, the loop picks up changes on its next tick
+    update_interval: Arc<AtomicU64>,
+    timing: Arc<Mutex<LoopTiming>>,
     max_changes_history: usize,
-    live_mode: Arc<AtomicBool>,
+}
+
+// send to every subscriber, forgetting the ones that dropped their receiver
+fn publish(subscribers: &Subscribers, event: MonitorEvent) {
+    subscribers
+        .lock()
+        .unwrap()
+        .retain(|sender| sender.send(event.clone()).is_ok());
+}
+
+// "i32", "f64", "string", "string:64" (1-1024 bytes), "bytes:4096"
+pub fn parse_data_type(name: &str) -> Result<DataType, MemscanError> {
+    match name {
+        "i32" => Ok(DataType::I32),
+        "i64" => Ok(DataType::I64),
+        "f32" => Ok(DataType::F32),
+        "f64" => Ok(DataType::F64),
+        "string" => Ok(DataType::String(256)),
+        _ => match name
+            .strip_prefix("string:")
+            .map(|size| size.parse::<usize>())
+        {
+            Some(Ok(size)) if (1..=1024).contains(&size) => Ok(DataType::String(size)),
+            Some(_) => Err(MemscanError::invalid_value(
+                name,
+                "a string size between 1 and 1024",
+            )),
+            None => match name
+                .strip_prefix("bytes:")
+                .map(|size| size.parse::<usize>())
+            {
+                Some(Ok(size)) if (1..=MAX_RANGE).contains(&size) => Ok(DataType::Bytes(size)),
+                Some(_) => Err(MemscanError::invalid_value(
+                    name,
+                    format!("a range size between 1 and {}", MAX_RANGE),
+                )),
+                None => Err(MemscanError::InvalidType {
+                    name: name.to_string(),
+                }),
+            },
+        },
+    }
+}
+
+// the other way around, parse_data_type(&data_type_name(t)) gives t back
+pub fn data_type_name(data_type: &DataType) -> String {
+    match data_type {
+        DataType::I32 => "i32".to_string(),
+        DataType::I64 => "i64".to_string(),
+        DataType::F32 => "f32".to_string(),
+        DataType::F64 => "f64".to_string(),
+        DataType::String(size) => format!("string:{}", size),
+        DataType::Bytes(size) => format!("bytes:{}", size),
+    }
+}
+
+// a value as text: numbers as numbers, strings up to the NUL with anything
+// unprintable dropped
+pub fn format_value(data_type: &DataType, bytes: &[u8]) -> String {
+    let four = bytes.get(..4).and_then(|b| <[u8; 4]>::try_from(b).ok());
+    let eight = bytes.get(..8).and_then(|b| <[u8; 8]>::try_from(b).ok());
+    let text = match data_type {
+        DataType::I32 => four.map(|b| i32::from_le_bytes(b).to_string()),
+        DataType::I64 => eight.map(|b| i64::from_le_bytes(b).to_string()),
+        DataType::F32 => four.map(|b| f32::from_le_bytes(b).to_string()),
+        DataType::F64 => eight.map(|b| f64::from_le_bytes(b).to_string()),
+        DataType::String(_) => Some(
+            bytes
+                .iter()
+                .take_while(|&&b| b != 0)
+                .filter(|&&b| b.is_ascii_graphic() || b == b' ')
+                .map(|&b| b as char)
+                .collect(),
+        ),
+        DataType::Bytes(_) => None,
+    };
+    text.unwrap_or_else(|| hex::encode(bytes))
+}
+
+impl MonitorTarget {
+    // the type changes are reported as: a range's interpretation, or the
+    // target's own type
+    pub fn value_type(&self) -> &DataType {
+        self.range
+            .as_ref()
+            .and_then(|range| range.interpret.as_ref())
+            .unwrap_or(&self.data_type)
+    }
 }
 
 impl MemoryMonitor {
     pub fn new(update_interval_ms: u64) -> Self {
         Self {
-            targets: Arc::new(Mutex::new(HashMap::new())),
+            targets: Arc::new(Mutex::new(BTreeMap::new())),
             changes: Arc::new(Mutex::new(Vec::new())),
+            exit: Arc::new(Mutex::new(None)),
             running: Arc::new(AtomicBool::new(false)),
-            update_interval: Duration::from_millis(update_interval_ms),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            markers: Arc::new(Mutex::new(Vec::new())),
+            next_target_id: AtomicUsize::new(1),
+            next_trigger_id: AtomicUsize::new(1),
+            update_interval: Arc::new(AtomicU64::new(update_interval_ms)),
+            timing: Arc::new(Mutex::new(LoopTiming::default())),
             max_changes_history: 1000,
-            live_mode: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn add_target(&self, address: usize, data_type: DataType, name: String) {
+    // events from now until the monitor stops. subscribe before
+    // start_monitoring to not miss anything
+    pub fn subscribe(&self) -> Receiver<MonitorEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    // returns the new target's id
+    pub fn add_target(&self, address: usize, data_type: DataType, name: String) -> usize {
+        let id = self.next_target_id.fetch_add(1, Ordering::Relaxed);
         let target = MonitorTarget {
+            id,
             address,
-            data_type,
             name: name.clone(),
             last_value: None,
             change_count: 0,
             last_changed: None,
+            last_error: None,
+            triggers: Vec::new(),
+            stats: TargetStats::default(),
+            interval: None,
+            last_polled: None,
+            group: None,
+            enabled: true,
+            range: matches!(data_type, DataType::Bytes(_)).then(RangeState::default),
+            chain: None,
+            data_type,
         };
 
+        self.targets.lock().unwrap().insert(id, target.clone());
+        publish(&self.subscribers, MonitorEvent::TargetAdded(target));
+        id
+    }
+
+    // the removed target, None if there's no target with that id
+    pub fn remove_target(&self, id: usize) -> Option<MonitorTarget> {
+        let target = self.targets.lock().unwrap().remove(&id)?;
+        publish(
+            &self.subscribers,
+            MonitorEvent::TargetRemoved(target.clone()),
+        );
+        Some(target)
+    }
+
+    // every target watching this address, whatever its type
+    pub fn targets_at(&self, address: usize) -> Vec<MonitorTarget> {
+        let targets = self.targets.lock().unwrap();
+        targets
+            .values()
+            .filter(|t| t.address == address)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_target(&self, id: usize) -> Option<MonitorTarget> {
+        self.targets.lock().unwrap().get(&id).cloned()
+    }
+
+    // None takes the target out of its group
+    pub fn set_group(&self, id: usize, group: Option<String>) -> Result<(), MemscanError> {
+        let mut targets = self.targets.lock().unwrap();
+        Self::target_mut(&mut targets, id)?.group = group;
+        Ok(())
+    }
+
+    pub fn set_enabled(&self, id: usize, enabled: bool) -> Result<(), MemscanError> {
+        let mut targets = self.targets.lock().unwrap();
+        let target = Self::target_mut(&mut targets, id)?;
+        // enabling an enabled target must not throw away its last value
+        if target.enabled == enabled {
+            return Ok(());
+        }
+        target.enabled = enabled;
+        // a re-enabled target starts from a fresh read instead of reporting
+        // everything that happened while it was off as one change
+        target.last_value = None;
+        target.last_polled = None;
+        Ok(())
+    }
+
+    // returns how many targets are in the group
+    pub fn set_group_enabled(&self, group: &str, enabled: bool) -> usize {
+        let ids: Vec<usize> = self
+            .targets
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|t| t.group.as_deref() == Some(group))
+            .map(|t| t.id)
+            .collect();
+        for &id in &ids {
+            let _ = self.set_enabled(id, enabled);
+        }
+        ids.len()
+    }
+
+    // (group, targets, enabled targets), sorted by name
+    pub fn groups(&self) -> Vec<(String, usize, usize)> {
+        let mut groups: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for target in self.targets.lock().unwrap().values() {
+            if let Some(group) = &target.group {
+                let entry = groups.entry(group.clone()).or_default();
+                entry.0 += 1;
+                if target.enabled {
+                    entry.1 += 1;
+                }
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(group, (total, enabled))| (group, total, enabled))
+            .collect()
+    }
+
+    fn target_mut(
+        targets: &mut BTreeMap<usize, MonitorTarget>,
+        id: usize,
+    ) -> Result<&mut MonitorTarget, MemscanError> {
+        targets
+            .get_mut(&id)
+            .ok_or_else(|| MemscanError::not_found(format!("Watch target #{}", id)))
+    }
+
+    // a chain makes the target follow pointers on every read, None pins it
+    // to the address it has now
+    pub fn set_pointer_chain(
+        &self,
+        id: usize,
+        chain: Option<PointerChain>,
+    ) -> Result<(), MemscanError> {
         let mut targets = self.targets.lock().unwrap();
-        targets.insert(address, target);
-        println!("  Added monitoring target: {} at 0x{:x}", name, address);
+        Self::target_mut(&mut targets, id)?.chain = chain;
+        Ok(())
     }
 
-    pub fn remove_target(&self, address: usize) {
+    // read the changed parts of a range target as this type, None goes back
+    // to raw runs of bytes. starts the hot spots over
+    pub fn set_range_type(
+        &self,
+        id: usize,
+        interpret: Option<DataType>,
+    ) -> Result<(), MemscanError> {
         let mut targets = self.targets.lock().unwrap();
-        if let Some(target) = targets.remove(&address) {
-            println!(
-                "  Removed monitoring target: {} at 0x{:x}",
-                target.name, address
-            );
+        let target = Self::target_mut(&mut targets, id)?;
+        let size = match target.data_type {
+            DataType::Bytes(size) => size,
+            _ => {
+                return Err(MemscanError::invalid_state(format!(
+                    "#{} {} is not a range target",
+                    id, target.name
+                )));
+            }
+        };
+        if let Some(data_type) = &interpret {
+            RangeState::check_interpret(data_type, size)?;
         }
+        let range = target.range.get_or_insert_with(RangeState::default);
+        range.interpret = interpret;
+        range.clear();
+        Ok(())
     }
 
-    pub fn start_monitoring(&self, process_handle: Arc<ProcessHandle>) -> Result<(), String> {
+    // returns the new trigger's id
+    pub fn add_trigger(
+        &self,
+        target_id: usize,
+        condition: TriggerCondition,
+        action: TriggerAction,
+    ) -> Result<usize, MemscanError> {
+        let mut targets = self.targets.lock().unwrap();
+        let target = Self::target_mut(&mut targets, target_id)?;
+        condition.check_type(target.value_type())?;
+
+        let id = self.next_trigger_id.fetch_add(1, Ordering::Relaxed);
+        target.triggers.push(Trigger::new(id, condition, action));
+        Ok(id)
+    }
+
+    pub fn remove_trigger(&self, id: usize) -> Option<Trigger> {
+        let mut targets = self.targets.lock().unwrap();
+        targets.values_mut().find_map(|target| {
+            let index = target.triggers.iter().position(|t| t.id == id)?;
+            Some(target.triggers.remove(index))
+        })
+    }
+
+    // takes effect on the next tick, no restart needed
+    pub fn set_interval(&self, interval_ms: u64) {
+        self.update_interval.store(interval_ms, Ordering::Relaxed);
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.update_interval.load(Ordering::Relaxed))
+    }
+
+    // None goes back to the monitor's interval
+    pub fn set_target_interval(
+        &self,
+        id: usize,
+        interval: Option<Duration>,
+    ) -> Result<(), MemscanError> {
+        let mut targets = self.targets.lock().unwrap();
+        Self::target_mut(&mut targets, id)?.interval = interval;
+        Ok(())
+    }
+
+    pub fn loop_timing(&self) -> LoopTiming {
+        self.timing.lock().unwrap().clone()
+    }
+
+    pub fn get_markers(&self) -> Vec<TriggerHit> {
+        self.markers.lock().unwrap().clone()
+    }
+
+    pub fn start_monitoring(&self, process_handle: Arc<ProcessHandle>) -> Result<(), MemscanError> {
         if self.running.load(Ordering::Relaxed) {
-            return Err("Monitor is already running".to_string());
+            return Err(MemscanError::invalid_state("Monitor is already running"));
         }
 
         self.running.store(true, Ordering::Relaxed);
-        println!(" Starting real time monitoring");
-
-        let targets_clone = Arc::clone(&self.targets);
-        let changes_clone = Arc::clone(&self.changes);
-        let running_clone = Arc::clone(&self.running);
-        let live_mode_clone = Arc::clone(&self.live_mode);
-        let update_interval = self.update_interval;
-        let max_changes = self.max_changes_history;
+        *self.exit.lock().unwrap() = None;
+
+        let state = LoopState {
+            targets: Arc::clone(&self.targets),
+            changes: Arc::clone(&self.changes),
+            exit: Arc::clone(&self.exit),
+            running: Arc::clone(&self.running),
+            subscribers: Arc::clone(&self.subscribers),
+            markers: Arc::clone(&self.markers),
+            update_interval: Arc::clone(&self.update_interval),
+            timing: Arc::clone(&self.timing),
+            max_changes: self.max_changes_history,
+        };
 
         // Start main monitoring thread
-        thread::spawn(move || {
-            Self::monitor_loop(
-                process_handle,
-                targets_clone,
-                changes_clone,
-                running_clone,
-                live_mode_clone,
-                update_interval,
-                max_changes,
-            )
-        });
+        thread::spawn(move || Self::monitor_loop(process_handle, state));
 
         Ok(())
     }
 
+    // the thread notices on its next tick and sends Stopped
     pub fn stop_monitoring(&self) {
         self.running.store(false, Ordering::Relaxed);
-        self.live_mode.store(false, Ordering::Relaxed);
-        println!("⏹️  Stopping memory monitoring");
     }
 
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
     }
 
-    pub fn enable_live_notifications(&self) {
-        self.live_mode.store(true, Ordering::Relaxed);
-        println!("🔴 Live notifications enabled - changes will appear automatically");
+    pub fn get_recent_changes(&self, count: usize) -> Vec<MonitorChange> {
+        let changes = self.changes.lock().unwrap();
+        let start_idx = changes.len().saturating_sub(count);
+        changes[start_idx..].to_vec()
     }
 
-    pub fn disable_live_notifications(&self) {
-        self.live_mode.store(false, Ordering::Relaxed);
-        println!("⚫ Live notifications disabled");
+    // set once the monitored process has exited
+    pub fn exit_event(&self) -> Option<MonitorExit> {
+        self.exit.lock().unwrap().clone()
     }
 
-    pub fn is_live_mode(&self) -> bool {
-        self.live_mode.load(Ordering::Relaxed)
+    pub fn get_targets(&self) -> Vec<MonitorTarget> {
+        let targets = self.targets.lock().unwrap();
+        targets.values().cloned().collect()
     }
 
-    pub fn get_recent_changes(&self, count: usize) -> Vec<MonitorChange> {
-        let changes = self.changes.lock().unwrap();
-        let start_idx = changes.len().saturating_sub(count);
-        changes[start_idx..].to_vec()
+    // None if there's no target with that id
+    pub fn get_target_stats(&self, id: usize) -> Option<TargetStats> {
+        let targets = self.targets.lock().unwrap();
+        targets.get(&id).map(|t| t.stats.clone())
     }
 
     pub fn get_targets_status(&self) -> Vec<(usize, String, u64, Option<Instant>)> {
@@ -171,176 +574,255 @@ impl MemoryMonitor {
             .collect()
     }
 
-    fn display_change_notification(change: &MonitorChange, live_mode: &Arc<AtomicBool>) {
-        if !live_mode.load(Ordering::Relaxed) {
-            return;
-        }
-
-        let elapsed = change.timestamp.elapsed();
-        println!(
-            "\n🔄 CHANGE DETECTED: {} (0x{:x}) - {:.1}s ago",
-            change.name,
-            change.address,
-            elapsed.as_secs_f64()
-        );
+    fn monitor_loop(process_handle: Arc<ProcessHandle>, state: LoopState) {
+        let LoopState {
+            targets,
+            changes,
+            exit,
+            running,
+            subscribers,
+            markers,
+            update_interval,
+            timing,
+            max_changes,
+        } = state;
+        let mut last_tick: Option<Instant> = None;
 
-        // Display based on the actual data type stored with the target
-        match change.data_type {
-            DataType::I32 => {
-                let old_i32 =
-                    i32::from_le_bytes(change.old_value[..4].try_into().unwrap_or([0; 4]));
-                let new_i32 =
-                    i32::from_le_bytes(change.new_value[..4].try_into().unwrap_or([0; 4]));
-                println!("   📊 {} → {} (i32)", old_i32, new_i32);
-            }
-            DataType::I64 => {
-                let old_i64 =
-                    i64::from_le_bytes(change.old_value[..8].try_into().unwrap_or([0; 8]));
-                let new_i64 =
-                    i64::from_le_bytes(change.new_value[..8].try_into().unwrap_or([0; 8]));
-                println!("   📊 {} → {} (i64)", old_i64, new_i64);
-            }
-            DataType::F32 => {
-                let old_f32 =
-                    f32::from_le_bytes(change.old_value[..4].try_into().unwrap_or([0; 4]));
-                let new_f32 =
-                    f32::from_le_bytes(change.new_value[..4].try_into().unwrap_or([0; 4]));
-                println!("   📊 {:.3} → {:.3} (f32)", old_f32, new_f32);
-            }
-            DataType::F64 => {
-                let old_f64 =
-                    f64::from_le_bytes(change.old_value[..8].try_into().unwrap_or([0; 8]));
-                let new_f64 =
-                    f64::from_le_bytes(change.new_value[..8].try_into().unwrap_or([0; 8]));
-                println!("   📊 {:.6} → {:.6} (f64)", old_f64, new_f64);
-            }
-            DataType::String(_) => {
-                // Display as string
-                Self::display_string_change(change);
-            }
-        }
-        print!("memscan> "); // Re-display prompt
-        use std::io::Write;
-        std::io::stdout().flush().unwrap();
-    }
-
-    fn display_string_change(change: &MonitorChange) {
-        // Find the actual null-terminated strings
-        let old_str_end = change
-            .old_value
-            .iter()
-            .position(|&b| b == 0)
-            .unwrap_or(change.old_value.len());
-        let new_str_end = change
-            .new_value
-            .iter()
-            .position(|&b| b == 0)
-            .unwrap_or(change.new_value.len());
-
-        let old_bytes = &change.old_value[..old_str_end];
-        let new_bytes = &change.new_value[..new_str_end];
-
-        // Try to create clean ASCII strings
-        let old_clean: String = old_bytes
-            .iter()
-            .filter(|&&b| b.is_ascii_graphic() || b == b' ')
-            .map(|&b| b as char)
-            .collect();
-        let new_clean: String = new_bytes
-            .iter()
-            .filter(|&&b| b.is_ascii_graphic() || b == b' ')
-            .map(|&b| b as char)
-            .collect();
-
-        // Show the change
-        if !old_clean.is_empty() || !new_clean.is_empty() {
-            println!("   📊 '{}' → '{}'", old_clean, new_clean);
-
-            // Show byte lengths if different
-            if old_str_end != new_str_end {
-                println!("   📏 Length: {} → {} bytes", old_str_end, new_str_end);
-            }
-        } else {
-            // Fallback to hex if no printable characters
-            println!("   📊 {:02x?} → {:02x?}", old_bytes, new_bytes);
-        }
-    }
-
-    fn monitor_loop(
-        process_handle: Arc<ProcessHandle>,
-        targets: Arc<Mutex<HashMap<usize, MonitorTarget>>>,
-        changes: Arc<Mutex<Vec<MonitorChange>>>,
-        running: Arc<AtomicBool>,
-        live_mode: Arc<AtomicBool>,
-        update_interval: Duration,
-        max_changes: usize,
-    ) {
         while running.load(Ordering::Relaxed) {
             let start_time = Instant::now();
+            let interval = Duration::from_millis(update_interval.load(Ordering::Relaxed));
+
+            // reads of a dead (or replaced) process just fail quietly, so
+            // check once per tick and stop instead of polling forever
+            if let Err(e) = process_handle.validate() {
+                let event = MonitorExit {
+                    pid: process_handle.pid(),
+                    replaced: matches!(e, MemscanError::ProcessExited { replaced: true, .. }),
+                    timestamp: Instant::now(),
+                };
+                *exit.lock().unwrap() = Some(event.clone());
+                running.store(false, Ordering::Relaxed);
+                publish(&subscribers, MonitorEvent::ProcessExited(event));
+                break;
+            }
 
             // read all monitored addresses
             {
                 let mut targets_guard = targets.lock().unwrap();
-                let addresses: Vec<usize> = targets_guard.keys().cloned().collect();
-
-                for address in addresses {
-                    if let Some(target) = targets_guard.get_mut(&address) {
-                        // Read with stability checking to avoid race conditions
-                        let stable_value = Self::read_with_stability_check(
-                            &process_handle,
-                            address,
-                            &target.data_type,
-                        );
 
-                        if let Some(current_value) = stable_value {
-                            if let Some(ref last_value) = target.last_value {
-                                // For strings, compare only meaningful content
-                                let values_different = if let DataType::String(_) = target.data_type
-                                {
-                                    Self::string_content_different(last_value, &current_value)
-                                } else {
-                                    current_value != *last_value
-                                };
+                for target in targets_guard.values_mut() {
+                    if !target.enabled {
+                        continue;
+                    }
+                    // targets with a slower interval than the tick sit some out
+                    let due = target.interval.unwrap_or(interval);
+                    if target.last_polled.is_some_and(|t| {
+                        start_time.duration_since(t) + Duration::from_millis(1) < due
+                    }) {
+                        continue;
+                    }
+                    target.last_polled = Some(start_time);
+
+                    // pointer chains are followed again first, the object
+                    // they lead to may have moved since the last read
+                    let resolved = match &target.chain {
+                        Some(chain) => chain.resolve(process_handle.as_ref()),
+                        None => Ok(target.address),
+                    };
+                    if let Ok(new_address) = resolved
+                        && new_address != target.address
+                    {
+                        publish(
+                            &subscribers,
+                            MonitorEvent::AddressChanged {
+                                target_id: target.id,
+                                name: target.name.clone(),
+                                old_address: target.address,
+                                new_address,
+                            },
+                        );
+                        target.address = new_address;
+                    }
+                    let address = target.address;
+
+                    // Read with stability checking to avoid race conditions
+                    let read = resolved.and_then(|_| {
+                        Self::read_with_stability_check(&process_handle, address, &target.data_type)
+                    });
+                    let current_value = match read {
+                        Ok(Some(value)) => value,
+                        // still changing, try again next tick
+                        Ok(None) => continue,
+                        Err(e) => {
+                            if target.last_error.is_none() {
+                                publish(
+                                    &subscribers,
+                                    MonitorEvent::ReadFailed {
+                                        target_id: target.id,
+                                        address,
+                                        name: target.name.clone(),
+                                        error: e.to_string(),
+                                    },
+                                );
+                            }
+                            target.last_error = Some(e.to_string());
+                            continue;
+                        }
+                    };
+                    target.last_error = None;
+
+                    // a range's hot spots stand in for its statistics
+                    if target.last_value.is_none() && target.range.is_none() {
+                        target
+                            .stats
+                            .record(&target.data_type, &current_value, Instant::now());
+                    }
 
-                                if values_different {
-                                    let change = MonitorChange {
+                    if let Some(ref last_value) = target.last_value {
+                        // For strings, compare only meaningful content
+                        let values_different = if let DataType::String(_) = target.data_type {
+                            Self::string_content_different(last_value, &current_value)
+                        } else {
+                            current_value != *last_value
+                        };
+
+                        if values_different {
+                            let timestamp = Instant::now();
+                            let time = SystemTime::now();
+                            target.change_count += 1;
+                            target.last_changed = Some(timestamp);
+
+                            // a range reports every run (or field) that changed
+                            // as a change of its own, named by its offset
+                            let new_changes: Vec<MonitorChange> = match target.range.as_mut() {
+                                Some(range) => range
+                                    .record(last_value, &current_value)
+                                    .into_iter()
+                                    .map(|(offset, len)| MonitorChange {
+                                        target_id: target.id,
+                                        address: address + offset,
+                                        name: format!("{}+0x{:x}", target.name, offset),
+                                        old_value: last_value[offset..offset + len].to_vec(),
+                                        new_value: current_value[offset..offset + len].to_vec(),
+                                        timestamp,
+                                        time,
+                                        data_type: range
+                                            .interpret
+                                            .clone()
+                                            .unwrap_or(DataType::Bytes(len)),
+                                    })
+                                    .collect(),
+                                None => {
+                                    target.stats.record(
+                                        &target.data_type,
+                                        &current_value,
+                                        timestamp,
+                                    );
+                                    vec![MonitorChange {
+                                        target_id: target.id,
                                         address,
                                         name: target.name.clone(),
                                         old_value: last_value.clone(),
                                         new_value: current_value.clone(),
-                                        timestamp: Instant::now(),
+                                        timestamp,
+                                        time,
                                         data_type: target.data_type.clone(),
-                                    };
+                                    }]
+                                }
+                            };
 
-                                    target.change_count += 1;
-                                    target.last_changed = Some(change.timestamp);
+                            for change in new_changes {
+                                {
+                                    let mut changes_guard = changes.lock().unwrap();
+                                    changes_guard.push(change.clone());
 
-                                    // Display change notification if live mode is enabled
-                                    Self::display_change_notification(&change, &live_mode);
+                                    if changes_guard.len() > max_changes {
+                                        changes_guard.remove(0);
+                                    }
+                                }
 
-                                    {
-                                        let mut changes_guard = changes.lock().unwrap();
-                                        changes_guard.push(change.clone());
+                                publish(&subscribers, MonitorEvent::ValueChanged(change.clone()));
 
-                                        if changes_guard.len() > max_changes {
-                                            changes_guard.remove(0);
+                                for trigger in target.triggers.iter_mut() {
+                                    if !trigger.check(&change) {
+                                        continue;
+                                    }
+                                    trigger.hits += 1;
+                                    let hit = TriggerHit {
+                                        trigger_id: trigger.id,
+                                        target_id: target.id,
+                                        address: change.address,
+                                        name: change.name.clone(),
+                                        condition: trigger.condition.clone(),
+                                        action: trigger.action.clone(),
+                                        old_value: change.old_value.clone(),
+                                        new_value: change.new_value.clone(),
+                                        data_type: change.data_type.clone(),
+                                        timestamp: change.timestamp,
+                                        outcome: trigger
+                                            .run_action(&process_handle, trigger.hits)
+                                            .map_err(|e| e.to_string()),
+                                    };
+                                    if trigger.action == TriggerAction::Marker {
+                                        let mut markers_guard = markers.lock().unwrap();
+                                        markers_guard.push(hit.clone());
+                                        if markers_guard.len() > max_changes {
+                                            markers_guard.remove(0);
                                         }
                                     }
+                                    publish(&subscribers, MonitorEvent::Triggered(hit));
                                 }
                             }
-                            target.last_value = Some(current_value);
                         }
                     }
+                    target.last_value = Some(current_value);
                 }
             }
 
-            let elapsed = start_time.elapsed();
-            if elapsed < update_interval {
-                thread::sleep(update_interval - elapsed);
+            let work = start_time.elapsed();
+            let fastest_target = targets
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|t| t.enabled)
+                .filter_map(|t| t.interval)
+                .min();
+            let tick_for =
+                |interval: Duration| fastest_target.map_or(interval, |t| t.min(interval));
+            {
+                let mut timing = timing.lock().unwrap();
+                timing.ticks += 1;
+                timing.tick = tick_for(interval);
+                timing.last_work = work;
+                timing.max_work = timing.max_work.max(work);
+                timing.average_work = LoopTiming::smooth(timing.average_work, work, timing.ticks);
+                if let Some(last_tick) = last_tick {
+                    let period = start_time.duration_since(last_tick);
+                    timing.average_period =
+                        LoopTiming::smooth(timing.average_period, period, timing.ticks - 1);
+                }
+                if work > timing.tick {
+                    timing.overruns += 1;
+                }
+            }
+            last_tick = Some(start_time);
+
+            // sleep in slices so stop and a new interval are picked up quickly
+            while running.load(Ordering::Relaxed) {
+                let tick = tick_for(Duration::from_millis(
+                    update_interval.load(Ordering::Relaxed),
+                ));
+                let elapsed = start_time.elapsed();
+                if elapsed >= tick {
+                    break;
+                }
+                thread::sleep((tick - elapsed).min(SLEEP_SLICE));
             }
         }
 
-        println!("  Memory monitoring stopped");
+        // dropping the senders ends every subscriber's receive loop
+        publish(&subscribers, MonitorEvent::Stopped);
+        subscribers.lock().unwrap().clear();
     }
 
     fn get_data_type_size(data_type: &DataType) -> usize {
@@ -349,7 +831,8 @@ impl MemoryMonitor {
             DataType::I64 => 8,
             DataType::F32 => 4,
             DataType::F64 => 8,
-            DataType::String(_) => 256, // Max read size for strings
+            DataType::String(size) => *size, // Max read size for strings
+            DataType::Bytes(size) => *size,
         }
     }
 
@@ -357,21 +840,15 @@ impl MemoryMonitor {
         process_handle: &Arc<ProcessHandle>,
         address: usize,
         max_size: usize,
-    ) -> (usize, Vec<u8>) {
+    ) -> Result<Vec<u8>, MemscanError> {
         // Read up to max_size bytes
-        if let Ok(data) = process_handle.read_memory(address, max_size) {
-            // Find null terminator
-            if let Some(null_pos) = data.iter().position(|&b| b == 0) {
-                // Return string content + null terminator
-                let actual_size = null_pos + 1;
-                (actual_size, data[..actual_size].to_vec())
-            } else {
-                // No null terminator found, return all data
-                (max_size, data)
-            }
-        } else {
-            // Failed to read, return empty
-            (0, vec![])
+        let data = process_handle.read_memory(address, max_size)?;
+        // Find null terminator
+        match data.iter().position(|&b| b == 0) {
+            // Return string content + null terminator
+            Some(null_pos) => Ok(data[..null_pos + 1].to_vec()),
+            // No null terminator found, return all data
+            None => Ok(data),
         }
     }
 
@@ -379,22 +856,22 @@ impl MemoryMonitor {
         process_handle: &Arc<ProcessHandle>,
         address: usize,
         data_type: &DataType,
-    ) -> Option<Vec<u8>> {
+    ) -> Result<Option<Vec<u8>>, MemscanError> {
         // Read the memory multiple times to ensure stability
         let (first_read, second_read) = match data_type {
-            DataType::String(_) => {
-                let (_, first) = Self::read_adaptive_string(process_handle, address, 256);
+            DataType::String(size) => {
+                let first = Self::read_adaptive_string(process_handle, address, *size)?;
                 // Small delay to let any writes complete
                 std::thread::sleep(std::time::Duration::from_millis(1));
-                let (_, second) = Self::read_adaptive_string(process_handle, address, 256);
+                let second = Self::read_adaptive_string(process_handle, address, *size)?;
                 (first, second)
             }
             _ => {
                 let size = Self::get_data_type_size(data_type);
-                let first = process_handle.read_memory(address, size).ok()?;
+                let first = process_handle.read_memory(address, size)?;
                 // Small delay to let any writes complete
                 std::thread::sleep(std::time::Duration::from_millis(1));
-                let second = process_handle.read_memory(address, size).ok()?;
+                let second = process_handle.read_memory(address, size)?;
                 (first, second)
             }
         };
@@ -406,10 +883,10 @@ impl MemoryMonitor {
         };
 
         if values_stable {
-            Some(second_read)
+            Ok(Some(second_read))
         } else {
             // Value is still changing, skip this update
-            None
+            Ok(None)
         }
     }
 
@@ -1,101 +1,223 @@
 // process management
 // built by the goat (danielscos)
 
+use crate::error::{DenialReason, MemscanError};
 use crate::memory::{MemoryReader, MemoryRegion};
-use std::fmt::{self, Error};
+use crate::modules::{self, Module};
+use crate::source::MemorySource;
+use crate::threads::{self, Registers, ThreadInfo};
+use regex::Regex;
+use std::fmt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+// how long a successful identity check is trusted. a PID can't be recycled
+// that fast, the whole PID space has to wrap first
+const VALIDATE_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Debug, Clone)]
 pub struct Process {
     pub pid: u32,
     pub name: String,
     pub handle: Option<ProcessHandle>,
+    // filled on first use, enumerating stays cheap
+    info: OnceLock<Option<ProcessInfo>>,
 }
 
+// everything /proc knows about a process that's worth showing
 #[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    pub exe: Option<PathBuf>,
+    pub cwd: Option<PathBuf>,
+    pub uid: u32,
+    pub euid: u32,
+    pub gid: u32,
+    pub egid: u32,
+    pub state: char,
+    pub ppid: u32,
+    pub threads: u32,
+    // clock ticks after boot, see started_at()
+    pub start_time: u64,
+    pub vm_rss_kb: Option<u64>,
+    pub vm_size_kb: Option<u64>,
+    pub tracer_pid: u32,
+    // (kind, inode) pairs from /proc/PID/ns, e.g. ("pid", 4026531836)
+    pub namespaces: Vec<(String, u64)>,
+    // NSpid: its PID in each nested pid namespace, ours first and its own
+    // (inside the container) last
+    pub ns_pids: Vec<u32>,
+}
+
+// what makes a process the same process: PIDs get reused, the start time
+// (and the executable, which changes on exec) don't
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessIdentity {
+    // clock ticks after boot
+    pub start_time: u64,
+    // (device, inode) of /proc/PID/exe, None when we can't look at it
+    pub exe: Option<(u64, u64)>,
+}
+
+impl ProcessIdentity {
+    // None when the process is gone, zombies included
+    pub fn read(pid: u32) -> Option<Self> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        if matches!(fields.first(), Some(&"Z") | Some(&"X") | None) {
+            return None;
+        }
+
+        Some(Self {
+            start_time: fields.get(19)?.parse().ok()?,
+            exe: std::fs::metadata(format!("/proc/{}/exe", pid))
+                .ok()
+                .map(|m| (m.dev(), m.ino())),
+        })
+    }
+}
+
+#[derive(Debug)]
 pub struct ProcessHandle {
     pid: u32,
     memory_reader: Option<MemoryReader>,
+    identity: Option<ProcessIdentity>,
+    opened_at: Instant,
+    // ms after opened_at of the last good identity check, +1 (0 = never)
+    validated_at: AtomicU64,
 }
 
-impl ProcessHandle {
-    pub fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.read_memory(address, size)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
+impl Clone for ProcessHandle {
+    fn clone(&self) -> Self {
+        Self {
+            pid: self.pid,
+            memory_reader: self.memory_reader.clone(),
+            identity: self.identity.clone(),
+            opened_at: self.opened_at,
+            validated_at: AtomicU64::new(self.validated_at.load(Ordering::Relaxed)),
         }
     }
+}
 
-    pub fn write_memory(&self, address: usize, data: &[u8]) -> Result<usize, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.write_memory(address, data)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
-        }
+impl ProcessHandle {
+    pub fn pid(&self) -> u32 {
+        self.pid
     }
 
-    pub fn write_i32(&self, address: usize, value: i32) -> Result<usize, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.write_i32(address, value)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
-        }
+    pub fn identity(&self) -> Option<&ProcessIdentity> {
+        self.identity.as_ref()
     }
 
-    pub fn write_i64(&self, address: usize, value: i64) -> Result<usize, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.write_i64(address, value)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
-        }
+    // is this still the process the handle was opened on? checks /proc every
+    // time, the operations below use the cached check()
+    pub fn validate(&self) -> Result<(), MemscanError> {
+        let expected = match &self.identity {
+            Some(identity) => identity,
+            None => return Ok(()),
+        };
+
+        let replaced = match ProcessIdentity::read(self.pid) {
+            None => false,
+            // exe can only be compared when both reads could see it
+            Some(current)
+                if current.start_time == expected.start_time
+                    && (current.exe == expected.exe
+                        || current.exe.is_none()
+                        || expected.exe.is_none()) =>
+            {
+                let elapsed = self.opened_at.elapsed().as_millis() as u64;
+                self.validated_at.store(elapsed + 1, Ordering::Relaxed);
+                return Ok(());
+            }
+            Some(_) => true,
+        };
+
+        Err(MemscanError::ProcessExited {
+            pid: self.pid,
+            replaced,
+        })
     }
 
-    pub fn write_f32(&self, address: usize, value: f32) -> Result<usize, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.write_f32(address, value)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
-        }
+    pub fn is_alive(&self) -> bool {
+        self.validate().is_ok()
     }
 
-    pub fn write_f64(&self, address: usize, value: f64) -> Result<usize, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.write_f64(address, value)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
+    fn check(&self) -> Result<(), MemscanError> {
+        let last = self.validated_at.load(Ordering::Relaxed);
+        let now = self.opened_at.elapsed().as_millis() as u64;
+        if last != 0 && now + 1 - last < VALIDATE_INTERVAL.as_millis() as u64 {
+            return Ok(());
         }
+        self.validate()
     }
 
-    pub fn write_string(&self, address: usize, value: &str) -> Result<usize, std::io::Error> {
-        if let Some(ref reader) = self.memory_reader {
-            reader.write_string(address, value)
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "Process not attached",
-            ))
-        }
+    // run an operation against the process, failures caused by it exiting
+    // come back as ProcessExited instead of whatever the syscall said
+    fn checked<T>(
+        &self,
+        operation: impl FnOnce(&MemoryReader) -> Result<T, MemscanError>,
+    ) -> Result<T, MemscanError> {
+        self.check()?;
+        let reader = self
+            .memory_reader
+            .as_ref()
+            .ok_or_else(|| MemscanError::invalid_state("Process not attached"))?;
+
+        operation(reader).map_err(|e| match self.validate() {
+            Err(exited) => exited,
+            Ok(()) => e,
+        })
+    }
+
+    pub fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        self.checked(|reader| reader.read_memory(address, size))
+    }
+
+    // SIGSTOP / SIGCONT, only after making sure the PID wasn't reused
+    pub fn pause(&self) -> Result<(), MemscanError> {
+        self.signal(nix::sys::signal::Signal::SIGSTOP)
     }
 
-    pub fn is_writable(&self, address: usize) -> Result<bool, std::io::Error> {
+    pub fn resume(&self) -> Result<(), MemscanError> {
+        self.signal(nix::sys::signal::Signal::SIGCONT)
+    }
+
+    fn signal(&self, signal: nix::sys::signal::Signal) -> Result<(), MemscanError> {
+        self.validate()?;
+        nix::sys::signal::kill(nix::unistd::Pid::from_raw(self.pid as i32), signal)
+            .map_err(|e| MemscanError::from_errno(e, self.pid, None))
+    }
+
+    pub fn write_memory(&self, address: usize, data: &[u8]) -> Result<usize, MemscanError> {
+        self.checked(|reader| reader.write_memory(address, data))
+    }
+
+    pub fn write_i32(&self, address: usize, value: i32) -> Result<usize, MemscanError> {
+        self.checked(|reader| reader.write_i32(address, value))
+    }
+
+    pub fn write_i64(&self, address: usize, value: i64) -> Result<usize, MemscanError> {
+        self.checked(|reader| reader.write_i64(address, value))
+    }
+
+    pub fn write_f32(&self, address: usize, value: f32) -> Result<usize, MemscanError> {
+        self.checked(|reader| reader.write_f32(address, value))
+    }
+
+    pub fn write_f64(&self, address: usize, value: f64) -> Result<usize, MemscanError> {
+        self.checked(|reader| reader.write_f64(address, value))
+    }
+
+    pub fn write_string(&self, address: usize, value: &str) -> Result<usize, MemscanError> {
+        self.checked(|reader| reader.write_string(address, value))
+    }
+
+    pub fn is_writable(&self, address: usize) -> Result<bool, MemscanError> {
         let regions = self.get_memory_regions()?;
 
         for region in regions {
@@ -107,22 +229,62 @@ impl ProcessHandle {
         Ok(false)
     }
 
-    pub fn safe_write_memory(&self, address: usize, data: &[u8]) -> Result<usize, std::io::Error> {
+    pub fn safe_write_memory(&self, address: usize, data: &[u8]) -> Result<usize, MemscanError> {
         if !self.is_writable(address)? {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::PermissionDenied,
-                "Memory region is not writable",
-            ));
+            return Err(MemscanError::PermissionDenied {
+                pid: Some(self.pid),
+                reason: DenialReason::ReadOnlyMapping { address },
+            });
         }
 
         self.write_memory(address, data)
     }
 
-    pub fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, std::io::Error> {
-        crate::memory::get_memory_regions(self.pid)
+    pub fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+        self.check()?;
+        crate::memory::get_memory_regions(self.pid).map_err(|e| match self.validate() {
+            Err(exited) => exited,
+            Ok(()) => e,
+        })
+    }
+
+    pub fn modules(&self) -> Result<Vec<Module>, MemscanError> {
+        modules::list_modules(self)
+    }
+
+    pub fn threads(&self) -> Result<Vec<ThreadInfo>, MemscanError> {
+        self.check()?;
+        threads::list_threads(self.pid)
+    }
+
+    pub fn read_registers(&self, tid: u32) -> Result<Registers, MemscanError> {
+        self.check_thread(tid)?;
+        threads::read_registers(tid)
     }
 
-    pub fn get_scannable_regions(&self) -> Result<Vec<MemoryRegion>, std::io::Error> {
+    pub fn write_register(
+        &self,
+        tid: u32,
+        name: &str,
+        value: u64,
+    ) -> Result<Registers, MemscanError> {
+        self.check_thread(tid)?;
+        threads::write_register(tid, name, value)
+    }
+
+    fn check_thread(&self, tid: u32) -> Result<(), MemscanError> {
+        self.check()?;
+        if std::path::Path::new(&format!("/proc/{}/task/{}", self.pid, tid)).exists() {
+            Ok(())
+        } else {
+            Err(MemscanError::not_found(format!(
+                "Thread {} in process {}",
+                tid, self.pid
+            )))
+        }
+    }
+
+    pub fn get_scannable_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
         let all_regions = self.get_memory_regions()?;
 
         // filter for readable regions larger than 1kb
@@ -135,28 +297,82 @@ impl ProcessHandle {
     }
 }
 
+impl MemorySource for ProcessHandle {
+    fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        ProcessHandle::read_memory(self, address, size)
+    }
+
+    fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+        ProcessHandle::get_memory_regions(self)
+    }
+
+    fn describe(&self) -> String {
+        match std::fs::read_to_string(format!("/proc/{}/comm", self.pid)) {
+            Ok(comm) => format!("{} (PID {})", comm.trim(), self.pid),
+            Err(_) => format!("PID {}", self.pid),
+        }
+    }
+
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    fn file_path(&self, path: &str) -> PathBuf {
+        let path = path.trim_end_matches(" (deleted)");
+        match root_prefix(self.pid) {
+            Some(root) => root.join(path.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        }
+    }
+
+    fn write_memory(&self, address: usize, data: &[u8]) -> Result<usize, MemscanError> {
+        ProcessHandle::write_memory(self, address, data)
+    }
+}
+
 impl Process {
     pub fn new(pid: u32, name: String) -> Self {
         Self {
             pid,
             name,
             handle: None,
+            info: OnceLock::new(),
         }
     }
 
-    pub fn open(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    // full metadata, read from /proc the first time it's asked for
+    pub fn info(&self) -> Option<&ProcessInfo> {
+        self.info
+            .get_or_init(|| ProcessInfo::read(self.pid).ok())
+            .as_ref()
+    }
+
+    pub fn open(&mut self) -> Result<(), MemscanError> {
         // for linux, we can access /proc/PID/ directly
-        let proc_path = format!("/proc/{}", self.pid);
-        if std::path::Path::new(&proc_path).exists() {
-            let memory_reader = MemoryReader::new(self.pid);
-            self.handle = Some(ProcessHandle {
+        let identity = match ProcessIdentity::read(self.pid) {
+            Some(identity) => identity,
+            None => return Err(MemscanError::ProcessNotFound { pid: self.pid }),
+        };
+
+        // listed a while ago? make sure it's still the same process
+        if let Some(Some(info)) = self.info.get()
+            && info.start_time != identity.start_time
+        {
+            return Err(MemscanError::ProcessExited {
                 pid: self.pid,
-                memory_reader: Some(memory_reader),
+                replaced: true,
             });
-            Ok(())
-        } else {
-            Err(format!("Process {} no longer exists", self.pid).into())
         }
+
+        let memory_reader = MemoryReader::new(self.pid);
+        self.handle = Some(ProcessHandle {
+            pid: self.pid,
+            memory_reader: Some(memory_reader),
+            identity: Some(identity),
+            opened_at: Instant::now(),
+            validated_at: AtomicU64::new(1),
+        });
+        Ok(())
     }
 }
 
@@ -166,15 +382,429 @@ impl fmt::Display for Process {
     }
 }
 
+impl ProcessInfo {
+    pub fn read(pid: u32) -> Result<Self, MemscanError> {
+        let base = format!("/proc/{}", pid);
+        let stat = std::fs::read_to_string(format!("{}/stat", base))
+            .map_err(|_| MemscanError::ProcessNotFound { pid })?;
+        let status = std::fs::read_to_string(format!("{}/status", base)).unwrap_or_default();
+
+        // comm may contain spaces and parens, everything after the last ')' is fixed
+        let (name, rest) = match (stat.find('('), stat.rfind(')')) {
+            (Some(open), Some(close)) if open < close => {
+                (stat[open + 1..close].to_string(), &stat[close + 1..])
+            }
+            _ => {
+                return Err(MemscanError::invalid_data(format!(
+                    "Malformed stat for PID {}",
+                    pid
+                )));
+            }
+        };
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("0");
+
+        let status_field = |key: &str| {
+            status
+                .lines()
+                .find_map(|l| l.strip_prefix(key))
+                .map(|v| v.split_whitespace().collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+        let id = |key: &str, i: usize| {
+            status_field(key)
+                .get(i)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0)
+        };
+        let kb = |key: &str| status_field(key).first().and_then(|v| v.parse().ok());
+
+        let cmdline = std::fs::read(format!("{}/cmdline", base))
+            .unwrap_or_default()
+            .split(|&b| b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+
+        let namespaces = std::fs::read_dir(format!("{}/ns", base))
+            .map(|entries| {
+                let mut namespaces: Vec<(String, u64)> = entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| {
+                        // links look like "pid:[4026531836]"
+                        let link = std::fs::read_link(e.path()).ok()?;
+                        let link = link.to_string_lossy();
+                        let inode = link.split('[').nth(1)?.trim_end_matches(']').parse().ok()?;
+                        Some((e.file_name().to_string_lossy().to_string(), inode))
+                    })
+                    .collect();
+                namespaces.sort();
+                namespaces
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            pid,
+            name,
+            cmdline,
+            exe: std::fs::read_link(format!("{}/exe", base)).ok(),
+            cwd: std::fs::read_link(format!("{}/cwd", base)).ok(),
+            uid: id("Uid:", 0),
+            euid: id("Uid:", 1),
+            gid: id("Gid:", 0),
+            egid: id("Gid:", 1),
+            state: field(0).chars().next().unwrap_or('?'),
+            ppid: field(1).parse().unwrap_or(0),
+            threads: field(17).parse().unwrap_or(1),
+            start_time: field(19).parse().unwrap_or(0),
+            vm_rss_kb: kb("VmRSS:"),
+            vm_size_kb: kb("VmSize:"),
+            tracer_pid: id("TracerPid:", 0),
+            ns_pids: parse_ns_pids(&status),
+            namespaces,
+        })
+    }
+
+    // command line joined with spaces, falling back to the comm name
+    pub fn command_line(&self) -> String {
+        if self.cmdline.is_empty() {
+            format!("[{}]", self.name)
+        } else {
+            self.cmdline.join(" ")
+        }
+    }
+
+    pub fn state_name(&self) -> &'static str {
+        match self.state {
+            'R' => "running",
+            'S' => "sleeping",
+            'D' => "disk sleep",
+            'T' => "stopped",
+            't' => "tracing stop",
+            'Z' => "zombie",
+            'X' => "dead",
+            'I' => "idle",
+            _ => "unknown",
+        }
+    }
+
+    pub fn is_traced(&self) -> bool {
+        self.tracer_pid != 0
+    }
+
+    // the PID the process sees for itself
+    pub fn ns_pid(&self) -> u32 {
+        self.ns_pids.last().copied().unwrap_or(self.pid)
+    }
+
+    // lives in a pid namespace below ours (a container)
+    pub fn in_child_pid_namespace(&self) -> bool {
+        self.ns_pids.len() > 1
+    }
+
+    pub fn namespace(&self, kind: &str) -> Option<u64> {
+        self.namespaces
+            .iter()
+            .find(|(k, _)| k == kind)
+            .map(|&(_, inode)| inode)
+    }
+
+    // wall clock start time from the boot time in /proc/stat
+    pub fn started_at(&self) -> Option<SystemTime> {
+        let stat = std::fs::read_to_string("/proc/stat").ok()?;
+        let boot_time: u64 = stat
+            .lines()
+            .find_map(|l| l.strip_prefix("btime "))?
+            .trim()
+            .parse()
+            .ok()?;
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks <= 0 {
+            return None;
+        }
+
+        let since_boot = Duration::from_millis(self.start_time * 1000 / ticks as u64);
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(boot_time) + since_boot)
+    }
+
+    // how long the process has been running
+    pub fn uptime(&self) -> Option<Duration> {
+        SystemTime::now().duration_since(self.started_at()?).ok()
+    }
+}
+
+// login name for a uid from /etc/passwd
+pub fn user_name(uid: u32) -> Option<String> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let id: u32 = fields.nth(1)?.parse().ok()?;
+        (id == uid).then(|| name.to_string())
+    })
+}
+
+// uid for a login name (or a plain number)
+pub fn user_id(name: &str) -> Option<u32> {
+    if let Ok(uid) = name.parse() {
+        return Some(uid);
+    }
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? != name {
+            return None;
+        }
+        fields.nth(1)?.parse().ok()
+    })
+}
+
+// which processes to keep when listing. empty filter keeps everything
+#[derive(Debug, Clone, Default)]
+pub struct ProcessFilter {
+    // case-insensitive substring of the comm name
+    pub name: Option<String>,
+    pub name_regex: Option<Regex>,
+    // case-insensitive substring of the full command line
+    pub cmdline: Option<String>,
+    pub uid: Option<u32>,
+    pub parent: Option<u32>,
+    // only processes we may ptrace / open /proc/PID/mem for
+    pub attachable: bool,
+}
+
+impl ProcessFilter {
+    pub fn matches(&self, process: &Process) -> bool {
+        if let Some(name) = &self.name
+            && !process.name.to_lowercase().contains(&name.to_lowercase())
+        {
+            return false;
+        }
+
+        if let Some(regex) = &self.name_regex
+            && !regex.is_match(&process.name)
+        {
+            return false;
+        }
+
+        if self.cmdline.is_some() || self.uid.is_some() || self.parent.is_some() {
+            let info = match process.info() {
+                Some(info) => info,
+                None => return false,
+            };
+
+            if let Some(pattern) = &self.cmdline
+                && !info
+                    .command_line()
+                    .to_lowercase()
+                    .contains(&pattern.to_lowercase())
+            {
+                return false;
+            }
+            if self.uid.is_some_and(|uid| info.uid != uid) {
+                return false;
+            }
+            if self.parent.is_some_and(|ppid| info.ppid != ppid) {
+                return false;
+            }
+        }
+
+        !self.attachable || can_attach(process.pid)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProcessSort {
+    #[default]
+    Name,
+    Pid,
+    // largest resident set first
+    Memory,
+    // newest first
+    StartTime,
+}
+
+impl ProcessSort {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "name" => Some(ProcessSort::Name),
+            "pid" => Some(ProcessSort::Pid),
+            "mem" | "memory" | "rss" => Some(ProcessSort::Memory),
+            "start" | "age" | "time" => Some(ProcessSort::StartTime),
+            _ => None,
+        }
+    }
+}
+
+fn parse_ns_pids(status: &str) -> Vec<u32> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("NSpid:"))
+        .map(|v| {
+            v.split_whitespace()
+                .filter_map(|p| p.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn namespace_inode(pid: &str, kind: &str) -> Option<u64> {
+    let link = std::fs::read_link(format!("/proc/{}/ns/{}", pid, kind)).ok()?;
+    link.to_string_lossy()
+        .split('[')
+        .nth(1)?
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+// the files of a process in another mount namespace or chroot (a container)
+// are reachable through /proc/PID/root. None when it sees the same files we do
+pub fn root_prefix(pid: u32) -> Option<PathBuf> {
+    let root = std::fs::read_link(format!("/proc/{}/root", pid)).ok()?;
+    let same_mounts = namespace_inode(&pid.to_string(), "mnt") == namespace_inode("self", "mnt");
+    if root == Path::new("/") && same_mounts {
+        return None;
+    }
+    Some(PathBuf::from(format!("/proc/{}/root", pid)))
+}
+
+// our PID for the process (or thread) that is ns_pid inside the pid namespace
+// of ns_of, e.g. PID 1 of a container given any process in it
+pub fn resolve_ns_pid(ns_pid: u32, ns_of: u32) -> Result<u32, MemscanError> {
+    let reference = ns_of.to_string();
+    let namespace = namespace_inode(&reference, "pid").ok_or_else(|| {
+        if Path::new(&format!("/proc/{}", ns_of)).exists() {
+            MemscanError::PermissionDenied {
+                pid: Some(ns_of),
+                reason: DenialReason::diagnose(ns_of),
+            }
+        } else {
+            MemscanError::ProcessNotFound { pid: ns_of }
+        }
+    })?;
+    let status = |path: String| std::fs::read_to_string(path).unwrap_or_default();
+    let level = parse_ns_pids(&status(format!("/proc/{}/status", ns_of))).len();
+
+    let entries = std::fs::read_dir("/proc").map_err(|e| MemscanError::io("/proc", e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.parse::<u32>().is_err() || namespace_inode(&name, "pid") != Some(namespace) {
+            continue;
+        }
+
+        // threads have namespace PIDs too
+        let Ok(tasks) = std::fs::read_dir(format!("/proc/{}/task", name)) else {
+            continue;
+        };
+        for task in tasks.filter_map(|t| t.ok()) {
+            let ids = parse_ns_pids(&status(format!(
+                "/proc/{}/task/{}/status",
+                name,
+                task.file_name().to_string_lossy()
+            )));
+            if level > 0 && ids.get(level - 1) == Some(&ns_pid) {
+                return Ok(ids[0]);
+            }
+        }
+    }
+
+    Err(MemscanError::not_found(format!(
+        "PID {} in the pid namespace of process {}",
+        ns_pid, ns_of
+    )))
+}
+
+// the same access check the kernel makes before ptrace attach
+pub fn can_attach(pid: u32) -> bool {
+    std::fs::File::open(format!("/proc/{}/mem", pid)).is_ok()
+}
+
+// look up one PID straight from /proc without walking every process
+pub fn get_process(pid: u32) -> Option<Process> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    let name = comm.trim().to_string();
+    if name.is_empty() || !std::path::Path::new(&format!("/proc/{}/maps", pid)).exists() {
+        return None;
+    }
+
+    Some(Process::new(pid, name))
+}
+
+// processes for a name given on the command line. "/expr/" is a regex on the
+// name, otherwise exact name (or executable file name) matches win over
+// substrings of the name, which win over substrings of the command line
+pub fn match_processes(pattern: &str) -> Result<Vec<Process>, MemscanError> {
+    let own_pid = std::process::id();
+    let all: Vec<Process> = find_processes(&ProcessFilter::default(), ProcessSort::Pid)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.pid != own_pid)
+        .collect();
+
+    if let Some(expr) = pattern
+        .strip_prefix('/')
+        .and_then(|p| p.strip_suffix('/'))
+        .filter(|p| !p.is_empty())
+    {
+        let regex = Regex::new(expr)
+            .map_err(|e| MemscanError::invalid_value(pattern, format!("a regex ({})", e)))?;
+        return Ok(all
+            .into_iter()
+            .filter(|p| regex.is_match(&p.name))
+            .collect());
+    }
+
+    let exact: Vec<Process> = all
+        .iter()
+        .filter(|p| {
+            p.name == pattern
+                || p.info()
+                    .and_then(|i| i.exe.as_ref())
+                    .and_then(|exe| exe.file_name())
+                    .is_some_and(|file| file == pattern)
+        })
+        .cloned()
+        .collect();
+    if !exact.is_empty() {
+        return Ok(exact);
+    }
+
+    let lowered = pattern.to_lowercase();
+    let by_name: Vec<Process> = all
+        .iter()
+        .filter(|p| p.name.to_lowercase().contains(&lowered))
+        .cloned()
+        .collect();
+    if !by_name.is_empty() {
+        return Ok(by_name);
+    }
+
+    Ok(all
+        .into_iter()
+        .filter(|p| {
+            p.info()
+                .is_some_and(|i| i.command_line().to_lowercase().contains(&lowered))
+        })
+        .collect())
+}
+
 // get list of running processes on linux
 
-pub fn enumerate_processes() -> Result<Vec<Process>, Box<dyn std::error::Error>> {
+pub fn enumerate_processes() -> Result<Vec<Process>, MemscanError> {
+    find_processes(&ProcessFilter::default(), ProcessSort::Name)
+}
+
+pub fn find_processes(
+    filter: &ProcessFilter,
+    sort: ProcessSort,
+) -> Result<Vec<Process>, MemscanError> {
     use std::fs;
 
     let mut processes = Vec::new();
-    let _current_uid = unsafe { libc::getuid() };
 
-    for entry in fs::read_dir("/proc")? {
+    let entries = fs::read_dir("/proc").map_err(|e| MemscanError::io("/proc", e))?;
+    for entry in entries {
         let entry = entry?;
         let file_name = entry.file_name();
         let name_str = file_name.to_string_lossy();
@@ -193,15 +823,24 @@ pub fn enumerate_processes() -> Result<Vec<Process>, Box<dyn std::error::Error>>
                 if std::path::Path::new(&status_path).exists() {
                     let maps_path = format!("/proc/{}/maps", pid);
                     if std::path::Path::new(&maps_path).exists() {
-                        processes.push(Process::new(pid, process_name));
+                        let process = Process::new(pid, process_name);
+                        if filter.matches(&process) {
+                            processes.push(process);
+                        }
                     }
                 }
             }
         }
     }
 
-    processes.sort_by(|a, b| a.name.cmp(&b.name));
-    processes.truncate(500); // Increased limit to show more processes
+    match sort {
+        ProcessSort::Name => processes.sort_by(|a, b| a.name.cmp(&b.name)),
+        ProcessSort::Pid => processes.sort_by_key(|p| p.pid),
+        ProcessSort::Memory => processes
+            .sort_by_key(|p| std::cmp::Reverse(p.info().and_then(|i| i.vm_rss_kb).unwrap_or(0))),
+        ProcessSort::StartTime => processes
+            .sort_by_key(|p| std::cmp::Reverse(p.info().map(|i| i.start_time).unwrap_or(0))),
+    }
 
     Ok(processes)
 }
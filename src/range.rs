@@ -0,0 +1,256 @@
+// range watch targets
+// built by the goat (danielscos)
+//
+// a range target reads a whole block every tick instead of one value. the
+// bytes that changed are grouped into runs, or into fields when the block is
+// read as an array of some type, and every run or field that changes counts
+// towards the block's hot spots
+
+use crate::error::MemscanError;
+use crate::monitor::DataType;
+use crate::session::value_size;
+use std::collections::BTreeMap;
+
+// biggest block a range target reads
+pub const MAX_RANGE: usize = 65536;
+
+// bytes [offset, offset + len) of the block changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangedRun {
+    pub offset: usize,
+    pub len: usize,
+}
+
+// neighbouring offsets that changed equally often
+#[derive(Debug, Clone, Copy)]
+pub struct HotSpot {
+    pub offset: usize,
+    pub len: usize,
+    pub changes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RangeState {
+    // report changed fields of this type instead of raw runs
+    pub interpret: Option<DataType>,
+    // offset -> times it changed: per byte, or per field with interpret
+    counts: BTreeMap<usize, u64>,
+}
+
+// consecutive changed bytes become one run
+pub fn changed_runs(old: &[u8], new: &[u8]) -> Vec<ChangedRun> {
+    let mut runs: Vec<ChangedRun> = Vec::new();
+    for (offset, (a, b)) in old.iter().zip(new).enumerate() {
+        if a == b {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.offset + run.len == offset => run.len += 1,
+            _ => runs.push(ChangedRun { offset, len: 1 }),
+        }
+    }
+    runs
+}
+
+// the fields (aligned to the start of the block) the runs touch. a partial
+// field at the end of the block is left out
+pub fn changed_fields(runs: &[ChangedRun], field_size: usize, block_len: usize) -> Vec<usize> {
+    let mut fields: Vec<usize> = runs
+        .iter()
+        .flat_map(|run| {
+            let first = run.offset / field_size;
+            let last = (run.offset + run.len - 1) / field_size;
+            (first..=last).map(move |field| field * field_size)
+        })
+        .filter(|&offset| offset + field_size <= block_len)
+        .collect();
+    fields.dedup();
+    fields
+}
+
+impl RangeState {
+    // only fixed size numbers can be read out of a block
+    pub fn check_interpret(data_type: &DataType, block_len: usize) -> Result<(), MemscanError> {
+        match data_type {
+            DataType::String(_) | DataType::Bytes(_) => Err(MemscanError::unsupported(
+                "Ranges can be read as i32, i64, f32 or f64",
+            )),
+            _ if !block_len.is_multiple_of(value_size(data_type)) => {
+                Err(MemscanError::invalid_value(
+                    &block_len.to_string(),
+                    format!("a multiple of {} bytes", value_size(data_type)),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // what a change of the block is reported as: (offset, len) of every run,
+    // or of every changed field. counts them as well
+    pub fn record(&mut self, old: &[u8], new: &[u8]) -> Vec<(usize, usize)> {
+        let runs = changed_runs(old, new);
+        match &self.interpret {
+            Some(data_type) => {
+                let size = value_size(data_type);
+                let fields = changed_fields(&runs, size, new.len().min(old.len()));
+                for &offset in &fields {
+                    *self.counts.entry(offset).or_default() += 1;
+                }
+                fields.into_iter().map(|offset| (offset, size)).collect()
+            }
+            None => {
+                for run in &runs {
+                    for offset in run.offset..run.offset + run.len {
+                        *self.counts.entry(offset).or_default() += 1;
+                    }
+                }
+                runs.into_iter().map(|run| (run.offset, run.len)).collect()
+            }
+        }
+    }
+
+    // most changed first
+    pub fn hot_spots(&self) -> Vec<HotSpot> {
+        let unit = self.interpret.as_ref().map_or(1, value_size);
+        let mut spots: Vec<HotSpot> = Vec::new();
+        for (&offset, &changes) in &self.counts {
+            match spots.last_mut() {
+                Some(spot) if spot.offset + spot.len == offset && spot.changes == changes => {
+                    spot.len += unit
+                }
+                _ => spots.push(HotSpot {
+                    offset,
+                    len: unit,
+                    changes,
+                }),
+            }
+        }
+        spots.sort_by(|a, b| b.changes.cmp(&a.changes).then(a.offset.cmp(&b.offset)));
+        spots
+    }
+
+    // offsets that changed at least once
+    pub fn changed_offsets(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(offset: usize, len: usize) -> ChangedRun {
+        ChangedRun { offset, len }
+    }
+
+    fn spots(state: &RangeState) -> Vec<(usize, usize, u64)> {
+        state
+            .hot_spots()
+            .iter()
+            .map(|s| (s.offset, s.len, s.changes))
+            .collect()
+    }
+
+    #[test]
+    fn runs() {
+        let old = [0u8; 12];
+        let mut new = old;
+        assert!(changed_runs(&old, &new).is_empty());
+
+        // adjacent bytes join, a gap splits
+        new[2] = 1;
+        new[3] = 1;
+        new[4] = 1;
+        new[7] = 1;
+        new[11] = 1;
+        assert_eq!(
+            changed_runs(&old, &new),
+            vec![run(2, 3), run(7, 1), run(11, 1)]
+        );
+
+        // only the bytes both blocks have are compared
+        assert_eq!(changed_runs(&old[..4], &new), vec![run(2, 2)]);
+    }
+
+    #[test]
+    fn fields() {
+        // a run spanning two fields touches both
+        assert_eq!(changed_fields(&[run(3, 2)], 4, 16), vec![0, 4]);
+        // separated runs in one field count it once
+        assert_eq!(changed_fields(&[run(4, 1), run(6, 2)], 4, 16), vec![4]);
+        // runs in neighbouring fields
+        assert_eq!(changed_fields(&[run(1, 1), run(5, 1)], 4, 16), vec![0, 4]);
+        // a run ending in one field and one starting in it
+        assert_eq!(
+            changed_fields(&[run(2, 3), run(6, 4)], 4, 16),
+            vec![0, 4, 8]
+        );
+        // a partial field at the end of the block is left out
+        assert_eq!(changed_fields(&[run(8, 6)], 4, 14), vec![8]);
+        assert!(changed_fields(&[run(12, 2)], 4, 14).is_empty());
+    }
+
+    #[test]
+    fn record_counts_fields() {
+        let mut state = RangeState {
+            interpret: Some(DataType::I32),
+            ..Default::default()
+        };
+        let old = [0u8; 16];
+        let mut new = old;
+        new[3] = 1;
+        new[4] = 1;
+        new[13] = 1;
+        assert_eq!(state.record(&old, &new), vec![(0, 4), (4, 4), (12, 4)]);
+        assert_eq!(state.changed_offsets(), 3);
+
+        state.clear();
+        assert_eq!(state.changed_offsets(), 0);
+    }
+
+    #[test]
+    fn hot_spots_merge_equal_neighbours() {
+        let mut state = RangeState::default();
+        let old = [0u8; 8];
+        let mut once = old;
+        once[0] = 1;
+        once[1] = 1;
+        once[2] = 1;
+        once[6] = 1;
+        let mut twice = old;
+        twice[2] = 1;
+        twice[3] = 1;
+
+        state.record(&old, &once);
+        state.record(&old, &twice);
+        // byte 2 changed twice, so it splits the run it was in. 4-5 never
+        // changed, so 6 is its own spot
+        assert_eq!(
+            spots(&state),
+            vec![(2, 1, 2), (0, 2, 1), (3, 1, 1), (6, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn hot_spots_in_fields() {
+        let mut state = RangeState {
+            interpret: Some(DataType::I64),
+            ..Default::default()
+        };
+        let old = [0u8; 32];
+        let mut new = old;
+        new[0] = 1;
+        new[9] = 1;
+        new[25] = 1;
+        state.record(&old, &new);
+        let mut later = old;
+        later[25] = 2;
+        state.record(&old, &later);
+        // fields 0 and 8 merge, 24 changed more often and comes first
+        assert_eq!(spots(&state), vec![(24, 8, 2), (0, 16, 1)]);
+    }
+}
@@ -1,9 +1,8 @@
 // memory scanning algos
 // built by the goat (danielcos)
 
-use crate::process::ProcessHandle;
-use std::error::Error;
-use std::fmt;
+use crate::error::MemscanError;
+use crate::source::MemorySource;
 
 // single scan result
 #[derive(Debug, Clone)]
@@ -11,26 +10,6 @@ pub struct ScanResult {
     pub address: usize,
 }
 
-// custom error type
-#[derive(Debug)]
-pub enum ScanError {
-    MemoryReadError(std::io::Error),
-    NoMemoryRegions,
-    InvalidValue,
-}
-
-impl fmt::Display for ScanError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ScanError::MemoryReadError(e) => write!(f, "Memory read error: {}", e),
-            ScanError::NoMemoryRegions => write!(f, "No scannable memory regions found"),
-            ScanError::InvalidValue => write!(f, "Invalid search value"),
-        }
-    }
-}
-
-impl Error for ScanError {}
-
 //==============================================================================
 //==============================================================================
 //
@@ -46,18 +25,16 @@ impl Error for ScanError {}
 //==============================================================================
 
 pub fn scan_for_i32(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     target_value: i32,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     println!("scanning for value {} has begun", target_value);
 
     // step 1, get scannable memory regions
-    let regions = process_handle
-        .get_scannable_regions()
-        .map_err(ScanError::MemoryReadError)?;
+    let regions = source.get_scannable_regions()?;
 
     if regions.is_empty() {
-        return Err(ScanError::NoMemoryRegions);
+        return Err(MemscanError::NoMemoryRegions);
     }
 
     println!("{} scannable memory regions", regions.len());
@@ -74,7 +51,7 @@ pub fn scan_for_i32(
             region.size / 1024
         );
 
-        match scan_region(process_handle, region, &target_bytes) {
+        match scan_region(source, region, &target_bytes) {
             Ok(mut region_results) => {
                 println!("   Found {} matches in this region", region_results.len());
                 results.append(&mut region_results);
@@ -96,10 +73,10 @@ pub fn scan_for_i32(
 }
 
 fn scan_region(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     region: &crate::memory::MemoryRegion,
     target_bytes: &[u8],
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     let mut results = Vec::new();
 
     // read memory in chunks
@@ -108,12 +85,14 @@ fn scan_region(
     let end_address = region.start_address + region.size;
 
     while current_address < end_address {
-        // calculate how much to read
+        // calculate how much to read, reaching into the next chunk far enough
+        // to catch a value that straddles the boundary
         let remaining = end_address - current_address;
-        let read_size = CHUNK_SIZE.min(remaining);
+        let step = CHUNK_SIZE.min(remaining);
+        let read_size = (CHUNK_SIZE + target_bytes.len() - 1).min(remaining);
 
         // try to read this chunk
-        match process_handle.read_memory(current_address, read_size) {
+        match source.read_memory(current_address, read_size) {
             Ok(chunk) => {
                 // search for target avalue in chunk
                 let chunk_results = search_bytes_in_chunk(&chunk, target_bytes, current_address);
@@ -132,7 +111,7 @@ fn scan_region(
             }
         }
 
-        current_address += read_size;
+        current_address += step;
     }
 
     Ok(results)
@@ -160,17 +139,15 @@ fn search_bytes_in_chunk(
 
 // i64 scanning
 pub fn scan_for_i64(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     target_value: i64,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     println!(" Scanning for i64 value: {}", target_value);
 
-    let regions = process_handle
-        .get_scannable_regions()
-        .map_err(ScanError::MemoryReadError)?;
+    let regions = source.get_scannable_regions()?;
 
     if regions.is_empty() {
-        return Err(ScanError::NoMemoryRegions);
+        return Err(MemscanError::NoMemoryRegions);
     }
 
     println!(" Found {} scannable memory regions", regions.len());
@@ -187,7 +164,7 @@ pub fn scan_for_i64(
             region.size / 1024
         );
 
-        match scan_region(process_handle, region, &target_bytes) {
+        match scan_region(source, region, &target_bytes) {
             Ok(mut region_results) => {
                 if region_results.is_empty() {
                     println!("    Found 0 matches in this region");
@@ -214,17 +191,15 @@ pub fn scan_for_i64(
 
 // f32 scanning
 pub fn scan_for_f32(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     target_value: f32,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     println!(" Scanning for f32 value: {}", target_value);
 
-    let regions = process_handle
-        .get_scannable_regions()
-        .map_err(ScanError::MemoryReadError)?;
+    let regions = source.get_scannable_regions()?;
 
     if regions.is_empty() {
-        return Err(ScanError::NoMemoryRegions);
+        return Err(MemscanError::NoMemoryRegions);
     }
 
     println!(" Found {} scannable memory regions", regions.len());
@@ -241,7 +216,7 @@ pub fn scan_for_f32(
             region.size / 1024
         );
 
-        match scan_region(process_handle, region, &target_bytes) {
+        match scan_region(source, region, &target_bytes) {
             Ok(mut region_results) => {
                 if region_results.is_empty() {
                     println!("    Found 0 matches in this region");
@@ -268,17 +243,15 @@ pub fn scan_for_f32(
 
 // f64 scanning
 pub fn scan_for_f64(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     target_value: f64,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     println!(" Scanning for f64 value: {}", target_value);
 
-    let regions = process_handle
-        .get_scannable_regions()
-        .map_err(ScanError::MemoryReadError)?;
+    let regions = source.get_scannable_regions()?;
 
     if regions.is_empty() {
-        return Err(ScanError::NoMemoryRegions);
+        return Err(MemscanError::NoMemoryRegions);
     }
 
     println!(" Found {} scannable memory regions", regions.len());
@@ -295,7 +268,7 @@ pub fn scan_for_f64(
             region.size / 1024
         );
 
-        match scan_region(process_handle, region, &target_bytes) {
+        match scan_region(source, region, &target_bytes) {
             Ok(mut region_results) => {
                 if region_results.is_empty() {
                     println!("    Found 0 matches in this region");
@@ -322,21 +295,22 @@ pub fn scan_for_f64(
 
 // String scanning (ASCII)
 pub fn scan_for_string(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     target_value: &str,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     println!(" Scanning for string: '{}'", target_value);
 
     if target_value.is_empty() {
-        return Err(ScanError::InvalidValue);
+        return Err(MemscanError::invalid_value(
+            target_value,
+            "a non-empty string",
+        ));
     }
 
-    let regions = process_handle
-        .get_scannable_regions()
-        .map_err(ScanError::MemoryReadError)?;
+    let regions = source.get_scannable_regions()?;
 
     if regions.is_empty() {
-        return Err(ScanError::NoMemoryRegions);
+        return Err(MemscanError::NoMemoryRegions);
     }
 
     println!(" Found {} scannable memory regions", regions.len());
@@ -353,7 +327,7 @@ pub fn scan_for_string(
             region.size / 1024
         );
 
-        match scan_region(process_handle, region, target_bytes) {
+        match scan_region(source, region, target_bytes) {
             Ok(mut region_results) => {
                 if region_results.is_empty() {
                     println!("    Found 0 matches in this region");
@@ -380,48 +354,232 @@ pub fn scan_for_string(
 
 // Typed scanning functions for different data types
 pub fn scan_process_for_i32(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     value_str: &str,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     let value = value_str
         .parse::<i32>()
-        .map_err(|_| ScanError::InvalidValue)?;
-    scan_for_i32(process_handle, value)
+        .map_err(|_| MemscanError::invalid_value(value_str, "an i32"))?;
+    scan_for_i32(source, value)
 }
 
 pub fn scan_process_for_i64(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     value_str: &str,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     let value = value_str
         .parse::<i64>()
-        .map_err(|_| ScanError::InvalidValue)?;
-    scan_for_i64(process_handle, value)
+        .map_err(|_| MemscanError::invalid_value(value_str, "an i64"))?;
+    scan_for_i64(source, value)
 }
 
 pub fn scan_process_for_f32(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     value_str: &str,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     let value = value_str
         .parse::<f32>()
-        .map_err(|_| ScanError::InvalidValue)?;
-    scan_for_f32(process_handle, value)
+        .map_err(|_| MemscanError::invalid_value(value_str, "an f32"))?;
+    scan_for_f32(source, value)
 }
 
 pub fn scan_process_for_f64(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     value_str: &str,
-) -> Result<Vec<ScanResult>, ScanError> {
+) -> Result<Vec<ScanResult>, MemscanError> {
     let value = value_str
         .parse::<f64>()
-        .map_err(|_| ScanError::InvalidValue)?;
-    scan_for_f64(process_handle, value)
+        .map_err(|_| MemscanError::invalid_value(value_str, "an f64"))?;
+    scan_for_f64(source, value)
 }
 
 pub fn scan_process_for_string(
-    process_handle: &ProcessHandle,
+    source: &dyn MemorySource,
     value_str: &str,
-) -> Result<Vec<ScanResult>, ScanError> {
-    scan_for_string(process_handle, value_str)
+) -> Result<Vec<ScanResult>, MemscanError> {
+    scan_for_string(source, value_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryRegion;
+
+    // regions held in memory, read like process_vm_readv would: a read stops
+    // at the end of the mapping it starts in
+    struct BufferSource {
+        regions: Vec<(MemoryRegion, Vec<u8>)>,
+    }
+
+    impl BufferSource {
+        fn new(layout: &[(usize, usize)]) -> Self {
+            let regions = layout
+                .iter()
+                .map(|&(start_address, size)| {
+                    let region = MemoryRegion {
+                        start_address,
+                        size,
+                        readable: true,
+                        writable: true,
+                        executable: false,
+                        offset: 0,
+                        path: None,
+                    };
+                    (region, vec![0u8; size])
+                })
+                .collect();
+            Self { regions }
+        }
+
+        fn put(&mut self, address: usize, bytes: &[u8]) {
+            for (region, data) in &mut self.regions {
+                for (i, &byte) in bytes.iter().enumerate() {
+                    let at = address + i;
+                    if at >= region.start_address && at < region.start_address + region.size {
+                        data[at - region.start_address] = byte;
+                    }
+                }
+            }
+        }
+    }
+
+    impl MemorySource for BufferSource {
+        fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+            let (region, data) = self
+                .regions
+                .iter()
+                .find(|(r, _)| address >= r.start_address && address < r.start_address + r.size)
+                .ok_or(MemscanError::UnmappedAddress { pid: None, address })?;
+            let offset = address - region.start_address;
+            let end = (offset + size).min(data.len());
+            Ok(data[offset..end].to_vec())
+        }
+
+        fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+            Ok(self.regions.iter().map(|(r, _)| r.clone()).collect())
+        }
+
+        fn describe(&self) -> String {
+            "test buffer".to_string()
+        }
+    }
+
+    fn addresses(results: Vec<ScanResult>) -> Vec<usize> {
+        results.into_iter().map(|r| r.address).collect()
+    }
+
+    #[test]
+    fn finds_each_value_type() {
+        let mut source = BufferSource::new(&[(0x10000, 0x2000)]);
+        source.put(0x10010, &123456i32.to_le_bytes());
+        source.put(0x10100, &(-9_000_000_000i64).to_le_bytes());
+        source.put(0x10200, &1.5f32.to_le_bytes());
+        source.put(0x10300, &(-2.25f64).to_le_bytes());
+        source.put(0x10400, b"player one");
+
+        assert_eq!(addresses(scan_for_i32(&source, 123456).unwrap()), [0x10010]);
+        assert_eq!(
+            addresses(scan_for_i64(&source, -9_000_000_000).unwrap()),
+            [0x10100]
+        );
+        assert_eq!(addresses(scan_for_f32(&source, 1.5).unwrap()), [0x10200]);
+        assert_eq!(addresses(scan_for_f64(&source, -2.25).unwrap()), [0x10300]);
+        assert_eq!(
+            addresses(scan_for_string(&source, "player one").unwrap()),
+            [0x10400]
+        );
+    }
+
+    #[test]
+    fn parses_values_for_the_typed_scans() {
+        let mut source = BufferSource::new(&[(0x10000, 0x2000)]);
+        source.put(0x10040, &777i64.to_le_bytes());
+
+        assert_eq!(
+            addresses(scan_process_for_i64(&source, "777").unwrap()),
+            [0x10040]
+        );
+        assert!(scan_process_for_i32(&source, "seven").is_err());
+        assert!(scan_process_for_f64(&source, "").is_err());
+        assert!(scan_process_for_string(&source, "").is_err());
+    }
+
+    #[test]
+    fn finds_unaligned_values() {
+        let mut source = BufferSource::new(&[(0x10000, 0x2000)]);
+        source.put(0x10001, &0x1234_5678i32.to_le_bytes());
+        source.put(0x10107, &0.1f64.to_le_bytes());
+
+        assert_eq!(
+            addresses(scan_for_i32(&source, 0x1234_5678).unwrap()),
+            [0x10001]
+        );
+        assert_eq!(addresses(scan_for_f64(&source, 0.1).unwrap()), [0x10107]);
+    }
+
+    #[test]
+    fn finds_values_at_region_edges() {
+        let mut source = BufferSource::new(&[(0x10000, 0x2000), (0x20000, 0x2000)]);
+        source.put(0x10000, &4242i32.to_le_bytes());
+        source.put(0x12000 - 4, &4242i32.to_le_bytes());
+        source.put(0x20000, &4242i32.to_le_bytes());
+
+        assert_eq!(
+            addresses(scan_for_i32(&source, 4242).unwrap()),
+            [0x10000, 0x12000 - 4, 0x20000]
+        );
+    }
+
+    #[test]
+    fn ignores_values_split_across_regions() {
+        // adjacent mappings are scanned one at a time, a value can't span two
+        let mut source = BufferSource::new(&[(0x10000, 0x2000), (0x12000, 0x2000)]);
+        source.put(0x12000 - 2, &0x0a0b_0c0di32.to_le_bytes());
+
+        assert!(scan_for_i32(&source, 0x0a0b_0c0d).unwrap().is_empty());
+    }
+
+    #[test]
+    fn finds_values_across_read_chunks() {
+        // regions are read 64KB at a time
+        let mut source = BufferSource::new(&[(0x100000, 0x30000)]);
+        source.put(0x100000 + 64 * 1024 - 3, &(-77i64).to_le_bytes());
+        source.put(0x100000 + 128 * 1024 - 4, &8.5f64.to_le_bytes());
+
+        assert_eq!(
+            addresses(scan_for_i64(&source, -77).unwrap()),
+            [0x100000 + 64 * 1024 - 3]
+        );
+        assert_eq!(
+            addresses(scan_for_f64(&source, 8.5).unwrap()),
+            [0x100000 + 128 * 1024 - 4]
+        );
+    }
+
+    #[test]
+    fn skips_small_and_unreadable_regions() {
+        let mut source = BufferSource::new(&[(0x10000, 0x400), (0x20000, 0x2000)]);
+        source.regions[1].0.readable = false;
+        source.put(0x10010, &99i32.to_le_bytes());
+        source.put(0x20010, &99i32.to_le_bytes());
+
+        assert!(matches!(
+            scan_for_i32(&source, 99),
+            Err(MemscanError::NoMemoryRegions)
+        ));
+    }
+
+    #[test]
+    fn scans_a_raw_dump() {
+        let path = std::env::temp_dir().join(format!("memscan-scan-{}.bin", std::process::id()));
+        let mut data = vec![0u8; 0x3000];
+        data[0x1235..0x1239].copy_from_slice(&31337i32.to_le_bytes());
+        std::fs::write(&path, &data).unwrap();
+
+        let dump = crate::source::RawDump::open(&path, 0x400000).unwrap();
+        let found = addresses(scan_for_i32(&dump, 31337).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(found, [0x401235]);
+    }
 }
@@ -0,0 +1,478 @@
+// scan sessions - narrowing results across repeated scans
+// built by the goat (danielscos)
+//
+// a session remembers candidate addresses and their last values. each call to
+// `narrow` keeps the candidates matching a filter (changed, unchanged, ...).
+// on linux targets with soft-dirty support only pages the target actually
+// wrote since the previous pass are read again, everything else is known to
+// be unchanged. without it every candidate page is re-read and compared
+
+use crate::error::MemscanError;
+use crate::monitor::DataType;
+use crate::scanner::ScanResult;
+use crate::softdirty::{self, PAGE_SIZE, Pagemap};
+use crate::source::MemorySource;
+use std::collections::BTreeMap;
+
+const READ_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanFilter {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    Exact(String),
+}
+
+impl ScanFilter {
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "changed" => ScanFilter::Changed,
+            "unchanged" => ScanFilter::Unchanged,
+            "increased" => ScanFilter::Increased,
+            "decreased" => ScanFilter::Decreased,
+            value => ScanFilter::Exact(value.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NarrowStats {
+    pub pages_total: usize,
+    pub pages_reread: usize,
+    pub soft_dirty: bool,
+    pub remaining: usize,
+}
+
+// a contiguous copy of target memory with one alive bit per candidate slot
+#[derive(Debug)]
+struct Chunk {
+    start: usize,
+    data: Vec<u8>,
+    alive: Vec<u64>,
+}
+
+impl Chunk {
+    fn is_alive(&self, slot: usize) -> bool {
+        self.alive[slot / 64] & (1 << (slot % 64)) != 0
+    }
+
+    fn set_alive(&mut self, slot: usize, alive: bool) {
+        if alive {
+            self.alive[slot / 64] |= 1 << (slot % 64);
+        } else {
+            self.alive[slot / 64] &= !(1 << (slot % 64));
+        }
+    }
+
+    fn alive_count(&self) -> usize {
+        self.alive.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn end(&self) -> usize {
+        self.start + self.data.len()
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanSession {
+    pid: Option<u32>,
+    data_type: DataType,
+    value_size: usize,
+    alignment: usize,
+    chunks: Vec<Chunk>,
+    soft_dirty: bool,
+}
+
+pub fn value_size(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::I32 | DataType::F32 => 4,
+        DataType::I64 | DataType::F64 => 8,
+        DataType::String(size) | DataType::Bytes(size) => *size,
+    }
+}
+
+impl ScanSession {
+    // unknown initial value: copy every writable region, every aligned slot
+    // is a candidate until the first narrowing pass
+    pub fn start_unknown(
+        source: &dyn MemorySource,
+        pid: Option<u32>,
+        data_type: DataType,
+    ) -> Result<Self, MemscanError> {
+        if matches!(data_type, DataType::String(_) | DataType::Bytes(_)) {
+            return Err(MemscanError::unsupported(
+                "Unknown-value scans don't work on strings or raw bytes",
+            ));
+        }
+
+        let regions: Vec<_> = source
+            .get_scannable_regions()?
+            .into_iter()
+            .filter(|r| r.writable)
+            .collect();
+
+        if regions.is_empty() {
+            return Err(MemscanError::NoMemoryRegions);
+        }
+
+        let size = value_size(&data_type);
+        let mut session = Self {
+            pid,
+            data_type,
+            value_size: size,
+            alignment: size,
+            chunks: Vec::new(),
+            soft_dirty: false,
+        };
+        // clear before the capture, a write landing while we copy must
+        // leave its page dirty for the first narrowing pass
+        session.enable_soft_dirty(source);
+
+        println!(" Capturing {} writable regions", regions.len());
+
+        for region in &regions {
+            let slots = region.size / size;
+            let mut chunk = Chunk {
+                start: region.start_address,
+                data: Vec::with_capacity(region.size),
+                alive: vec![u64::MAX; slots.div_ceil(64)],
+            };
+            // clear the padding bits past the last slot
+            for slot in slots..chunk.alive.len() * 64 {
+                chunk.set_alive(slot, false);
+            }
+
+            let end = region.start_address + region.size;
+            let mut address = region.start_address;
+            while address < end {
+                let read_size = READ_CHUNK.min(end - address);
+                let data = source.read_memory(address, read_size).unwrap_or_default();
+                let offset = address - region.start_address;
+
+                // slots we could not read are dropped right away
+                if data.len() < read_size {
+                    let first = (offset + data.len()) / size;
+                    let last = ((offset + read_size) / size).min(slots);
+                    for slot in first..last {
+                        chunk.set_alive(slot, false);
+                    }
+                }
+
+                chunk.data.extend_from_slice(&data);
+                chunk.data.resize(offset + read_size, 0);
+                address += read_size;
+            }
+
+            session.chunks.push(chunk);
+        }
+
+        Ok(session)
+    }
+
+    // continue from the results of an exact scan
+    pub fn from_results(
+        source: &dyn MemorySource,
+        pid: Option<u32>,
+        data_type: DataType,
+        results: &[ScanResult],
+    ) -> Self {
+        let size = value_size(&data_type);
+        let mut session = Self {
+            pid,
+            data_type,
+            value_size: size,
+            alignment: 1,
+            chunks: Vec::new(),
+            soft_dirty: false,
+        };
+        session.enable_soft_dirty(source);
+
+        // one chunk per page holding results, with room for values that
+        // run past the page end
+        let mut pages: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for result in results {
+            let page = result.address & !(PAGE_SIZE - 1);
+            pages.entry(page).or_default().push(result.address - page);
+        }
+
+        for (page, offsets) in pages {
+            let Ok(data) = source.read_memory(page, PAGE_SIZE + size - 1) else {
+                continue;
+            };
+            let mut chunk = Chunk {
+                start: page,
+                alive: vec![0; data.len().div_ceil(64)],
+                data,
+            };
+            for offset in offsets {
+                if offset + size <= chunk.data.len() {
+                    chunk.set_alive(offset, true);
+                }
+            }
+            session.chunks.push(chunk);
+        }
+
+        session
+    }
+
+    fn enable_soft_dirty(&mut self, source: &dyn MemorySource) {
+        let Some(pid) = self.pid else {
+            return;
+        };
+        let regions = source.get_memory_regions().unwrap_or_default();
+        if !softdirty::is_supported(pid, &regions) {
+            return;
+        }
+        // tracked first so a heat sample can't clear the bits behind us
+        softdirty::track(pid);
+        self.soft_dirty = softdirty::clear_soft_dirty(pid).is_ok();
+        if !self.soft_dirty {
+            softdirty::untrack(pid);
+        }
+    }
+
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    pub fn uses_soft_dirty(&self) -> bool {
+        self.soft_dirty
+    }
+
+    pub fn count(&self) -> usize {
+        self.chunks.iter().map(|c| c.alive_count()).sum()
+    }
+
+    // candidate addresses with their last seen values
+    pub fn candidates(&self, limit: usize) -> Vec<(usize, Vec<u8>)> {
+        let mut out = Vec::new();
+        for chunk in &self.chunks {
+            for slot in 0..chunk.alive.len() * 64 {
+                if out.len() >= limit {
+                    return out;
+                }
+                if chunk.is_alive(slot) {
+                    let offset = slot * self.alignment;
+                    out.push((
+                        chunk.start + offset,
+                        chunk.data[offset..offset + self.value_size].to_vec(),
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_results(&self) -> Vec<ScanResult> {
+        self.candidates(usize::MAX)
+            .into_iter()
+            .map(|(address, _)| ScanResult { address })
+            .collect()
+    }
+
+    pub fn narrow(
+        &mut self,
+        source: &dyn MemorySource,
+        filter: &ScanFilter,
+    ) -> Result<NarrowStats, MemscanError> {
+        let target = match filter {
+            ScanFilter::Exact(text) => Some(encode_value(&self.data_type, text)?),
+            _ => None,
+        };
+
+        let dirty = self.collect_dirty_pages();
+        let mut stats = NarrowStats {
+            soft_dirty: dirty.is_some(),
+            ..Default::default()
+        };
+
+        for (index, chunk) in self.chunks.iter_mut().enumerate() {
+            if chunk.alive_count() == 0 {
+                continue;
+            }
+
+            // byte ranges of the chunk that need a fresh read
+            let first_page = chunk.start & !(PAGE_SIZE - 1);
+            let page_count = (chunk.end() - first_page).div_ceil(PAGE_SIZE);
+            stats.pages_total += page_count;
+
+            let mut runs: Vec<(usize, usize)> = Vec::new();
+            for page in 0..page_count {
+                let is_dirty = match &dirty {
+                    Some(flags) => flags[index].get(page).copied().unwrap_or(true),
+                    None => true,
+                };
+                if !is_dirty {
+                    continue;
+                }
+                stats.pages_reread += 1;
+
+                let start = (first_page + page * PAGE_SIZE).max(chunk.start) - chunk.start;
+                let end = (first_page + (page + 1) * PAGE_SIZE).min(chunk.end()) - chunk.start;
+                match runs.last_mut() {
+                    Some(last) if last.1 == start => last.1 = end,
+                    _ => runs.push((start, end)),
+                }
+            }
+
+            let previous = if runs.is_empty() {
+                None
+            } else {
+                Some(chunk.data.clone())
+            };
+            let mut unreadable: Vec<(usize, usize)> = Vec::new();
+
+            for &(start, end) in &runs {
+                let fresh = source
+                    .read_memory(chunk.start + start, end - start)
+                    .unwrap_or_default();
+                chunk.data[start..start + fresh.len()].copy_from_slice(&fresh);
+                if start + fresh.len() < end {
+                    unreadable.push((start + fresh.len(), end));
+                }
+            }
+
+            let size = self.value_size;
+            let touches = |ranges: &[(usize, usize)], offset: usize| {
+                ranges
+                    .iter()
+                    .any(|&(start, end)| offset < end && offset + size > start)
+            };
+
+            for slot in 0..chunk.alive.len() * 64 {
+                if !chunk.is_alive(slot) {
+                    continue;
+                }
+
+                let offset = slot * self.alignment;
+                if touches(&unreadable, offset) {
+                    chunk.set_alive(slot, false);
+                    continue;
+                }
+
+                let new_value = &chunk.data[offset..offset + size];
+                let old_value = match &previous {
+                    Some(prev) if touches(&runs, offset) => &prev[offset..offset + size],
+                    _ => new_value,
+                };
+
+                let keep = match filter {
+                    ScanFilter::Changed => old_value != new_value,
+                    ScanFilter::Unchanged => old_value == new_value,
+                    ScanFilter::Increased => {
+                        compare(&self.data_type, old_value, new_value)
+                            == Some(std::cmp::Ordering::Less)
+                    }
+                    ScanFilter::Decreased => {
+                        compare(&self.data_type, old_value, new_value)
+                            == Some(std::cmp::Ordering::Greater)
+                    }
+                    ScanFilter::Exact(_) => target.as_deref() == Some(new_value),
+                };
+
+                if !keep {
+                    chunk.set_alive(slot, false);
+                }
+            }
+        }
+
+        self.chunks.retain(|c| c.alive_count() > 0);
+        stats.remaining = self.count();
+        Ok(stats)
+    }
+
+    // soft-dirty flags for every chunk, then one clear for the next pass.
+    // a page written after its chunk's pagemap read but before the clear is
+    // missed, so the window spans every chunk read, not just the last one
+    fn collect_dirty_pages(&mut self) -> Option<Vec<Vec<bool>>> {
+        if !self.soft_dirty {
+            return None;
+        }
+        let pid = self.pid?;
+
+        let result = (|| {
+            let pagemap = Pagemap::open(pid)?;
+            let mut flags = Vec::with_capacity(self.chunks.len());
+            for chunk in &self.chunks {
+                let first_page = chunk.start & !(PAGE_SIZE - 1);
+                flags.push(pagemap.dirty_pages(first_page, chunk.end() - first_page)?);
+            }
+            softdirty::clear_soft_dirty(pid)?;
+            Ok::<_, MemscanError>(flags)
+        })();
+
+        match result {
+            Ok(flags) => Some(flags),
+            Err(_) => {
+                // fall back to full comparison from now on
+                self.soft_dirty = false;
+                softdirty::untrack(pid);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for ScanSession {
+    fn drop(&mut self) {
+        if self.soft_dirty
+            && let Some(pid) = self.pid
+        {
+            softdirty::untrack(pid);
+        }
+    }
+}
+
+fn encode_value(data_type: &DataType, text: &str) -> Result<Vec<u8>, MemscanError> {
+    let bytes = match data_type {
+        DataType::I32 => text.parse::<i32>().map(|v| v.to_le_bytes().to_vec()).ok(),
+        DataType::I64 => text.parse::<i64>().map(|v| v.to_le_bytes().to_vec()).ok(),
+        DataType::F32 => text.parse::<f32>().map(|v| v.to_le_bytes().to_vec()).ok(),
+        DataType::F64 => text.parse::<f64>().map(|v| v.to_le_bytes().to_vec()).ok(),
+        DataType::String(size) => {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.resize(*size, 0);
+            Some(bytes)
+        }
+        DataType::Bytes(size) => hex::decode(text).ok().filter(|b| b.len() == *size),
+    };
+    bytes.ok_or_else(|| {
+        let expected = match data_type {
+            DataType::I32 => "an i32",
+            DataType::I64 => "an i64",
+            DataType::F32 => "an f32",
+            DataType::F64 => "an f64",
+            DataType::String(_) => "a string",
+            DataType::Bytes(_) => "hex bytes of the right length",
+        };
+        MemscanError::invalid_value(text, expected)
+    })
+}
+
+fn compare(data_type: &DataType, old: &[u8], new: &[u8]) -> Option<std::cmp::Ordering> {
+    match data_type {
+        DataType::I32 => {
+            let a = i32::from_le_bytes(old.try_into().ok()?);
+            let b = i32::from_le_bytes(new.try_into().ok()?);
+            Some(a.cmp(&b))
+        }
+        DataType::I64 => {
+            let a = i64::from_le_bytes(old.try_into().ok()?);
+            let b = i64::from_le_bytes(new.try_into().ok()?);
+            Some(a.cmp(&b))
+        }
+        DataType::F32 => {
+            let a = f32::from_le_bytes(old.try_into().ok()?);
+            let b = f32::from_le_bytes(new.try_into().ok()?);
+            a.partial_cmp(&b)
+        }
+        DataType::F64 => {
+            let a = f64::from_le_bytes(old.try_into().ok()?);
+            let b = f64::from_le_bytes(new.try_into().ok()?);
+            a.partial_cmp(&b)
+        }
+        DataType::String(_) | DataType::Bytes(_) => None,
+    }
+}
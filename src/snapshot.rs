@@ -0,0 +1,206 @@
+// memscan snapshots - a compact on-disk copy of every readable region
+// built by the goat (danielscos)
+//
+// layout (little endian):
+//   header:  magic[8] version:u32 pid:u32 region_count:u32 reserved:u32
+//   table:   start:u64 size:u64 map_offset:u64 data_offset:u64 data_size:u64
+//            flags:u32 path_len:u32 path[path_len]      (one per region)
+//   data:    region contents at their data_offset
+
+use crate::error::MemscanError;
+use crate::memory::{MemoryRegion, read_zero_filled};
+use crate::source::{MemorySource, OfflineImage, Segment, display_name};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"MEMSNAP\0";
+const SNAPSHOT_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 24;
+const ENTRY_SIZE: usize = 48;
+const COPY_CHUNK: usize = 1024 * 1024;
+
+const FLAG_READ: u32 = 1;
+const FLAG_WRITE: u32 = 2;
+const FLAG_EXEC: u32 = 4;
+
+#[derive(Debug)]
+pub struct Snapshot {
+    name: String,
+    image: OfflineImage,
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotSummary {
+    pub regions: usize,
+    pub saved_bytes: u64,
+    pub unreadable_bytes: u64,
+}
+
+fn invalid(message: &str) -> MemscanError {
+    MemscanError::invalid_data(message)
+}
+
+impl Snapshot {
+    pub fn open(path: &Path) -> Result<Self, MemscanError> {
+        let mut file =
+            File::open(path).map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+        let mut contents = Vec::new();
+
+        let mut header = [0u8; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        if header[..8] != SNAPSHOT_MAGIC {
+            return Err(invalid("Not a memscan snapshot"));
+        }
+
+        let field =
+            |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+        if field(8) != SNAPSHOT_VERSION {
+            return Err(invalid("Unsupported snapshot version"));
+        }
+        let pid = field(12);
+        let count = field(16) as usize;
+
+        let mut segments = Vec::with_capacity(count);
+        for _ in 0..count {
+            contents.resize(ENTRY_SIZE, 0);
+            file.read_exact(&mut contents)?;
+            let u64_field = |offset: usize| {
+                u64::from_le_bytes(contents[offset..offset + 8].try_into().unwrap())
+            };
+            let u32_field = |offset: usize| {
+                u32::from_le_bytes(contents[offset..offset + 4].try_into().unwrap())
+            };
+
+            let start = u64_field(0) as usize;
+            let size = u64_field(8) as usize;
+            let map_offset = u64_field(16);
+            let data_offset = u64_field(24);
+            let data_size = u64_field(32);
+            let flags = u32_field(40);
+            let path_len = u32_field(44) as usize;
+
+            let mut path_bytes = vec![0u8; path_len];
+            file.read_exact(&mut path_bytes)?;
+
+            segments.push(Segment::new(
+                MemoryRegion {
+                    start_address: start,
+                    size,
+                    readable: flags & FLAG_READ != 0,
+                    writable: flags & FLAG_WRITE != 0,
+                    executable: flags & FLAG_EXEC != 0,
+                    offset: map_offset,
+                    path: (path_len > 0).then(|| String::from_utf8_lossy(&path_bytes).to_string()),
+                },
+                data_offset,
+                data_size,
+            )?);
+        }
+
+        Ok(Self {
+            name: display_name(path),
+            image: OfflineImage::new(file, segments),
+            pid: (pid != 0).then_some(pid),
+        })
+    }
+}
+
+impl MemorySource for Snapshot {
+    fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        self.image.read(address, size)
+    }
+
+    fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+        Ok(self.image.regions())
+    }
+
+    fn describe(&self) -> String {
+        match self.pid {
+            Some(pid) => format!("snapshot {} (PID {})", self.name, pid),
+            None => format!("snapshot {}", self.name),
+        }
+    }
+}
+
+// save every readable region of a source. unreadable chunks are stored as
+// zeros so the table offsets can be written up front
+pub fn write_snapshot(
+    source: &dyn MemorySource,
+    pid: Option<u32>,
+    output: &Path,
+) -> Result<SnapshotSummary, MemscanError> {
+    let regions = source.get_memory_regions()?;
+
+    let table_size: usize = regions
+        .iter()
+        .map(|r| ENTRY_SIZE + r.path.as_ref().map_or(0, |p| p.len()))
+        .sum();
+    let mut data_offset = (HEADER_SIZE + table_size) as u64;
+
+    let file =
+        File::create(output).map_err(|e| MemscanError::io(output.display().to_string(), e))?;
+    let mut out = BufWriter::new(file);
+    out.write_all(&SNAPSHOT_MAGIC)?;
+    out.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+    out.write_all(&pid.unwrap_or(0).to_le_bytes())?;
+    out.write_all(&(regions.len() as u32).to_le_bytes())?;
+    out.write_all(&0u32.to_le_bytes())?;
+
+    for region in &regions {
+        let data_size = if region.readable {
+            region.size as u64
+        } else {
+            0
+        };
+        let mut flags = 0;
+        if region.readable {
+            flags |= FLAG_READ;
+        }
+        if region.writable {
+            flags |= FLAG_WRITE;
+        }
+        if region.executable {
+            flags |= FLAG_EXEC;
+        }
+        let path = region.path.as_deref().unwrap_or("");
+
+        out.write_all(&(region.start_address as u64).to_le_bytes())?;
+        out.write_all(&(region.size as u64).to_le_bytes())?;
+        out.write_all(&region.offset.to_le_bytes())?;
+        out.write_all(&data_offset.to_le_bytes())?;
+        out.write_all(&data_size.to_le_bytes())?;
+        out.write_all(&flags.to_le_bytes())?;
+        out.write_all(&(path.len() as u32).to_le_bytes())?;
+        out.write_all(path.as_bytes())?;
+
+        data_offset += data_size;
+    }
+
+    let mut saved_bytes = 0u64;
+    let mut unreadable_bytes = 0u64;
+
+    for region in regions.iter().filter(|r| r.readable) {
+        let mut address = region.start_address;
+        let end = region.start_address + region.size;
+
+        while address < end {
+            let size = COPY_CHUNK.min(end - address);
+            let (chunk, missing) =
+                read_zero_filled(address, size, |at, len| source.read_memory(at, len));
+            saved_bytes += size as u64 - missing;
+            unreadable_bytes += missing;
+            out.write_all(&chunk)?;
+            address += size;
+        }
+    }
+
+    out.flush()?;
+
+    Ok(SnapshotSummary {
+        regions: regions.len(),
+        saved_bytes,
+        unreadable_bytes,
+    })
+}
@@ -0,0 +1,171 @@
+// soft-dirty page tracking (linux)
+// built by the goat (danielscos)
+//
+// writing "4" to /proc/PID/clear_refs clears the soft-dirty bit on every page,
+// the kernel sets it again on the next write. bit 55 of each /proc/PID/pagemap
+// entry exposes it, so after a clear we can tell exactly which pages changed
+
+use crate::error::MemscanError;
+use crate::memory::MemoryRegion;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::FileExt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const PAGE_SIZE: usize = 4096;
+
+const PM_SOFT_DIRTY: u64 = 1 << 55;
+const PM_SWAPPED: u64 = 1 << 62;
+const PM_PRESENT: u64 = 1 << 63;
+
+lazy_static::lazy_static! {
+    // pids whose soft-dirty bits a scan session is relying on, and how many
+    // sessions. clearing them behind its back would hide writes from it
+    static ref TRACKED: Mutex<HashMap<u32, usize>> = Mutex::new(HashMap::new());
+}
+
+pub(crate) fn track(pid: u32) {
+    *TRACKED.lock().unwrap().entry(pid).or_insert(0) += 1;
+}
+
+pub(crate) fn untrack(pid: u32) {
+    let mut tracked = TRACKED.lock().unwrap();
+    if let Some(count) = tracked.get_mut(&pid) {
+        *count -= 1;
+        if *count == 0 {
+            tracked.remove(&pid);
+        }
+    }
+}
+
+pub fn is_tracked(pid: u32) -> bool {
+    TRACKED.lock().unwrap().contains_key(&pid)
+}
+
+pub fn clear_soft_dirty(pid: u32) -> Result<(), MemscanError> {
+    let context = || format!("/proc/{}/clear_refs", pid);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(context())
+        .map_err(|e| MemscanError::from_proc_io(e, pid, context()))?;
+    file.write_all(b"4")
+        .map_err(|e| MemscanError::io(context(), e))?;
+    Ok(())
+}
+
+// open handle on /proc/PID/pagemap for repeated lookups
+pub struct Pagemap {
+    file: File,
+}
+
+impl Pagemap {
+    pub fn open(pid: u32) -> Result<Self, MemscanError> {
+        let path = format!("/proc/{}/pagemap", pid);
+        Ok(Self {
+            file: File::open(&path).map_err(|e| MemscanError::from_proc_io(e, pid, path))?,
+        })
+    }
+
+    // dirty flag per page of [start, start + size), start must be page aligned
+    pub fn dirty_pages(&self, start: usize, size: usize) -> Result<Vec<bool>, MemscanError> {
+        Ok(self
+            .entries(start, size)?
+            .into_iter()
+            .map(|entry| entry & PM_SOFT_DIRTY != 0)
+            .collect())
+    }
+
+    fn entries(&self, start: usize, size: usize) -> Result<Vec<u64>, MemscanError> {
+        let pages = size.div_ceil(PAGE_SIZE);
+        let mut raw = vec![0u8; pages * 8];
+        let offset = (start / PAGE_SIZE * 8) as u64;
+        self.file
+            .read_exact_at(&mut raw, offset)
+            .map_err(|e| MemscanError::io("Reading pagemap", e))?;
+
+        Ok(raw
+            .chunks_exact(8)
+            .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+            .collect())
+    }
+}
+
+// kernels built without CONFIG_MEM_SOFT_DIRTY accept clear_refs but never set
+// the bit, which would make every page look clean. freshly faulted pages are
+// always soft-dirty, so a working kernel shows the bit on some present page
+// before the first clear
+pub fn is_supported(pid: u32, regions: &[MemoryRegion]) -> bool {
+    let Ok(pagemap) = Pagemap::open(pid) else {
+        return false;
+    };
+    if OpenOptions::new()
+        .write(true)
+        .open(format!("/proc/{}/clear_refs", pid))
+        .is_err()
+    {
+        return false;
+    }
+
+    regions.iter().filter(|r| r.writable).any(|region| {
+        pagemap
+            .entries(region.start_address, region.size)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .any(|&e| e & (PM_PRESENT | PM_SWAPPED) != 0 && e & PM_SOFT_DIRTY != 0)
+            })
+            .unwrap_or(false)
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct RegionHeat {
+    pub region: MemoryRegion,
+    pub pages: usize,
+    pub dirty_pages: usize,
+}
+
+impl RegionHeat {
+    pub fn ratio(&self) -> f64 {
+        if self.pages == 0 {
+            0.0
+        } else {
+            self.dirty_pages as f64 / self.pages as f64
+        }
+    }
+}
+
+// clear, wait, and count which pages of each writable region were written.
+// refused while a scan session is tracking the same process, the clear
+// would make its next pass miss every write made before the sample
+pub fn sample_write_heat(
+    pid: u32,
+    regions: &[MemoryRegion],
+    window: Duration,
+) -> Result<Vec<RegionHeat>, MemscanError> {
+    if is_tracked(pid) {
+        return Err(MemscanError::invalid_state(
+            "a scan session is tracking this process's page writes",
+        ));
+    }
+    clear_soft_dirty(pid)?;
+    std::thread::sleep(window);
+
+    let pagemap = Pagemap::open(pid)?;
+    let mut heat = Vec::new();
+
+    for region in regions.iter().filter(|r| r.writable) {
+        let Ok(entries) = pagemap.entries(region.start_address, region.size) else {
+            continue;
+        };
+        heat.push(RegionHeat {
+            region: region.clone(),
+            pages: entries.len(),
+            dirty_pages: entries.iter().filter(|&&e| e & PM_SOFT_DIRTY != 0).count(),
+        });
+    }
+
+    Ok(heat)
+}
@@ -0,0 +1,260 @@
+// memory sources - anything that can be scanned, dumped and read like a live process
+// built by the goat (danielscos)
+//
+// a live ProcessHandle is one source, core files, memscan snapshots and raw
+// dumps are others. offline sources are read-only
+
+use crate::error::MemscanError;
+use crate::memory::MemoryRegion;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+pub trait MemorySource: Send + Sync {
+    fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError>;
+
+    fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError>;
+
+    // short name for prompts and headers, e.g. "PID 1234" or "core.1234"
+    fn describe(&self) -> String;
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    // where a file the target mapped (a path from its maps) can be opened
+    // from here. containerized processes see a different filesystem
+    fn file_path(&self, path: &str) -> PathBuf {
+        PathBuf::from(path.trim_end_matches(" (deleted)"))
+    }
+
+    fn write_memory(&self, _address: usize, _data: &[u8]) -> Result<usize, MemscanError> {
+        Err(read_only_error())
+    }
+
+    fn get_scannable_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+        // filter for readable regions larger than 1kb
+        Ok(self
+            .get_memory_regions()?
+            .into_iter()
+            .filter(|r| r.readable && r.size > 1024)
+            .collect())
+    }
+
+    fn read_exact(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        let data = self.read_memory(address, size)?;
+        if data.len() < size {
+            let region = self.get_memory_regions().ok().and_then(|regions| {
+                regions
+                    .iter()
+                    .find(|r| address >= r.start_address && address < r.start_address + r.size)
+                    .map(|r| (r.start_address, r.start_address + r.size))
+            });
+            return Err(MemscanError::PartialRead {
+                address,
+                requested: size,
+                read: data.len(),
+                region,
+            });
+        }
+        Ok(data)
+    }
+
+    fn read_i32(&self, address: usize) -> Result<i32, MemscanError> {
+        let data = self.read_exact(address, 4)?;
+        Ok(i32::from_le_bytes(data[..4].try_into().unwrap()))
+    }
+
+    fn read_i64(&self, address: usize) -> Result<i64, MemscanError> {
+        let data = self.read_exact(address, 8)?;
+        Ok(i64::from_le_bytes(data[..8].try_into().unwrap()))
+    }
+
+    fn read_f32(&self, address: usize) -> Result<f32, MemscanError> {
+        let data = self.read_exact(address, 4)?;
+        Ok(f32::from_le_bytes(data[..4].try_into().unwrap()))
+    }
+
+    fn read_f64(&self, address: usize) -> Result<f64, MemscanError> {
+        let data = self.read_exact(address, 8)?;
+        Ok(f64::from_le_bytes(data[..8].try_into().unwrap()))
+    }
+
+    fn read_pointer(&self, address: usize) -> Result<usize, MemscanError> {
+        let data = self.read_exact(address, 8)?;
+        Ok(u64::from_le_bytes(data[..8].try_into().unwrap()) as usize)
+    }
+
+    // read a NUL terminated string of at most max_len bytes
+    fn read_string(&self, address: usize, max_len: usize) -> Result<String, MemscanError> {
+        let data = self.read_memory(address, max_len)?;
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        Ok(String::from_utf8_lossy(&data[..end]).to_string())
+    }
+}
+
+pub fn read_only_error() -> MemscanError {
+    MemscanError::ReadOnly
+}
+
+// one mapping of an offline image and where its bytes live in the file.
+// bytes past file_size (e.g. bss in a core, skipped pages) read as zeros
+#[derive(Debug, Clone)]
+pub(crate) struct Segment {
+    pub region: MemoryRegion,
+    pub file_offset: u64,
+    pub file_size: u64,
+}
+
+impl Segment {
+    // the numbers come from a file, a mapping that wraps past the top of the
+    // address space or the file would break every lookup
+    pub fn new(
+        region: MemoryRegion,
+        file_offset: u64,
+        file_size: u64,
+    ) -> Result<Self, MemscanError> {
+        if region.start_address.checked_add(region.size).is_none()
+            || file_offset.checked_add(file_size).is_none()
+        {
+            return Err(MemscanError::invalid_data(format!(
+                "Segment at 0x{:x} runs past the end of the address space",
+                region.start_address
+            )));
+        }
+        Ok(Self {
+            region,
+            file_offset,
+            file_size,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct OfflineImage {
+    file: File,
+    segments: Vec<Segment>,
+}
+
+impl OfflineImage {
+    pub fn new(file: File, mut segments: Vec<Segment>) -> Self {
+        segments.sort_by_key(|s| s.region.start_address);
+        Self { file, segments }
+    }
+
+    pub fn regions(&self) -> Vec<MemoryRegion> {
+        self.segments.iter().map(|s| s.region.clone()).collect()
+    }
+
+    fn find(&self, address: usize) -> Option<&Segment> {
+        let idx = self
+            .segments
+            .partition_point(|s| s.region.start_address <= address);
+        let segment = self.segments.get(idx.checked_sub(1)?)?;
+        (address < segment.region.start_address + segment.region.size).then_some(segment)
+    }
+
+    // behaves like process_vm_readv: reads across adjacent mappings and
+    // returns what it got, failing only if nothing at all is mapped
+    pub fn read(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        let mut buffer = Vec::with_capacity(size);
+        let mut current = address;
+
+        while buffer.len() < size {
+            let Some(segment) = self.find(current) else {
+                break;
+            };
+
+            let region_offset = (current - segment.region.start_address) as u64;
+            let region_end = segment.region.start_address + segment.region.size;
+            let count = (size - buffer.len()).min(region_end - current);
+
+            let start = buffer.len();
+            buffer.resize(start + count, 0);
+
+            if region_offset < segment.file_size {
+                let available = ((segment.file_size - region_offset) as usize).min(count);
+                self.file.read_exact_at(
+                    &mut buffer[start..start + available],
+                    segment.file_offset + region_offset,
+                )?;
+            }
+
+            current += count;
+        }
+
+        if buffer.is_empty() && size > 0 {
+            return Err(MemscanError::UnmappedAddress { pid: None, address });
+        }
+
+        Ok(buffer)
+    }
+}
+
+// raw binary dump loaded at a known base address
+#[derive(Debug)]
+pub struct RawDump {
+    name: String,
+    image: OfflineImage,
+}
+
+impl RawDump {
+    pub fn open(path: &Path, base_address: usize) -> Result<Self, MemscanError> {
+        let file = File::open(path).map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+        let size = file.metadata()?.len();
+
+        let region = MemoryRegion {
+            start_address: base_address,
+            size: size as usize,
+            readable: true,
+            writable: false,
+            executable: false,
+            offset: 0,
+            path: Some(path.display().to_string()),
+        };
+
+        Ok(Self {
+            name: display_name(path),
+            image: OfflineImage::new(file, vec![Segment::new(region, 0, size)?]),
+        })
+    }
+}
+
+impl MemorySource for RawDump {
+    fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>, MemscanError> {
+        self.image.read(address, size)
+    }
+
+    fn get_memory_regions(&self) -> Result<Vec<MemoryRegion>, MemscanError> {
+        Ok(self.image.regions())
+    }
+
+    fn describe(&self) -> String {
+        format!("raw dump {}", self.name)
+    }
+}
+
+pub(crate) fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// open a core file or memscan snapshot, telling them apart by magic
+pub fn open_memory_file(path: &Path) -> Result<Box<dyn MemorySource>, MemscanError> {
+    let mut magic = [0u8; 8];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+
+    if magic[..4] == crate::elf::ELF_MAGIC {
+        Ok(Box::new(crate::corefile::CoreFile::open(path)?))
+    } else if magic == crate::snapshot::SNAPSHOT_MAGIC {
+        Ok(Box::new(crate::snapshot::Snapshot::open(path)?))
+    } else {
+        Err(MemscanError::invalid_data(
+            "Not a core file or memscan snapshot (raw dumps need a base address: FILE@0xBASE)",
+        ))
+    }
+}
@@ -0,0 +1,234 @@
+// launching targets under memscan
+// built by the goat (danielscos)
+//
+// the child asks to be traced before exec, so it stops right after execve.
+// from there it runs to AT_ENTRY on a temporary int3: the program and its
+// libraries are loaded and relocated but main hasn't run yet. being our own
+// child it can be traced without root even with ptrace_scope=1
+
+use crate::error::MemscanError;
+use crate::threads;
+use nix::sys::ptrace;
+use nix::sys::signal::Signal;
+use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+use nix::unistd::Pid;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+const AT_ENTRY: u64 = 9;
+
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    // start from an empty environment instead of ours
+    pub clear_env: bool,
+    pub cwd: Option<PathBuf>,
+}
+
+impl SpawnOptions {
+    pub fn new(program: &str) -> Self {
+        Self {
+            program: program.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SpawnedProcess {
+    pid: u32,
+    entry: Option<usize>,
+    // still stopped under ptrace, waiting for resume()
+    stopped: bool,
+    exit_status: Option<i32>,
+}
+
+fn errno_error(context: &str, errno: nix::errno::Errno) -> MemscanError {
+    MemscanError::io(context, io::Error::from(errno))
+}
+
+pub fn spawn_stopped(options: &SpawnOptions) -> Result<SpawnedProcess, MemscanError> {
+    let mut command = Command::new(&options.program);
+    command.args(&options.args);
+    if options.clear_env {
+        command.env_clear();
+    }
+    command.envs(options.env.iter().map(|(k, v)| (k, v)));
+    if let Some(cwd) = &options.cwd {
+        command.current_dir(cwd);
+    }
+
+    // runs in the child between fork and exec
+    unsafe {
+        command.pre_exec(|| ptrace::traceme().map_err(io::Error::from));
+    }
+
+    let child = command
+        .spawn()
+        .map_err(|e| MemscanError::io(format!("Failed to start {}", options.program), e))?;
+    let pid = child.id();
+    let target = Pid::from_raw(pid as i32);
+
+    // the exec itself reports as SIGTRAP
+    match waitpid(target, None) {
+        Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => {}
+        Ok(WaitStatus::Exited(_, code)) => {
+            return Err(MemscanError::invalid_state(format!(
+                "{} exited with status {} before it could be stopped",
+                options.program, code
+            )));
+        }
+        Ok(other) => {
+            return Err(MemscanError::invalid_state(format!(
+                "Unexpected state after exec: {:?}",
+                other
+            )));
+        }
+        Err(e) => return Err(errno_error("waitpid failed", e)),
+    }
+
+    // don't leave the child running untraced if memscan dies first
+    let _ = ptrace::setoptions(target, ptrace::Options::PTRACE_O_EXITKILL);
+
+    let mut spawned = SpawnedProcess {
+        pid,
+        entry: None,
+        stopped: true,
+        exit_status: None,
+    };
+
+    if cfg!(target_arch = "x86_64")
+        && let Some(entry) = read_entry_point(pid)
+    {
+        if let Err(e) = run_to_entry(target, entry) {
+            let _ = spawned.kill();
+            return Err(e);
+        }
+        spawned.entry = Some(entry);
+    }
+
+    Ok(spawned)
+}
+
+// AT_ENTRY from the auxiliary vector, already relocated for PIE executables
+fn read_entry_point(pid: u32) -> Option<usize> {
+    let auxv = std::fs::read(format!("/proc/{}/auxv", pid)).ok()?;
+    auxv.chunks_exact(16).find_map(|pair| {
+        let key = u64::from_le_bytes(pair[..8].try_into().unwrap());
+        let value = u64::from_le_bytes(pair[8..].try_into().unwrap());
+        (key == AT_ENTRY).then_some(value as usize)
+    })
+}
+
+#[cfg(target_arch = "x86_64")]
+fn run_to_entry(target: Pid, entry: usize) -> Result<(), MemscanError> {
+    let address = entry as ptrace::AddressType;
+    let original = ptrace::read(target, address).map_err(|e| errno_error("peek failed", e))?;
+    let patched = (original & !0xff) | 0xcc;
+
+    unsafe { ptrace::write(target, address, patched as *mut libc::c_void) }
+        .map_err(|e| errno_error("poke failed", e))?;
+
+    let mut signal = None;
+    loop {
+        ptrace::cont(target, signal).map_err(|e| errno_error("continue failed", e))?;
+        match waitpid(target, None) {
+            Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => break,
+            // pass anything else through and keep going
+            Ok(WaitStatus::Stopped(_, sig)) => signal = Some(sig),
+            Ok(WaitStatus::Exited(_, code)) => {
+                return Err(MemscanError::invalid_state(format!(
+                    "Process exited with status {} before reaching its entry point",
+                    code
+                )));
+            }
+            Ok(WaitStatus::Signaled(_, sig, _)) => {
+                return Err(MemscanError::invalid_state(format!(
+                    "Process killed by {} before reaching its entry point",
+                    sig
+                )));
+            }
+            Ok(_) => signal = None,
+            Err(e) => return Err(errno_error("waitpid failed", e)),
+        }
+    }
+
+    // put the instruction back and rewind over the int3
+    unsafe { ptrace::write(target, address, original as *mut libc::c_void) }
+        .map_err(|e| errno_error("poke failed", e))?;
+    let tid = target.as_raw() as u32;
+    let mut registers = threads::getregs(tid)?;
+    registers.set("rip", entry as u64);
+    threads::setregs(tid, &registers)?;
+
+    Ok(())
+}
+
+// no int3 here, spawn_stopped leaves the process at the exec stop
+#[cfg(not(target_arch = "x86_64"))]
+fn run_to_entry(_target: Pid, _entry: usize) -> Result<(), MemscanError> {
+    Err(MemscanError::unsupported(
+        "Stopping at the entry point is only supported on x86_64",
+    ))
+}
+
+impl SpawnedProcess {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    // where the process is stopped, None if it's still at the exec stop
+    pub fn entry(&self) -> Option<usize> {
+        self.entry
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    // let it run. we detach so signals reach it normally, it stays our child
+    pub fn resume(&mut self) -> Result<(), MemscanError> {
+        if !self.stopped {
+            return Ok(());
+        }
+
+        ptrace::detach(Pid::from_raw(self.pid as i32), None)
+            .map_err(|e| errno_error("detach failed", e))?;
+        self.stopped = false;
+        Ok(())
+    }
+
+    pub fn kill(&mut self) -> Result<(), MemscanError> {
+        if self.exit_status.is_some() {
+            return Ok(());
+        }
+
+        let target = Pid::from_raw(self.pid as i32);
+        nix::sys::signal::kill(target, Signal::SIGKILL)
+            .map_err(|e| errno_error("kill failed", e))?;
+        let _ = waitpid(target, None);
+        self.stopped = false;
+        self.exit_status = Some(128 + Signal::SIGKILL as i32);
+        Ok(())
+    }
+
+    // reap the child if it has exited. returns its exit status (128 + signal
+    // for signal deaths) so it doesn't linger as a zombie
+    pub fn try_wait(&mut self) -> Option<i32> {
+        if self.exit_status.is_some() || self.stopped {
+            return self.exit_status;
+        }
+
+        let status = match waitpid(Pid::from_raw(self.pid as i32), Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::Exited(_, code)) => Some(code),
+            Ok(WaitStatus::Signaled(_, sig, _)) => Some(128 + sig as i32),
+            _ => None,
+        };
+        self.exit_status = status;
+        status
+    }
+}
@@ -0,0 +1,298 @@
+// running statistics for monitor targets
+// built by the goat (danielscos)
+//
+// updated by the monitor thread every time a target's value is first read or
+// changes. min/max/mean/stddev are over the values seen (Welford, so nothing
+// is kept per value), rates over the last few change times, and the
+// time-in-value table remembers how long each value was held
+
+use crate::monitor::{DataType, format_value};
+use crate::trigger::numeric_value;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// windows change rates are reported for
+pub const RATE_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+];
+
+// distinct values tracked before the rest go into OTHER_VALUES
+const MAX_DISTINCT: usize = 256;
+pub const OTHER_VALUES: &str = "(other)";
+
+#[derive(Debug, Clone, Default)]
+pub struct NumericStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    // sum of squared differences from the mean, see stddev()
+    m2: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TargetStats {
+    // values seen: the first read plus every change
+    pub samples: u64,
+    // None for strings, or until a value could be read
+    pub numeric: Option<NumericStats>,
+    // change times within the longest rate window
+    recent_changes: VecDeque<Instant>,
+    // value (as text) -> how long it was held, not counting the current one
+    held: HashMap<String, Duration>,
+    current: Option<(String, Instant)>,
+}
+
+impl NumericStats {
+    fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    // population standard deviation
+    pub fn stddev(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64).sqrt()
+        }
+    }
+}
+
+impl TargetStats {
+    // a value was read for the first time, or changed to this
+    pub fn record(&mut self, data_type: &DataType, value: &[u8], at: Instant) {
+        if self.samples > 0 {
+            self.recent_changes.push_back(at);
+            let longest = RATE_WINDOWS[RATE_WINDOWS.len() - 1];
+            while self
+                .recent_changes
+                .front()
+                .is_some_and(|&t| at.duration_since(t) > longest)
+            {
+                self.recent_changes.pop_front();
+            }
+        }
+        self.samples += 1;
+
+        // NaN would poison the mean, it only shows up in the value table
+        if let Some(number) = numeric_value(data_type, value).filter(|n| !n.is_nan()) {
+            self.numeric
+                .get_or_insert_with(Default::default)
+                .add(number);
+        }
+
+        let text = format_value(data_type, value);
+        if let Some((previous, since)) = self.current.take() {
+            let key = if self.held.contains_key(&previous) || self.held.len() < MAX_DISTINCT {
+                previous
+            } else {
+                OTHER_VALUES.to_string()
+            };
+            *self.held.entry(key).or_default() += at.duration_since(since);
+        }
+        self.current = Some((text, at));
+    }
+
+    // changes per second over the last window
+    pub fn change_rate(&self, window: Duration) -> f64 {
+        let now = Instant::now();
+        let changes = self
+            .recent_changes
+            .iter()
+            .filter(|&&t| now.duration_since(t) <= window)
+            .count();
+        changes as f64 / window.as_secs_f64()
+    }
+
+    // (value, time held) longest first, including the value held right now
+    pub fn time_in_value(&self) -> Vec<(String, Duration)> {
+        let mut held = self.held.clone();
+        if let Some((value, since)) = &self.current {
+            *held.entry(value.clone()).or_default() += since.elapsed();
+        }
+        let mut values: Vec<_> = held.into_iter().collect();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        values
+    }
+
+    // every value seen (up to the cap), sorted
+    pub fn distinct_values(&self) -> Vec<String> {
+        let mut values: Vec<String> = self
+            .held
+            .keys()
+            .chain(self.current.iter().map(|(value, _)| value))
+            .filter(|v| *v != OTHER_VALUES)
+            .cloned()
+            .collect();
+        values.sort();
+        values.dedup();
+        values
+    }
+
+    pub fn current_value(&self) -> Option<&str> {
+        self.current.as_ref().map(|(value, _)| value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn i32_bytes(value: i32) -> Vec<u8> {
+        value.to_le_bytes().to_vec()
+    }
+
+    fn record_all(stats: &mut TargetStats, data_type: &DataType, values: &[Vec<u8>]) {
+        let start = Instant::now();
+        for (i, value) in values.iter().enumerate() {
+            stats.record(data_type, value, start + Duration::from_secs(i as u64));
+        }
+    }
+
+    #[test]
+    fn mean_and_stddev() {
+        let mut stats = TargetStats::default();
+        let values: Vec<_> = [2, 4, 4, 4, 5, 5, 7, 9].map(i32_bytes).to_vec();
+        record_all(&mut stats, &DataType::I32, &values);
+
+        assert_eq!(stats.samples, 8);
+        let numeric = stats.numeric.clone().unwrap();
+        assert_eq!(numeric.count, 8);
+        assert_eq!(numeric.min, 2.0);
+        assert_eq!(numeric.max, 9.0);
+        assert!((numeric.mean - 5.0).abs() < 1e-12);
+        assert!((numeric.stddev() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn single_value_has_no_spread() {
+        let mut stats = TargetStats::default();
+        record_all(
+            &mut stats,
+            &DataType::I64,
+            &[(-7i64).to_le_bytes().to_vec()],
+        );
+        let numeric = stats.numeric.clone().unwrap();
+        assert_eq!((numeric.min, numeric.max, numeric.mean), (-7.0, -7.0, -7.0));
+        assert_eq!(numeric.stddev(), 0.0);
+    }
+
+    #[test]
+    fn nan_is_left_out_of_the_numbers() {
+        let mut stats = TargetStats::default();
+        let values: Vec<_> = [1.0, f64::NAN, 3.0]
+            .map(|v: f64| v.to_le_bytes().to_vec())
+            .to_vec();
+        record_all(&mut stats, &DataType::F64, &values);
+
+        assert_eq!(stats.samples, 3);
+        let numeric = stats.numeric.clone().unwrap();
+        assert_eq!(numeric.count, 2);
+        assert_eq!((numeric.min, numeric.max, numeric.mean), (1.0, 3.0, 2.0));
+        // still a value it held
+        assert!(stats.distinct_values().contains(&"NaN".to_string()));
+    }
+
+    #[test]
+    fn strings_have_no_numbers() {
+        let mut stats = TargetStats::default();
+        record_all(
+            &mut stats,
+            &DataType::String(8),
+            &[b"north\0".to_vec(), b"south\0".to_vec()],
+        );
+        assert!(stats.numeric.is_none());
+        assert_eq!(stats.distinct_values(), vec!["north", "south"]);
+        assert_eq!(stats.current_value(), Some("south"));
+    }
+
+    #[test]
+    fn time_in_value() {
+        let mut stats = TargetStats::default();
+        // in the future, so the value held right now adds nothing yet
+        let start = Instant::now() + Duration::from_secs(3600);
+        let at = |secs| start + Duration::from_secs(secs);
+        stats.record(&DataType::I32, &i32_bytes(1), at(0));
+        stats.record(&DataType::I32, &i32_bytes(2), at(3));
+        stats.record(&DataType::I32, &i32_bytes(1), at(4));
+        stats.record(&DataType::I32, &i32_bytes(3), at(10));
+
+        assert_eq!(
+            stats.time_in_value(),
+            vec![
+                ("1".to_string(), Duration::from_secs(9)),
+                ("2".to_string(), Duration::from_secs(1)),
+                ("3".to_string(), Duration::ZERO),
+            ]
+        );
+    }
+
+    #[test]
+    fn distinct_values_overflow() {
+        let mut stats = TargetStats::default();
+        let values: Vec<_> = (0..MAX_DISTINCT as i32 + 44).map(i32_bytes).collect();
+        record_all(&mut stats, &DataType::I32, &values);
+
+        // the first MAX_DISTINCT held values plus the current one
+        let distinct = stats.distinct_values();
+        assert_eq!(distinct.len(), MAX_DISTINCT + 1);
+        assert!(!distinct.contains(&OTHER_VALUES.to_string()));
+
+        // the 43 values past the cap were held a second each
+        let other = stats
+            .time_in_value()
+            .into_iter()
+            .find(|(value, _)| value == OTHER_VALUES)
+            .unwrap();
+        assert_eq!(other.1, Duration::from_secs(43));
+
+        // a value already in the table keeps its own entry
+        let before = stats.held["0"];
+        let at = Instant::now() + Duration::from_secs(1000);
+        stats.record(&DataType::I32, &i32_bytes(0), at);
+        stats.record(&DataType::I32, &i32_bytes(1), at + Duration::from_secs(5));
+        assert_eq!(stats.held["0"], before + Duration::from_secs(5));
+    }
+
+    #[test]
+    fn change_rates() {
+        let mut stats = TargetStats::default();
+        let now = Instant::now();
+        let ago = |ms| now.checked_sub(Duration::from_millis(ms)).unwrap();
+        // the first read isn't a change
+        for at in [ago(30_000), ago(20_000), ago(5_000), ago(500)] {
+            stats.record(&DataType::I32, &i32_bytes(0), at);
+        }
+
+        assert_eq!(stats.change_rate(RATE_WINDOWS[0]), 1.0);
+        assert_eq!(stats.change_rate(RATE_WINDOWS[1]), 0.2);
+        assert_eq!(stats.change_rate(RATE_WINDOWS[2]), 3.0 / 60.0);
+    }
+
+    #[test]
+    fn old_changes_are_dropped() {
+        let mut stats = TargetStats::default();
+        let start = Instant::now();
+        for secs in [0, 10, 80, 100] {
+            stats.record(
+                &DataType::I32,
+                &i32_bytes(secs as i32),
+                start + Duration::from_secs(secs),
+            );
+        }
+        // only the changes within a minute of the last one stay
+        assert_eq!(stats.recent_changes.len(), 2);
+    }
+}
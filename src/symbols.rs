@@ -0,0 +1,264 @@
+// symbol tables of loaded modules
+// built by the goat (danielscos)
+//
+// .symtab when the file has one, .dynsym otherwise (stripped binaries still
+// export their dynamic symbols). tables are cached per file so resolving lots
+// of addresses only parses each library once
+
+use crate::elf::{self, ElfFile, SHT_DYNSYM, SHT_SYMTAB};
+use crate::error::MemscanError;
+use crate::modules::{self, Module};
+use crate::source::MemorySource;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const SYM_SIZE: usize = 24;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+const STT_GNU_IFUNC: u8 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Object,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    // link-time address, add the module's load bias for the runtime one
+    pub address: usize,
+    pub size: usize,
+    pub kind: SymbolKind,
+}
+
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    // sorted by address
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&i| &self.symbols[i])
+    }
+
+    // the symbol covering a link-time address and the offset into it
+    pub fn lookup(&self, address: usize) -> Option<(&Symbol, usize)> {
+        let index = self.symbols.partition_point(|s| s.address <= address);
+
+        // walk back over symbols starting at or before the address, the
+        // closest one that covers it wins (nested/aliased symbols are common)
+        self.symbols[..index]
+            .iter()
+            .rev()
+            .take(8)
+            .find(|s| address < s.address + s.size.max(1))
+            .map(|s| (s, address - s.address))
+    }
+}
+
+// a file as it was when parsed, so a rebuilt binary gets parsed again
+pub(crate) type CacheKey = (String, u64, Option<SystemTime>);
+
+pub(crate) fn file_key(path: &Path) -> Result<CacheKey, MemscanError> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+    Ok((
+        path.to_string_lossy().to_string(),
+        metadata.len(),
+        metadata.modified().ok(),
+    ))
+}
+
+// the file-backed mappings a module list is built from. while they stay the
+// same a source's cached symbolizer is still right
+type Layout = Vec<(usize, usize, u64, String)>;
+
+lazy_static::lazy_static! {
+    static ref SYMBOL_CACHE: Mutex<HashMap<CacheKey, Arc<SymbolTable>>> = Mutex::new(HashMap::new());
+    static ref SYMBOLIZER_CACHE: Mutex<HashMap<String, (Layout, Arc<Symbolizer>)>> = Mutex::new(HashMap::new());
+}
+
+// parse (or fetch from the cache) the symbols of an ELF file
+pub fn load_symbols(path: &Path) -> Result<Arc<SymbolTable>, MemscanError> {
+    let key = file_key(path)?;
+    if let Some(table) = SYMBOL_CACHE.lock().unwrap().get(&key) {
+        return Ok(Arc::clone(table));
+    }
+
+    let table = Arc::new(parse_symbols(&ElfFile::open(path)?));
+    SYMBOL_CACHE.lock().unwrap().insert(key, Arc::clone(&table));
+    Ok(table)
+}
+
+fn parse_symbols(file: &ElfFile) -> SymbolTable {
+    let has_symtab = file.sections.iter().any(|(_, sh)| sh.sh_type == SHT_SYMTAB);
+    let wanted = if has_symtab { SHT_SYMTAB } else { SHT_DYNSYM };
+
+    let mut symbols = Vec::new();
+    for (_, section) in file.sections.iter().filter(|(_, sh)| sh.sh_type == wanted) {
+        let strings = match file.sections.get(section.sh_link as usize) {
+            Some((_, strtab)) => file.section_data(strtab),
+            None => continue,
+        };
+
+        for entry in file.section_data(section).chunks_exact(SYM_SIZE) {
+            let info = entry[4];
+            let shndx = elf::u16_at(entry, 6).unwrap_or(0);
+            let value = elf::u64_at(entry, 8).unwrap_or(0) as usize;
+            let size = elf::u64_at(entry, 16).unwrap_or(0) as usize;
+
+            let kind = match info & 0xf {
+                STT_FUNC | STT_GNU_IFUNC => SymbolKind::Function,
+                STT_OBJECT => SymbolKind::Object,
+                _ => continue,
+            };
+            // undefined (imported) symbols have no address in this module
+            if shndx == 0 || value == 0 {
+                continue;
+            }
+
+            let name = match elf::str_at(strings, elf::u32_at(entry, 0).unwrap_or(0) as usize) {
+                Some(n) if !n.is_empty() => n.to_string(),
+                _ => continue,
+            };
+
+            symbols.push(Symbol {
+                name,
+                address: value,
+                size,
+                kind,
+            });
+        }
+    }
+
+    symbols.sort_by(|a, b| a.address.cmp(&b.address).then(a.name.cmp(&b.name)));
+    symbols.dedup_by(|a, b| a.address == b.address && a.name == b.name);
+
+    let mut by_name = HashMap::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        by_name.entry(symbol.name.clone()).or_insert(i);
+    }
+
+    SymbolTable { symbols, by_name }
+}
+
+// address <-> symbol translation for one process (or core/snapshot)
+pub struct Symbolizer {
+    modules: Vec<Module>,
+    tables: Mutex<HashMap<String, Option<Arc<SymbolTable>>>>,
+}
+
+impl Symbolizer {
+    pub fn new(source: &dyn MemorySource) -> Result<Self, MemscanError> {
+        Ok(Self::from_modules(modules::list_modules(source)?))
+    }
+
+    // symbolizer for a source, built once and reused until the source maps
+    // or unmaps a file. only the maps are read to check that
+    pub fn cached(source: &dyn MemorySource) -> Result<Arc<Self>, MemscanError> {
+        let layout: Layout = source
+            .get_memory_regions()?
+            .into_iter()
+            .filter_map(|r| {
+                let path = r.path.filter(|p| p.starts_with('/'))?;
+                Some((r.start_address, r.size, r.offset, path))
+            })
+            .collect();
+        let key = source.describe();
+
+        if let Some((cached_layout, symbolizer)) = SYMBOLIZER_CACHE.lock().unwrap().get(&key)
+            && *cached_layout == layout
+        {
+            return Ok(Arc::clone(symbolizer));
+        }
+
+        let symbolizer = Arc::new(Self::new(source)?);
+        SYMBOLIZER_CACHE
+            .lock()
+            .unwrap()
+            .insert(key, (layout, Arc::clone(&symbolizer)));
+        Ok(symbolizer)
+    }
+
+    pub fn from_modules(modules: Vec<Module>) -> Self {
+        Self {
+            modules,
+            tables: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    pub fn symbols(&self, module: &Module) -> Option<Arc<SymbolTable>> {
+        let mut tables = self.tables.lock().unwrap();
+        tables
+            .entry(module.path.clone())
+            .or_insert_with(|| load_symbols(&module.file).ok())
+            .clone()
+    }
+
+    // "module!symbol+0x1c", "module+0x4028" outside any symbol, None outside modules
+    pub fn describe(&self, address: usize) -> Option<String> {
+        let module = modules::module_at(&self.modules, address)?;
+
+        if let Some(table) = self.symbols(module)
+            && let Some((symbol, offset)) = table.lookup(address.wrapping_sub(module.load_bias))
+        {
+            return Some(if offset == 0 {
+                format!("{}!{}", module.name, symbol.name)
+            } else {
+                format!("{}!{}+0x{:x}", module.name, symbol.name, offset)
+            });
+        }
+
+        Some(format!("{}+0x{:x}", module.name, address - module.start))
+    }
+
+    // runtime address of "module!symbol". an empty module searches them all
+    pub fn resolve(&self, module_name: &str, symbol_name: &str) -> Result<usize, MemscanError> {
+        let candidates: Vec<&Module> = if module_name.is_empty() {
+            self.modules.iter().collect()
+        } else {
+            let module = modules::find_module(&self.modules, module_name)
+                .ok_or_else(|| MemscanError::not_found(format!("Module '{}'", module_name)))?;
+            vec![module]
+        };
+
+        for module in candidates {
+            if let Some(table) = self.symbols(module)
+                && let Some(symbol) = table.find(symbol_name)
+            {
+                return Ok(module.load_bias.wrapping_add(symbol.address));
+            }
+        }
+
+        Err(MemscanError::not_found(format!(
+            "Symbol '{}!{}'",
+            module_name, symbol_name
+        )))
+    }
+
+    // start of a module by name, for "module+0x1234"
+    pub fn module_base(&self, module_name: &str) -> Option<usize> {
+        modules::find_module(&self.modules, module_name).map(|m| m.start)
+    }
+}
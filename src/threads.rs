@@ -0,0 +1,305 @@
+// threads and their registers
+// built by the goat (danielscos)
+//
+// tasks come from /proc/PID/task. registers need the thread in a ptrace stop:
+// we seize + interrupt it for the duration of the read/write and detach right
+// after, unless we're already its tracer (a process started by 'spawn')
+
+use crate::error::MemscanError;
+use nix::sys::ptrace;
+use nix::sys::wait::{WaitPidFlag, waitpid};
+use nix::unistd::Pid;
+
+#[derive(Debug, Clone)]
+pub struct ThreadInfo {
+    pub tid: u32,
+    pub name: String,
+    pub state: char,
+}
+
+// user_regs_struct field names, in struct (and elf_gregset_t) order
+#[cfg(target_arch = "x86_64")]
+pub const REGISTER_NAMES: [&str; 27] = [
+    "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx", "rsi",
+    "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds", "es", "fs",
+    "gs",
+];
+#[cfg(not(target_arch = "x86_64"))]
+pub const REGISTER_NAMES: [&str; 0] = [];
+
+// the order `regs` prints them in
+pub const DISPLAY_ORDER: [&str; 21] = [
+    "rip", "rsp", "rbp", "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12",
+    "r13", "r14", "r15", "eflags", "orig_rax", "fs_base", "gs_base",
+];
+
+#[derive(Debug, Clone)]
+pub struct Registers {
+    values: Vec<u64>,
+}
+
+impl Registers {
+    // names are case-insensitive, pc and sp work as aliases
+    pub fn get(&self, name: &str) -> Option<u64> {
+        register_index(name).map(|i| self.values[i])
+    }
+
+    pub fn set(&mut self, name: &str, value: u64) -> bool {
+        match register_index(name) {
+            Some(i) => {
+                self.values[i] = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // (name, value) pairs in struct order
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        REGISTER_NAMES
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+
+    // raw elf_gregset_t bytes, as stored in core file notes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+}
+
+pub fn is_register(name: &str) -> bool {
+    register_index(name).is_some()
+}
+
+fn register_index(name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    let name = match name.as_str() {
+        "pc" => "rip",
+        "sp" => "rsp",
+        "flags" => "eflags",
+        other => other,
+    };
+    REGISTER_NAMES.iter().position(|&n| n == name)
+}
+
+pub fn list_threads(pid: u32) -> Result<Vec<ThreadInfo>, MemscanError> {
+    let mut threads = Vec::new();
+
+    for tid in list_tasks(pid) {
+        let base = format!("/proc/{}/task/{}", pid, tid);
+        let name = std::fs::read_to_string(format!("{}/comm", base))
+            .map(|c| c.trim().to_string())
+            .unwrap_or_default();
+
+        threads.push(ThreadInfo {
+            tid: tid as u32,
+            name,
+            state: thread_state(tid as u32).unwrap_or('?'),
+        });
+    }
+
+    if threads.is_empty() && !std::path::Path::new(&format!("/proc/{}", pid)).exists() {
+        return Err(MemscanError::ProcessNotFound { pid });
+    }
+
+    Ok(threads)
+}
+
+pub(crate) fn list_tasks(pid: u32) -> Vec<i32> {
+    let mut tids: Vec<i32> = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.file_name().to_string_lossy().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if tids.is_empty() {
+        tids.push(pid as i32);
+    }
+    tids.sort_unstable();
+    tids
+}
+
+// a thread held in a ptrace stop, released on drop
+struct ThreadStop {
+    tid: Pid,
+    attached: bool,
+}
+
+impl ThreadStop {
+    fn new(tid: u32) -> Result<Self, MemscanError> {
+        let target = Pid::from_raw(tid as i32);
+
+        match ptrace::seize(target, ptrace::Options::empty()) {
+            Ok(()) => {
+                let stop = Self {
+                    tid: target,
+                    attached: true,
+                };
+                ptrace::interrupt(target).map_err(|e| ptrace_error("interrupt", tid, e))?;
+                waitpid(target, Some(WaitPidFlag::__WALL))
+                    .map_err(|e| ptrace_error("waitpid", tid, e))?;
+                Ok(stop)
+            }
+            // we may already be tracing it, in which case it has to be stopped
+            Err(e) if traced_by_us(tid) => {
+                if thread_state(tid) == Some('t') {
+                    Ok(Self {
+                        tid: target,
+                        attached: false,
+                    })
+                } else {
+                    Err(ptrace_error("attach", tid, e))
+                }
+            }
+            Err(e) => Err(ptrace_error("attach", tid, e)),
+        }
+    }
+}
+
+impl Drop for ThreadStop {
+    fn drop(&mut self) {
+        if self.attached {
+            let _ = ptrace::detach(self.tid, None);
+        }
+    }
+}
+
+// permission and gone-process errors keep their diagnosis, anything else
+// says what we were doing
+pub(crate) fn ptrace_error(action: &str, tid: u32, errno: nix::errno::Errno) -> MemscanError {
+    match MemscanError::from_errno(errno, tid, None) {
+        MemscanError::Io { source, .. } => {
+            MemscanError::io(format!("Failed to {} thread {}", action, tid), source)
+        }
+        other => other,
+    }
+}
+
+fn traced_by_us(tid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/status", tid))
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|l| l.strip_prefix("TracerPid:"))
+                .and_then(|v| v.trim().parse::<u32>().ok())
+        })
+        .is_some_and(|tracer| tracer == std::process::id())
+}
+
+// the state follows the last ')' so thread names can't confuse it
+fn thread_state(tid: u32) -> Option<char> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", tid)).ok()?;
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().next()?.chars().next()
+}
+
+pub fn read_registers(tid: u32) -> Result<Registers, MemscanError> {
+    let _stop = ThreadStop::new(tid)?;
+    getregs(tid)
+}
+
+// read-modify-write of a single register, returns the new register set
+pub fn write_register(tid: u32, name: &str, value: u64) -> Result<Registers, MemscanError> {
+    let _stop = ThreadStop::new(tid)?;
+    let mut registers = getregs(tid)?;
+    if !registers.set(name, value) {
+        return Err(MemscanError::not_found(format!("Register '{}'", name)));
+    }
+    setregs(tid, &registers)?;
+    Ok(registers)
+}
+
+// the caller must already have the thread in a ptrace stop
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn getregs(tid: u32) -> Result<Registers, MemscanError> {
+    let regs = ptrace::getregs(Pid::from_raw(tid as i32))
+        .map_err(|e| ptrace_error("read registers of", tid, e))?;
+
+    let values = vec![
+        regs.r15,
+        regs.r14,
+        regs.r13,
+        regs.r12,
+        regs.rbp,
+        regs.rbx,
+        regs.r11,
+        regs.r10,
+        regs.r9,
+        regs.r8,
+        regs.rax,
+        regs.rcx,
+        regs.rdx,
+        regs.rsi,
+        regs.rdi,
+        regs.orig_rax,
+        regs.rip,
+        regs.cs,
+        regs.eflags,
+        regs.rsp,
+        regs.ss,
+        regs.fs_base,
+        regs.gs_base,
+        regs.ds,
+        regs.es,
+        regs.fs,
+        regs.gs,
+    ];
+
+    Ok(Registers { values })
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn setregs(tid: u32, registers: &Registers) -> Result<(), MemscanError> {
+    let v = &registers.values;
+    let regs = libc::user_regs_struct {
+        r15: v[0],
+        r14: v[1],
+        r13: v[2],
+        r12: v[3],
+        rbp: v[4],
+        rbx: v[5],
+        r11: v[6],
+        r10: v[7],
+        r9: v[8],
+        r8: v[9],
+        rax: v[10],
+        rcx: v[11],
+        rdx: v[12],
+        rsi: v[13],
+        rdi: v[14],
+        orig_rax: v[15],
+        rip: v[16],
+        cs: v[17],
+        eflags: v[18],
+        rsp: v[19],
+        ss: v[20],
+        fs_base: v[21],
+        gs_base: v[22],
+        ds: v[23],
+        es: v[24],
+        fs: v[25],
+        gs: v[26],
+    };
+
+    ptrace::setregs(Pid::from_raw(tid as i32), regs)
+        .map_err(|e| ptrace_error("write registers of", tid, e))
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn getregs(_tid: u32) -> Result<Registers, MemscanError> {
+    Err(MemscanError::unsupported(
+        "Register access is only supported on x86_64",
+    ))
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn setregs(_tid: u32, _registers: &Registers) -> Result<(), MemscanError> {
+    Err(MemscanError::unsupported(
+        "Register access is only supported on x86_64",
+    ))
+}
@@ -0,0 +1,1220 @@
+// hardware watchpoints and software breakpoints
+// built by the goat (danielscos)
+//
+// a Tracer seizes every thread of a process and keeps them running under
+// ptrace. ptrace requests only work from the thread that attached, so the
+// tracer gets a thread of its own and the Tracer handle talks to it over a
+// channel. watchpoints live in the x86_64 debug registers: DR0-DR3 hold the
+// addresses, DR7 enables them. registers are per thread and not inherited,
+// threads created later get them on their first stop. the cpu traps after
+// the instruction that touched the address, so the reported rip is the
+// instruction right after the access
+//
+// breakpoints replace the first byte of an instruction with int3 (0xcc).
+// to get past one the original byte goes back for a single step with every
+// other thread stopped, so none of them can run through it unseen
+//
+// children are traced from their first instruction so they can't trip over
+// an int3. a forked child gets the original bytes back in its copy of the
+// memory and is let go. a vforked child shares the parent's memory until it
+// execs, so it's traced like one of the threads until then
+
+use crate::error::MemscanError;
+use crate::process::ProcessHandle;
+use crate::threads::{Registers, getregs, list_tasks, ptrace_error, setregs};
+use nix::errno::Errno;
+use nix::sys::ptrace;
+use nix::sys::signal::Signal;
+use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+use nix::unistd::Pid;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// DR0-DR3
+pub const MAX_WATCHPOINTS: usize = 4;
+
+// offsetof(struct user, u_debugreg)
+const DEBUG_REGISTERS: usize = 848;
+
+const INT3: u8 = 0xcc;
+
+fn trace_options() -> ptrace::Options {
+    ptrace::Options::PTRACE_O_TRACECLONE
+        | ptrace::Options::PTRACE_O_TRACEFORK
+        | ptrace::Options::PTRACE_O_TRACEVFORK
+        | ptrace::Options::PTRACE_O_TRACEVFORKDONE
+        | ptrace::Options::PTRACE_O_TRACEEXEC
+}
+
+// nap between waitpid rounds when nothing happened
+const IDLE_SLICE: Duration = Duration::from_millis(1);
+
+// how long a detach waits for a vforked child to let its parent go
+const VFORK_WAIT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchKind {
+    Write,
+    // reads and writes, x86 has no read-only watchpoints
+    Access,
+}
+
+impl fmt::Display for WatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchKind::Write => write!(f, "write"),
+            WatchKind::Access => write!(f, "access"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Watchpoint {
+    pub id: usize,
+    pub address: usize,
+    pub len: usize,
+    pub kind: WatchKind,
+    pub hits: u64,
+    // rip after the access -> times: the instructions that touch the address
+    pub sites: BTreeMap<usize, u64>,
+    // the debug register it sits in
+    slot: usize,
+    // value at the last hit, what the next hit is compared to
+    last_value: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchpointHit {
+    pub watchpoint_id: usize,
+    pub tid: u32,
+    pub rip: usize,
+    pub address: usize,
+    pub kind: WatchKind,
+    pub old_value: Vec<u8>,
+    pub new_value: Vec<u8>,
+    pub timestamp: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: usize,
+    pub address: usize,
+    // keep going after a hit instead of stopping the process
+    pub auto_continue: bool,
+    pub hits: u64,
+    // thread id -> hits, who runs this code
+    pub threads: BTreeMap<u32, u64>,
+    pub last_registers: Option<Registers>,
+    // the byte the int3 replaced
+    original: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakpointHit {
+    pub breakpoint_id: usize,
+    pub tid: u32,
+    pub address: usize,
+    // hits so far, this one included
+    pub hits: u64,
+    // rip already points back at the breakpoint
+    pub registers: Registers,
+    // the process is stopped until Tracer::resume
+    pub stopped: bool,
+    pub timestamp: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub enum TraceEvent {
+    WatchpointHit(WatchpointHit),
+    BreakpointHit(BreakpointHit),
+    // every traced thread is gone, the tracer stops itself after this
+    ProcessExited {
+        pid: u32,
+    },
+    // the process ran a new program, the breakpoints and watchpoints set in
+    // the old one were dropped. tracing goes on
+    Exec {
+        pid: u32,
+        breakpoints: usize,
+        watchpoints: usize,
+    },
+    // always the last event, the channel is closed after it
+    Detached,
+}
+
+type Subscribers = Arc<Mutex<Vec<Sender<TraceEvent>>>>;
+
+fn publish(subscribers: &Subscribers, event: TraceEvent) {
+    subscribers
+        .lock()
+        .unwrap()
+        .retain(|sender| sender.send(event.clone()).is_ok());
+}
+
+// what the Tracer asks its thread to do, the answer goes back on reply
+enum Request {
+    AddWatchpoint {
+        address: usize,
+        len: usize,
+        kind: WatchKind,
+        reply: Sender<Result<usize, MemscanError>>,
+    },
+    RemoveWatchpoint {
+        id: usize,
+        reply: Sender<Result<Watchpoint, MemscanError>>,
+    },
+    AddBreakpoint {
+        address: usize,
+        auto_continue: bool,
+        reply: Sender<Result<usize, MemscanError>>,
+    },
+    RemoveBreakpoint {
+        id: usize,
+        reply: Sender<Result<Breakpoint, MemscanError>>,
+    },
+    Resume {
+        reply: Sender<Result<(), MemscanError>>,
+    },
+    Detach {
+        reply: Sender<()>,
+    },
+}
+
+pub struct Tracer {
+    pid: u32,
+    requests: Sender<Request>,
+    watchpoints: Arc<Mutex<Vec<Watchpoint>>>,
+    breakpoints: Arc<Mutex<Vec<Breakpoint>>>,
+    paused: Arc<AtomicBool>,
+    subscribers: Subscribers,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Tracer {
+    // seizes every thread of the process, they keep running
+    pub fn attach(handle: Arc<ProcessHandle>) -> Result<Self, MemscanError> {
+        if !cfg!(target_arch = "x86_64") {
+            return Err(MemscanError::unsupported(
+                "Watchpoints and breakpoints are only supported on x86_64",
+            ));
+        }
+
+        let pid = handle.pid();
+        let watchpoints = Arc::new(Mutex::new(Vec::new()));
+        let breakpoints = Arc::new(Mutex::new(Vec::new()));
+        let paused = Arc::new(AtomicBool::new(false));
+        let subscribers: Subscribers = Arc::new(Mutex::new(Vec::new()));
+        let (requests, receiver) = channel();
+        let (ready, attached) = channel();
+
+        let mut state = TraceState {
+            pid,
+            handle,
+            threads: BTreeMap::new(),
+            watchpoints: Arc::clone(&watchpoints),
+            breakpoints: Arc::clone(&breakpoints),
+            paused: Arc::clone(&paused),
+            pause_requested: false,
+            subscribers: Arc::clone(&subscribers),
+            next_watchpoint_id: 1,
+            next_breakpoint_id: 1,
+        };
+
+        let thread = thread::spawn(move || match state.attach_all() {
+            Ok(()) => {
+                let _ = ready.send(Ok(()));
+                state.run(receiver);
+            }
+            Err(e) => {
+                state.detach_all();
+                let _ = ready.send(Err(e));
+            }
+        });
+
+        match attached.recv() {
+            Ok(Ok(())) => Ok(Self {
+                pid,
+                requests,
+                watchpoints,
+                breakpoints,
+                paused,
+                subscribers,
+                thread: Some(thread),
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err(MemscanError::invalid_state(
+                    "Tracer thread died while attaching",
+                ))
+            }
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.thread.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    // stopped at a breakpoint, waiting for resume
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn subscribe(&self) -> Receiver<TraceEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    // len is 1, 2, 4 or 8 and the address has to be aligned to it.
+    // returns the watchpoint's id
+    pub fn add_watchpoint(
+        &self,
+        address: usize,
+        len: usize,
+        kind: WatchKind,
+    ) -> Result<usize, MemscanError> {
+        if ![1, 2, 4, 8].contains(&len) {
+            return Err(MemscanError::invalid_value(
+                &len.to_string(),
+                "a watchpoint size of 1, 2, 4 or 8 bytes",
+            ));
+        }
+        if !address.is_multiple_of(len) {
+            return Err(MemscanError::invalid_address(
+                &format!("0x{:x}", address),
+                format!(
+                    "a {} byte watchpoint needs a {} byte aligned address",
+                    len, len
+                ),
+            ));
+        }
+
+        let (reply, answer) = channel();
+        self.request(Request::AddWatchpoint {
+            address,
+            len,
+            kind,
+            reply,
+        })?;
+        answer.recv().unwrap_or_else(|_| Err(self.gone()))
+    }
+
+    pub fn remove_watchpoint(&self, id: usize) -> Result<Watchpoint, MemscanError> {
+        let (reply, answer) = channel();
+        self.request(Request::RemoveWatchpoint { id, reply })?;
+        answer.recv().unwrap_or_else(|_| Err(self.gone()))
+    }
+
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.watchpoints.lock().unwrap().clone()
+    }
+
+    // the address has to be the first byte of an instruction in executable
+    // memory. returns the breakpoint's id
+    pub fn add_breakpoint(
+        &self,
+        address: usize,
+        auto_continue: bool,
+    ) -> Result<usize, MemscanError> {
+        let (reply, answer) = channel();
+        self.request(Request::AddBreakpoint {
+            address,
+            auto_continue,
+            reply,
+        })?;
+        answer.recv().unwrap_or_else(|_| Err(self.gone()))
+    }
+
+    // puts the original byte back
+    pub fn remove_breakpoint(&self, id: usize) -> Result<Breakpoint, MemscanError> {
+        let (reply, answer) = channel();
+        self.request(Request::RemoveBreakpoint { id, reply })?;
+        answer.recv().unwrap_or_else(|_| Err(self.gone()))
+    }
+
+    pub fn breakpoints(&self) -> Vec<Breakpoint> {
+        self.breakpoints.lock().unwrap().clone()
+    }
+
+    // lets a process stopped at a breakpoint run again, stepping over it
+    pub fn resume(&self) -> Result<(), MemscanError> {
+        let (reply, answer) = channel();
+        self.request(Request::Resume { reply })?;
+        answer.recv().unwrap_or_else(|_| Err(self.gone()))
+    }
+
+    // clears the debug registers, restores the bytes under the breakpoints
+    // and lets go of every thread
+    pub fn detach(mut self) -> (Vec<Watchpoint>, Vec<Breakpoint>) {
+        self.shutdown();
+        (self.watchpoints(), self.breakpoints())
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let (reply, answer) = channel();
+            if self.requests.send(Request::Detach { reply }).is_ok() {
+                let _ = answer.recv();
+            }
+            let _ = thread.join();
+        }
+    }
+
+    fn request(&self, request: Request) -> Result<(), MemscanError> {
+        self.requests.send(request).map_err(|_| self.gone())
+    }
+
+    fn gone(&self) -> MemscanError {
+        MemscanError::invalid_state(format!("No longer tracing process {}", self.pid))
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[derive(Debug, Default)]
+struct TracedThread {
+    stopped: bool,
+    // in a group-stop (SIGSTOP and friends), resumed with PTRACE_LISTEN so it
+    // stays stopped until someone sends SIGCONT
+    group_stop: bool,
+    // signal to deliver when it's resumed
+    signal: Option<Signal>,
+    // a new thread that hasn't had the debug registers set yet
+    fresh: bool,
+    // stopped on the int3 at this address (rip already moved back to it),
+    // it has to step over the original instruction before it runs on
+    at_breakpoint: Option<usize>,
+    // a vfork child, let go once it execs
+    vfork_child: bool,
+    // waiting in the kernel for its vfork child to exec or exit. it can't
+    // run any code until then, and can't be stopped either
+    in_vfork: bool,
+}
+
+// everything the tracer thread owns
+struct TraceState {
+    pid: u32,
+    handle: Arc<ProcessHandle>,
+    threads: BTreeMap<u32, TracedThread>,
+    watchpoints: Arc<Mutex<Vec<Watchpoint>>>,
+    breakpoints: Arc<Mutex<Vec<Breakpoint>>>,
+    // every thread is held at a breakpoint hit
+    paused: Arc<AtomicBool>,
+    // a breakpoint without auto_continue was hit, pause once everything
+    // is stopped
+    pause_requested: bool,
+    subscribers: Subscribers,
+    next_watchpoint_id: usize,
+    next_breakpoint_id: usize,
+}
+
+impl TraceState {
+    // threads can appear while we go through the list, so go round again
+    // until a pass finds nothing new
+    fn attach_all(&mut self) -> Result<(), MemscanError> {
+        loop {
+            let mut found = false;
+            for tid in list_tasks(self.pid) {
+                let tid = tid as u32;
+                if self.threads.contains_key(&tid) {
+                    continue;
+                }
+                match ptrace::seize(pid(tid), trace_options()) {
+                    Ok(()) => {
+                        self.threads.insert(tid, TracedThread::default());
+                        found = true;
+                    }
+                    // a thread that exited in the meantime
+                    Err(Errno::ESRCH) if tid != self.pid => {}
+                    Err(e) => return Err(ptrace_error("attach to", tid, e)),
+                }
+            }
+            if !found {
+                return Ok(());
+            }
+        }
+    }
+
+    fn run(&mut self, requests: Receiver<Request>) {
+        loop {
+            match requests.try_recv() {
+                Ok(Request::Detach { reply }) => {
+                    self.detach_all();
+                    let _ = reply.send(());
+                    break;
+                }
+                Ok(request) => self.handle_request(request),
+                Err(TryRecvError::Disconnected) => {
+                    self.detach_all();
+                    break;
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+
+            let mut idle = true;
+            let tids: Vec<u32> = self.threads.keys().copied().collect();
+            for tid in tids {
+                match waitpid(pid(tid), Some(WaitPidFlag::__WALL | WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::StillAlive) => {}
+                    Ok(status) => {
+                        idle = false;
+                        self.handle_status(tid, status, false);
+                    }
+                    Err(_) => {
+                        self.threads.remove(&tid);
+                    }
+                }
+            }
+
+            // threads that hit a breakpoint wait for the rest to stop, then
+            // either everyone steps over it and goes on or the process pauses
+            if !self.is_paused() && self.threads.values().any(|t| t.at_breakpoint.is_some()) {
+                self.stop_all();
+                if self.pause_requested {
+                    self.paused.store(true, Ordering::Relaxed);
+                } else {
+                    self.resume_all();
+                }
+            }
+
+            if self.threads.is_empty() {
+                publish(
+                    &self.subscribers,
+                    TraceEvent::ProcessExited { pid: self.pid },
+                );
+                break;
+            }
+            if idle {
+                thread::sleep(IDLE_SLICE);
+            }
+        }
+
+        self.paused.store(false, Ordering::Relaxed);
+        publish(&self.subscribers, TraceEvent::Detached);
+        self.subscribers.lock().unwrap().clear();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn handle_request(&mut self, request: Request) {
+        match request {
+            Request::AddWatchpoint {
+                address,
+                len,
+                kind,
+                reply,
+            } => {
+                let result =
+                    self.with_all_stopped(|state| state.add_watchpoint(address, len, kind));
+                let _ = reply.send(result);
+            }
+            Request::RemoveWatchpoint { id, reply } => {
+                let result = self.with_all_stopped(|state| state.remove_watchpoint(id));
+                let _ = reply.send(result);
+            }
+            Request::AddBreakpoint {
+                address,
+                auto_continue,
+                reply,
+            } => {
+                let result =
+                    self.with_all_stopped(|state| state.add_breakpoint(address, auto_continue));
+                let _ = reply.send(result);
+            }
+            Request::RemoveBreakpoint { id, reply } => {
+                let result = self.with_all_stopped(|state| state.remove_breakpoint(id));
+                let _ = reply.send(result);
+            }
+            Request::Resume { reply } => {
+                let result = if self.is_paused() {
+                    self.pause_requested = false;
+                    self.paused.store(false, Ordering::Relaxed);
+                    self.resume_all();
+                    Ok(())
+                } else {
+                    Err(MemscanError::invalid_state(format!(
+                        "PID {} is not stopped at a breakpoint",
+                        self.pid
+                    )))
+                };
+                let _ = reply.send(result);
+            }
+            Request::Detach { reply } => {
+                let _ = reply.send(());
+            }
+        }
+    }
+
+    // registers and code only change with the whole process stopped, a
+    // paused process stays paused afterwards
+    fn with_all_stopped<T>(&mut self, change: impl FnOnce(&mut Self) -> T) -> T {
+        self.stop_all();
+        let result = change(self);
+        if !self.is_paused() {
+            self.resume_all();
+        }
+        result
+    }
+
+    fn add_watchpoint(
+        &mut self,
+        address: usize,
+        len: usize,
+        kind: WatchKind,
+    ) -> Result<usize, MemscanError> {
+        let slot = {
+            let watchpoints = self.watchpoints.lock().unwrap();
+            if watchpoints
+                .iter()
+                .any(|w| w.address == address && w.len == len && w.kind == kind)
+            {
+                return Err(MemscanError::invalid_state(format!(
+                    "Already watching 0x{:x} ({} bytes, {})",
+                    address, len, kind
+                )));
+            }
+            (0..MAX_WATCHPOINTS)
+                .find(|slot| watchpoints.iter().all(|w| w.slot != *slot))
+                .ok_or_else(|| {
+                    MemscanError::unsupported(format!(
+                        "All {} debug registers are in use, remove a watchpoint first",
+                        MAX_WATCHPOINTS
+                    ))
+                })?
+        };
+
+        let id = self.next_watchpoint_id;
+        let watchpoint = Watchpoint {
+            id,
+            address,
+            len,
+            kind,
+            hits: 0,
+            sites: BTreeMap::new(),
+            slot,
+            last_value: self.handle.read_memory(address, len).unwrap_or_default(),
+        };
+        self.watchpoints.lock().unwrap().push(watchpoint);
+
+        if let Err(e) = self.apply_debug_registers() {
+            self.watchpoints.lock().unwrap().retain(|w| w.id != id);
+            let _ = self.apply_debug_registers();
+            return Err(e);
+        }
+        self.next_watchpoint_id += 1;
+        Ok(id)
+    }
+
+    fn remove_watchpoint(&mut self, id: usize) -> Result<Watchpoint, MemscanError> {
+        let removed = {
+            let mut watchpoints = self.watchpoints.lock().unwrap();
+            let index = watchpoints
+                .iter()
+                .position(|w| w.id == id)
+                .ok_or_else(|| MemscanError::not_found(format!("Watchpoint #{}", id)))?;
+            watchpoints.remove(index)
+        };
+        self.apply_debug_registers()?;
+        Ok(removed)
+    }
+
+    // every thread has to be stopped, except one waiting on a vfork, which
+    // gets them when it stops again
+    fn apply_debug_registers(&mut self) -> Result<(), MemscanError> {
+        let mut result = Ok(());
+        let tids: Vec<u32> = self.threads.keys().copied().collect();
+        for tid in tids {
+            if let Some(thread) = self.threads.get_mut(&tid)
+                && thread.in_vfork
+                && !thread.stopped
+            {
+                thread.fresh = true;
+                continue;
+            }
+            if let Err(e) = self.apply_registers(tid)
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    // the thread has to be stopped
+    fn apply_registers(&mut self, tid: u32) -> Result<(), MemscanError> {
+        let watchpoints = self.watchpoints.lock().unwrap().clone();
+        let mut dr7 = 0u64;
+        for watchpoint in &watchpoints {
+            write_debug_register(tid, watchpoint.slot, watchpoint.address as u64)?;
+            dr7 |= enable_bits(watchpoint);
+        }
+        write_debug_register(tid, 7, dr7)?;
+        if let Some(thread) = self.threads.get_mut(&tid) {
+            thread.fresh = false;
+        }
+        Ok(())
+    }
+
+    fn add_breakpoint(
+        &mut self,
+        address: usize,
+        auto_continue: bool,
+    ) -> Result<usize, MemscanError> {
+        if let Some(existing) = self
+            .breakpoints
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|b| b.address == address)
+        {
+            return Err(MemscanError::invalid_state(format!(
+                "Breakpoint #{} is already at 0x{:x}",
+                existing.id, address
+            )));
+        }
+
+        // an int3 in data would corrupt it
+        let executable = self.handle.get_memory_regions()?.iter().any(|r| {
+            r.executable && (r.start_address..r.start_address + r.size).contains(&address)
+        });
+        if !executable {
+            return Err(MemscanError::invalid_address(
+                &format!("0x{:x}", address),
+                "not in executable memory",
+            ));
+        }
+
+        let tid = self.stopped_thread()?;
+        let original = read_byte(tid, address)?;
+        write_byte(tid, address, INT3)?;
+
+        let id = self.next_breakpoint_id;
+        self.next_breakpoint_id += 1;
+        self.breakpoints.lock().unwrap().push(Breakpoint {
+            id,
+            address,
+            auto_continue,
+            hits: 0,
+            threads: BTreeMap::new(),
+            last_registers: None,
+            original,
+        });
+        Ok(id)
+    }
+
+    // a thread stopped on it is already moved back to the address, with the
+    // original byte in place it just runs the instruction
+    fn remove_breakpoint(&mut self, id: usize) -> Result<Breakpoint, MemscanError> {
+        let removed = {
+            let mut breakpoints = self.breakpoints.lock().unwrap();
+            let index = breakpoints
+                .iter()
+                .position(|b| b.id == id)
+                .ok_or_else(|| MemscanError::not_found(format!("Breakpoint #{}", id)))?;
+            breakpoints.remove(index)
+        };
+        let tid = self.stopped_thread()?;
+        write_byte(tid, removed.address, removed.original)?;
+        Ok(removed)
+    }
+
+    // peek and poke need a stopped thread, any of them will do
+    fn stopped_thread(&self) -> Result<u32, MemscanError> {
+        self.threads
+            .iter()
+            .find(|(_, t)| t.stopped)
+            .map(|(&tid, _)| tid)
+            .ok_or_else(|| {
+                MemscanError::invalid_state(format!("PID {} has no stopped thread", self.pid))
+            })
+    }
+
+    // a thread waiting on its vfork child won't stop until the child lets it
+    // go, and the child is one of the threads being stopped, so it's left be
+    fn stop_all(&mut self) {
+        let running: Vec<u32> = self
+            .threads
+            .iter()
+            .filter(|(_, t)| !t.stopped && !t.in_vfork)
+            .map(|(&tid, _)| tid)
+            .collect();
+
+        for &tid in &running {
+            let _ = ptrace::interrupt(pid(tid));
+        }
+
+        // anything that comes in before the interrupt stop is handled as
+        // usual, but the thread stays stopped
+        for tid in running {
+            while self.threads.get(&tid).is_some_and(|t| !t.stopped) {
+                match waitpid(pid(tid), Some(WaitPidFlag::__WALL)) {
+                    Ok(status) => self.handle_status(tid, status, true),
+                    Err(_) => {
+                        self.threads.remove(&tid);
+                    }
+                }
+            }
+        }
+    }
+
+    // every thread has to be stopped: the ones on a breakpoint step over it
+    // before anyone runs again
+    fn resume_all(&mut self) {
+        let stopped: Vec<u32> = self
+            .threads
+            .iter()
+            .filter(|(_, t)| t.stopped)
+            .map(|(&tid, _)| tid)
+            .collect();
+        for &tid in &stopped {
+            self.step_over(tid);
+        }
+        for tid in stopped {
+            self.resume(tid);
+        }
+    }
+
+    fn resume(&mut self, tid: u32) {
+        let Some(thread) = self.threads.get_mut(&tid) else {
+            return;
+        };
+        let result = if thread.group_stop {
+            listen(tid)
+        } else {
+            ptrace::cont(pid(tid), thread.signal.take())
+        };
+        thread.stopped = false;
+        if result.is_err() {
+            self.threads.remove(&tid);
+        }
+    }
+
+    // after an exec the int3s went away with the old image and the kernel
+    // cleared the debug registers. nothing is written back, the original
+    // bytes would land in the new program
+    fn forget_image(&mut self) {
+        let breakpoints = std::mem::take(&mut *self.breakpoints.lock().unwrap()).len();
+        let watchpoints = std::mem::take(&mut *self.watchpoints.lock().unwrap()).len();
+        // the other threads are gone too, their exits still come in
+        for thread in self.threads.values_mut() {
+            thread.at_breakpoint = None;
+        }
+        self.pause_requested = false;
+        publish(
+            &self.subscribers,
+            TraceEvent::Exec {
+                pid: self.pid,
+                breakpoints,
+                watchpoints,
+            },
+        );
+    }
+
+    // runs the instruction under the breakpoint the thread is stopped on
+    // with the original byte back in place, then puts the int3 back
+    fn step_over(&mut self, tid: u32) {
+        let Some(address) = self
+            .threads
+            .get_mut(&tid)
+            .and_then(|t| t.at_breakpoint.take())
+        else {
+            return;
+        };
+        // removed while the thread sat on it
+        let Some(original) = self
+            .breakpoints
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|b| b.address == address)
+            .map(|b| b.original)
+        else {
+            return;
+        };
+
+        if write_byte(tid, address, original).is_err() {
+            return;
+        }
+        loop {
+            if ptrace::step(pid(tid), None).is_err() {
+                self.threads.remove(&tid);
+                break;
+            }
+            match waitpid(pid(tid), Some(WaitPidFlag::__WALL)) {
+                Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => {
+                    // the instruction may have hit a watchpoint on its way
+                    self.watchpoint_trap(tid);
+                    break;
+                }
+                // a signal came in first, it's delivered when the thread
+                // is resumed
+                Ok(WaitStatus::Stopped(_, signal)) => {
+                    if let Some(thread) = self.threads.get_mut(&tid) {
+                        thread.signal = Some(signal);
+                    }
+                }
+                Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) | Err(_) => {
+                    self.threads.remove(&tid);
+                    break;
+                }
+                // a clone or an interrupt, keep stepping
+                Ok(status) => self.handle_status(tid, status, true),
+            }
+        }
+
+        if let Ok(stopped) = self.stopped_thread() {
+            let _ = write_byte(stopped, address, INT3);
+        }
+    }
+
+    // keep_stopped: we're stopping the process and the thread stays where it
+    // is, otherwise it's resumed right away. a thread on a breakpoint is left
+    // for the run loop either way
+    fn handle_status(&mut self, tid: u32, status: WaitStatus, keep_stopped: bool) {
+        match status {
+            WaitStatus::Exited(..) | WaitStatus::Signaled(..) => {
+                self.threads.remove(&tid);
+                return;
+            }
+            WaitStatus::PtraceEvent(_, _, event)
+                if event == ptrace::Event::PTRACE_EVENT_CLONE as i32 =>
+            {
+                if let Ok(new_tid) = ptrace::getevent(pid(tid)) {
+                    self.threads.insert(
+                        new_tid as u32,
+                        TracedThread {
+                            fresh: true,
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+            WaitStatus::PtraceEvent(_, _, event)
+                if event == ptrace::Event::PTRACE_EVENT_FORK as i32 =>
+            {
+                if let Ok(child) = ptrace::getevent(pid(tid)) {
+                    self.release_child(child as u32);
+                }
+            }
+            WaitStatus::PtraceEvent(_, _, event)
+                if event == ptrace::Event::PTRACE_EVENT_VFORK as i32 =>
+            {
+                if let Ok(child) = ptrace::getevent(pid(tid)) {
+                    self.threads.insert(
+                        child as u32,
+                        TracedThread {
+                            fresh: true,
+                            vfork_child: true,
+                            ..Default::default()
+                        },
+                    );
+                }
+                if let Some(thread) = self.threads.get_mut(&tid) {
+                    thread.in_vfork = true;
+                }
+            }
+            WaitStatus::PtraceEvent(_, _, event)
+                if event == ptrace::Event::PTRACE_EVENT_VFORK_DONE as i32 =>
+            {
+                if let Some(thread) = self.threads.get_mut(&tid) {
+                    thread.in_vfork = false;
+                }
+            }
+            // the vfork child has its own memory now, without our int3s
+            WaitStatus::PtraceEvent(_, _, event)
+                if event == ptrace::Event::PTRACE_EVENT_EXEC as i32
+                    && self.threads.get(&tid).is_some_and(|t| t.vfork_child) =>
+            {
+                let _ = write_debug_register(tid, 7, 0);
+                let _ = ptrace::detach(pid(tid), None);
+                self.threads.remove(&tid);
+                return;
+            }
+            WaitStatus::PtraceEvent(_, _, event)
+                if event == ptrace::Event::PTRACE_EVENT_EXEC as i32 =>
+            {
+                self.forget_image();
+            }
+            // interrupts and a new thread's first stop come with SIGTRAP, a
+            // group-stop with the signal that stopped the process
+            WaitStatus::PtraceEvent(_, signal, event)
+                if event == ptrace::Event::PTRACE_EVENT_STOP as i32 =>
+            {
+                if let Some(thread) = self.threads.get_mut(&tid) {
+                    thread.group_stop = signal != Signal::SIGTRAP;
+                }
+            }
+            WaitStatus::PtraceEvent(..) => {}
+            WaitStatus::Stopped(_, Signal::SIGTRAP) => {
+                if !self.breakpoint_trap(tid)
+                    && !self.watchpoint_trap(tid)
+                    && let Some(thread) = self.threads.get_mut(&tid)
+                {
+                    thread.signal = Some(Signal::SIGTRAP);
+                }
+            }
+            WaitStatus::Stopped(_, signal) => {
+                if let Some(thread) = self.threads.get_mut(&tid) {
+                    thread.signal = Some(signal);
+                }
+            }
+            _ => return,
+        }
+
+        let Some(thread) = self.threads.get_mut(&tid) else {
+            return;
+        };
+        thread.stopped = true;
+        let on_breakpoint = thread.at_breakpoint.is_some();
+        if thread.fresh {
+            let _ = self.apply_registers(tid);
+        }
+        if !keep_stopped && !on_breakpoint {
+            self.resume(tid);
+        }
+    }
+
+    // an int3 of ours leaves rip one past it. false when it wasn't ours
+    fn breakpoint_trap(&mut self, tid: u32) -> bool {
+        let Ok(mut registers) = getregs(tid) else {
+            return false;
+        };
+        let address = registers.get("rip").unwrap_or(0).wrapping_sub(1) as usize;
+
+        let hit = {
+            let mut breakpoints = self.breakpoints.lock().unwrap();
+            let Some(breakpoint) = breakpoints.iter_mut().find(|b| b.address == address) else {
+                return false;
+            };
+            registers.set("rip", address as u64);
+            if setregs(tid, &registers).is_err() {
+                return false;
+            }
+            breakpoint.hits += 1;
+            *breakpoint.threads.entry(tid).or_default() += 1;
+            breakpoint.last_registers = Some(registers.clone());
+            BreakpointHit {
+                breakpoint_id: breakpoint.id,
+                tid,
+                address,
+                hits: breakpoint.hits,
+                registers,
+                stopped: !breakpoint.auto_continue,
+                timestamp: Instant::now(),
+            }
+        };
+
+        if let Some(thread) = self.threads.get_mut(&tid) {
+            thread.at_breakpoint = Some(address);
+        }
+        if hit.stopped {
+            self.pause_requested = true;
+        }
+        publish(&self.subscribers, TraceEvent::BreakpointHit(hit));
+        true
+    }
+
+    // DR6 says which watchpoints fired. false when it wasn't ours
+    fn watchpoint_trap(&mut self, tid: u32) -> bool {
+        let Ok(dr6) = read_debug_register(tid, 6) else {
+            return false;
+        };
+        let fired: Vec<usize> = (0..MAX_WATCHPOINTS)
+            .filter(|slot| dr6 & (1 << slot) != 0)
+            .collect();
+        let _ = write_debug_register(tid, 6, 0);
+        if fired.is_empty() {
+            return false;
+        }
+
+        let rip = getregs(tid).ok().and_then(|r| r.get("rip")).unwrap_or(0) as usize;
+
+        let mut hits = Vec::new();
+        {
+            let mut watchpoints = self.watchpoints.lock().unwrap();
+            for watchpoint in watchpoints.iter_mut().filter(|w| fired.contains(&w.slot)) {
+                let new_value = self
+                    .handle
+                    .read_memory(watchpoint.address, watchpoint.len)
+                    .unwrap_or_default();
+                let old_value = std::mem::replace(&mut watchpoint.last_value, new_value.clone());
+                watchpoint.hits += 1;
+                *watchpoint.sites.entry(rip).or_default() += 1;
+                hits.push(WatchpointHit {
+                    watchpoint_id: watchpoint.id,
+                    tid,
+                    rip,
+                    address: watchpoint.address,
+                    kind: watchpoint.kind,
+                    old_value,
+                    new_value,
+                    timestamp: Instant::now(),
+                });
+            }
+        }
+        for hit in hits {
+            publish(&self.subscribers, TraceEvent::WatchpointHit(hit));
+        }
+        true
+    }
+
+    // a forked child starts out traced and stopped, with a copy of the memory
+    // that still has our int3s in it
+    fn release_child(&mut self, child: u32) {
+        if !matches!(
+            waitpid(pid(child), Some(WaitPidFlag::__WALL)),
+            Ok(WaitStatus::PtraceEvent(..)) | Ok(WaitStatus::Stopped(..))
+        ) {
+            return;
+        }
+        for breakpoint in self.breakpoints.lock().unwrap().iter() {
+            let _ = write_byte(child, breakpoint.address, breakpoint.original);
+        }
+        let _ = write_debug_register(child, 7, 0);
+        let _ = ptrace::detach(pid(child), None);
+    }
+
+    // the code gets its original bytes back, a thread on a breakpoint then
+    // simply runs the instruction. a thread in a group-stop is handed back
+    // stopped
+    fn detach_all(&mut self) {
+        self.stop_all();
+        if let Ok(tid) = self.stopped_thread() {
+            for breakpoint in self.breakpoints.lock().unwrap().iter() {
+                let _ = write_byte(tid, breakpoint.address, breakpoint.original);
+            }
+        }
+        let tids: Vec<u32> = self
+            .threads
+            .iter()
+            .filter(|(_, t)| t.stopped)
+            .map(|(&tid, _)| tid)
+            .collect();
+        for tid in tids {
+            let _ = write_debug_register(tid, 7, 0);
+            let thread = &self.threads[&tid];
+            let signal = if thread.group_stop {
+                Some(Signal::SIGSTOP)
+            } else {
+                thread.signal
+            };
+            let _ = ptrace::detach(pid(tid), signal);
+            self.threads.remove(&tid);
+        }
+
+        // a thread still waiting on its vfork child stops once the child,
+        // running free now, execs or exits
+        let deadline = Instant::now() + VFORK_WAIT;
+        while !self.threads.is_empty() && Instant::now() < deadline {
+            let tids: Vec<u32> = self.threads.keys().copied().collect();
+            for tid in tids {
+                match waitpid(pid(tid), Some(WaitPidFlag::__WALL | WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::StillAlive) => continue,
+                    Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) | Err(_) => {}
+                    Ok(_) => {
+                        let _ = write_debug_register(tid, 7, 0);
+                        let _ = ptrace::detach(pid(tid), None);
+                    }
+                }
+                self.threads.remove(&tid);
+            }
+            thread::sleep(IDLE_SLICE);
+        }
+        self.threads.clear();
+    }
+}
+
+fn pid(tid: u32) -> Pid {
+    Pid::from_raw(tid as i32)
+}
+
+// nix has no wrapper for it
+fn listen(tid: u32) -> nix::Result<()> {
+    let result = unsafe {
+        libc::ptrace(
+            libc::PTRACE_LISTEN,
+            tid as libc::pid_t,
+            std::ptr::null_mut::<libc::c_void>(),
+            std::ptr::null_mut::<libc::c_void>(),
+        )
+    };
+    Errno::result(result).map(drop)
+}
+
+// peek and poke go through ptrace, which writes read-only code pages too
+fn read_byte(tid: u32, address: usize) -> Result<u8, MemscanError> {
+    ptrace::read(pid(tid), address as ptrace::AddressType)
+        .map(|word| word as u8)
+        .map_err(|e| MemscanError::from_errno(e, tid, Some(address)))
+}
+
+fn write_byte(tid: u32, address: usize, byte: u8) -> Result<(), MemscanError> {
+    let word = ptrace::read(pid(tid), address as ptrace::AddressType)
+        .map_err(|e| MemscanError::from_errno(e, tid, Some(address)))?;
+    let patched = (word & !0xff) | byte as libc::c_long;
+    unsafe {
+        ptrace::write(
+            pid(tid),
+            address as ptrace::AddressType,
+            patched as *mut libc::c_void,
+        )
+    }
+    .map_err(|e| MemscanError::from_errno(e, tid, Some(address)))
+}
+
+// DR7: local enable at bit 2n, the access type at 16 + 4n and the length at
+// 18 + 4n for slot n
+fn enable_bits(watchpoint: &Watchpoint) -> u64 {
+    let access: u64 = match watchpoint.kind {
+        WatchKind::Write => 0b01,
+        WatchKind::Access => 0b11,
+    };
+    let len: u64 = match watchpoint.len {
+        1 => 0b00,
+        2 => 0b01,
+        8 => 0b10,
+        _ => 0b11,
+    };
+    let slot = watchpoint.slot as u64;
+    (1 << (2 * slot)) | (access << (16 + 4 * slot)) | (len << (18 + 4 * slot))
+}
+
+fn debug_register_offset(index: usize) -> *mut libc::c_void {
+    (DEBUG_REGISTERS + index * 8) as *mut libc::c_void
+}
+
+fn read_debug_register(tid: u32, index: usize) -> Result<u64, MemscanError> {
+    ptrace::read_user(pid(tid), debug_register_offset(index))
+        .map(|value| value as u64)
+        .map_err(|e| ptrace_error("read debug registers of", tid, e))
+}
+
+fn write_debug_register(tid: u32, index: usize, value: u64) -> Result<(), MemscanError> {
+    unsafe {
+        ptrace::write_user(
+            pid(tid),
+            debug_register_offset(index),
+            value as *mut libc::c_void,
+        )
+    }
+    .map_err(|e| ptrace_error("write debug registers of", tid, e))
+}
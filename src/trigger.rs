@@ -0,0 +1,410 @@
+// conditional triggers on monitor targets
+// built by the goat (danielscos)
+//
+// a trigger is checked every time its target's value changes. when the
+// condition holds the monitor thread runs the action and sends a Triggered
+// event, so nobody has to be watching for the pause or snapshot to happen
+
+use crate::error::MemscanError;
+use crate::monitor::{DataType, MonitorChange, data_type_name};
+use crate::process::ProcessHandle;
+use crate::range::MAX_RANGE;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerCondition {
+    // crossed upwards: old < x <= new
+    Above(f64),
+    // crossed downwards: old > x >= new
+    Below(f64),
+    // became this value. numbers compare in the target's own type, so an
+    // f32 target matches "0.1" as 0.1f32. strings compare by content
+    Equals(String),
+    // moved by more than n in one step
+    ChangedBy(f64),
+    // more than k changes within one second
+    Rate(u32),
+    // a float became NaN
+    NaN,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerAction {
+    Notify,
+    // SIGSTOP the process, 'continue' lets it go again
+    Pause,
+    // save a raw dump of the range to <prefix>-<hit>.bin
+    Snapshot {
+        address: usize,
+        size: usize,
+        prefix: String,
+    },
+    // remember the hit in the monitor's marker list
+    Marker,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub id: usize,
+    pub condition: TriggerCondition,
+    pub action: TriggerAction,
+    pub hits: u64,
+    // change times inside the rate window
+    recent: VecDeque<Instant>,
+}
+
+// one time a trigger fired
+#[derive(Debug, Clone)]
+pub struct TriggerHit {
+    pub trigger_id: usize,
+    pub target_id: usize,
+    pub address: usize,
+    pub name: String,
+    pub condition: TriggerCondition,
+    pub action: TriggerAction,
+    pub old_value: Vec<u8>,
+    pub new_value: Vec<u8>,
+    pub data_type: DataType,
+    pub timestamp: Instant,
+    // what the action did (the snapshot file, ...) or why it failed
+    pub outcome: Result<String, String>,
+}
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+// the value as a number, None for strings
+pub fn numeric_value(data_type: &DataType, bytes: &[u8]) -> Option<f64> {
+    match data_type {
+        DataType::I32 => Some(i32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as f64),
+        DataType::I64 => Some(i64::from_le_bytes(bytes.get(..8)?.try_into().ok()?) as f64),
+        DataType::F32 => Some(f32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as f64),
+        DataType::F64 => Some(f64::from_le_bytes(bytes.get(..8)?.try_into().ok()?)),
+        DataType::String(_) | DataType::Bytes(_) => None,
+    }
+}
+
+fn string_value(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+fn parse_number(text: &str) -> Result<f64, MemscanError> {
+    text.parse::<f64>()
+        .map_err(|_| MemscanError::invalid_value(text, "a number"))
+}
+
+// snapshot sizes are capped like range targets, every hit writes one out
+fn parse_size(text: &str) -> Result<usize, MemscanError> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => text.parse::<usize>().ok(),
+    };
+    parsed
+        .filter(|&size| (1..=MAX_RANGE).contains(&size))
+        .ok_or_else(|| {
+            MemscanError::invalid_value(text, format!("a size between 1 and {} bytes", MAX_RANGE))
+        })
+}
+
+// the operand read as the target's type, Some(false) if it isn't one
+fn equals(data_type: &DataType, bytes: &[u8], operand: &str) -> Option<bool> {
+    Some(match data_type {
+        DataType::I32 => {
+            i32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) == operand.parse::<i32>().ok()?
+        }
+        DataType::I64 => {
+            i64::from_le_bytes(bytes.get(..8)?.try_into().ok()?) == operand.parse::<i64>().ok()?
+        }
+        DataType::F32 => {
+            f32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) == operand.parse::<f32>().ok()?
+        }
+        DataType::F64 => {
+            f64::from_le_bytes(bytes.get(..8)?.try_into().ok()?) == operand.parse::<f64>().ok()?
+        }
+        DataType::String(_) | DataType::Bytes(_) => string_value(bytes) == operand,
+    })
+}
+
+fn parse_operand(data_type: &DataType, text: &str) -> Result<(), MemscanError> {
+    let valid = match data_type {
+        DataType::I32 => text.parse::<i32>().is_ok(),
+        DataType::I64 => text.parse::<i64>().is_ok(),
+        DataType::F32 => text.parse::<f32>().is_ok(),
+        DataType::F64 => text.parse::<f64>().is_ok(),
+        DataType::String(_) | DataType::Bytes(_) => true,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(MemscanError::invalid_value(
+            text,
+            format!("an {} value", data_type_name(data_type)),
+        ))
+    }
+}
+
+// "above 100", "below 0.5", "equals 42", "changed-by 10", "rate 5", "nan"
+pub fn parse_condition(words: &[&str]) -> Result<TriggerCondition, MemscanError> {
+    let argument = || {
+        words
+            .get(1)
+            .copied()
+            .ok_or_else(|| MemscanError::invalid_value(words[0], "a value after it"))
+    };
+    match words.first().copied().unwrap_or("") {
+        "above" | ">" => Ok(TriggerCondition::Above(parse_number(argument()?)?)),
+        "below" | "<" => Ok(TriggerCondition::Below(parse_number(argument()?)?)),
+        "equals" | "=" | "==" => Ok(TriggerCondition::Equals(argument()?.to_string())),
+        "changed-by" | "delta" => Ok(TriggerCondition::ChangedBy(parse_number(argument()?)?)),
+        "rate" => argument()?
+            .parse::<u32>()
+            .map(TriggerCondition::Rate)
+            .map_err(|_| MemscanError::invalid_value(words[1], "changes per second")),
+        "nan" => Ok(TriggerCondition::NaN),
+        other => Err(MemscanError::invalid_value(
+            other,
+            "above, below, equals, changed-by, rate or nan",
+        )),
+    }
+}
+
+// "notify", "pause", "mark", "snapshot <ADDRESS> <SIZE> [PREFIX]". the
+// address is resolved by the caller, so it comes in as a number
+pub fn parse_action(
+    words: &[&str],
+    snapshot_address: Option<usize>,
+    default_prefix: &str,
+) -> Result<TriggerAction, MemscanError> {
+    match words.first().copied().unwrap_or("notify") {
+        "notify" => Ok(TriggerAction::Notify),
+        "pause" | "stop" => Ok(TriggerAction::Pause),
+        "mark" | "marker" => Ok(TriggerAction::Marker),
+        "snapshot" => {
+            let address = snapshot_address
+                .ok_or_else(|| MemscanError::invalid_value("snapshot", "an address and size"))?;
+            let size =
+                parse_size(words.get(2).ok_or_else(|| {
+                    MemscanError::invalid_value("snapshot", "an address and size")
+                })?)?;
+            Ok(TriggerAction::Snapshot {
+                address,
+                size,
+                prefix: words
+                    .get(3)
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| default_prefix.to_string()),
+            })
+        }
+        other => Err(MemscanError::invalid_value(
+            other,
+            "notify, pause, snapshot or mark",
+        )),
+    }
+}
+
+impl TriggerCondition {
+    // can it ever hold for a target of this type
+    pub fn check_type(&self, data_type: &DataType) -> Result<(), MemscanError> {
+        let is_string = matches!(data_type, DataType::String(_));
+        match self {
+            // raw bytes only have a rate, a range read as numbers has more
+            Self::Rate(_) => Ok(()),
+            _ if matches!(data_type, DataType::Bytes(_)) => {
+                Err(MemscanError::unsupported(format!(
+                    "'{}' needs a typed target, read the range as a type first",
+                    self
+                )))
+            }
+            Self::Above(_) | Self::Below(_) | Self::ChangedBy(_) if is_string => Err(
+                MemscanError::unsupported(format!("'{}' needs a numeric target", self)),
+            ),
+            Self::NaN if !matches!(data_type, DataType::F32 | DataType::F64) => Err(
+                MemscanError::unsupported("'nan' needs an f32 or f64 target"),
+            ),
+            Self::Equals(value) => parse_operand(data_type, value),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Trigger {
+    pub fn new(id: usize, condition: TriggerCondition, action: TriggerAction) -> Self {
+        Self {
+            id,
+            condition,
+            action,
+            hits: 0,
+            recent: VecDeque::new(),
+        }
+    }
+
+    // does this change fire the trigger
+    pub fn check(&mut self, change: &MonitorChange) -> bool {
+        let old = numeric_value(&change.data_type, &change.old_value);
+        let new = numeric_value(&change.data_type, &change.new_value);
+
+        match &self.condition {
+            TriggerCondition::Above(x) => {
+                matches!((old, new), (Some(old), Some(new)) if old < *x && new >= *x)
+            }
+            TriggerCondition::Below(x) => {
+                matches!((old, new), (Some(old), Some(new)) if old > *x && new <= *x)
+            }
+            TriggerCondition::Equals(value) => {
+                equals(&change.data_type, &change.new_value, value).unwrap_or(false)
+            }
+            TriggerCondition::ChangedBy(n) => {
+                matches!((old, new), (Some(old), Some(new)) if (new - old).abs() > *n)
+            }
+            TriggerCondition::Rate(k) => {
+                self.recent.push_back(change.timestamp);
+                while self
+                    .recent
+                    .front()
+                    .is_some_and(|&t| change.timestamp.duration_since(t) > RATE_WINDOW)
+                {
+                    self.recent.pop_front();
+                }
+                // fire once per burst, not on every change after the k-th
+                if self.recent.len() > *k as usize {
+                    self.recent.clear();
+                    true
+                } else {
+                    false
+                }
+            }
+            TriggerCondition::NaN => new.is_some_and(f64::is_nan) && !old.is_some_and(f64::is_nan),
+        }
+    }
+
+    // run the action for the hit-th time, returns what it did
+    pub fn run_action(
+        &self,
+        process_handle: &ProcessHandle,
+        hit: u64,
+    ) -> Result<String, MemscanError> {
+        match &self.action {
+            TriggerAction::Notify => Ok("notified".to_string()),
+            TriggerAction::Marker => Ok("marker recorded".to_string()),
+            TriggerAction::Pause => {
+                process_handle.pause()?;
+                Ok(format!("paused PID {}", process_handle.pid()))
+            }
+            TriggerAction::Snapshot {
+                address,
+                size,
+                prefix,
+            } => {
+                let data = process_handle.read_memory(*address, *size)?;
+                let path = PathBuf::from(format!("{}-{}.bin", prefix, hit));
+                std::fs::write(&path, &data)
+                    .map_err(|e| MemscanError::io(path.display().to_string(), e))?;
+                Ok(format!("saved {}@0x{:x}", path.display(), address))
+            }
+        }
+    }
+}
+
+impl fmt::Display for TriggerCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Above(x) => write!(f, "above {}", x),
+            Self::Below(x) => write!(f, "below {}", x),
+            Self::Equals(value) => write!(f, "equals {}", value),
+            Self::ChangedBy(n) => write!(f, "changed-by {}", n),
+            Self::Rate(k) => write!(f, "rate {}/s", k),
+            Self::NaN => write!(f, "nan"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Notify => write!(f, "notify"),
+            Self::Pause => write!(f, "pause"),
+            Self::Snapshot {
+                address,
+                size,
+                prefix,
+            } => write!(f, "snapshot 0x{:x} {} -> {}-N.bin", address, size, prefix),
+            Self::Marker => write!(f, "mark"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn change(data_type: DataType, old_value: Vec<u8>, new_value: Vec<u8>) -> MonitorChange {
+        MonitorChange {
+            target_id: 1,
+            address: 0x1000,
+            name: "test".to_string(),
+            old_value,
+            new_value,
+            timestamp: Instant::now(),
+            time: SystemTime::now(),
+            data_type,
+        }
+    }
+
+    fn trigger(words: &[&str]) -> Trigger {
+        Trigger::new(1, parse_condition(words).unwrap(), TriggerAction::Notify)
+    }
+
+    #[test]
+    fn equals_compares_in_the_target_type() {
+        let f32_change = change(
+            DataType::F32,
+            0.2f32.to_le_bytes().to_vec(),
+            0.1f32.to_le_bytes().to_vec(),
+        );
+        assert!(trigger(&["equals", "0.1"]).check(&f32_change));
+
+        let f64_change = change(
+            DataType::F64,
+            0.2f64.to_le_bytes().to_vec(),
+            0.1f64.to_le_bytes().to_vec(),
+        );
+        assert!(trigger(&["equals", "0.1"]).check(&f64_change));
+
+        let big = (1i64 << 53) + 1;
+        let i64_change = change(
+            DataType::I64,
+            0i64.to_le_bytes().to_vec(),
+            big.to_le_bytes().to_vec(),
+        );
+        assert!(!trigger(&["equals", &(big - 1).to_string()]).check(&i64_change));
+        assert!(trigger(&["equals", &big.to_string()]).check(&i64_change));
+    }
+
+    #[test]
+    fn equals_operand_must_fit_the_type() {
+        let condition = parse_condition(&["equals", "0.5"]).unwrap();
+        assert!(condition.check_type(&DataType::F32).is_ok());
+        assert!(condition.check_type(&DataType::I32).is_err());
+        assert!(condition.check_type(&DataType::String(16)).is_ok());
+
+        let condition = parse_condition(&["equals", "99999999999"]).unwrap();
+        assert!(condition.check_type(&DataType::I64).is_ok());
+        assert!(condition.check_type(&DataType::I32).is_err());
+    }
+
+    #[test]
+    fn snapshot_size_is_capped() {
+        fn action(size: &str) -> Result<TriggerAction, MemscanError> {
+            parse_action(&["snapshot", "0x1000", size], Some(0x1000), "hit")
+        }
+        assert!(action("64").is_ok());
+        assert!(action(&MAX_RANGE.to_string()).is_ok());
+        assert!(action(&(MAX_RANGE + 1).to_string()).is_err());
+        assert!(action("0xffffffffffffffff").is_err());
+        assert!(action("0").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 // utility funcs
 // built by the goat (danielcos)
 
+use crate::access::{CAP_SYS_PTRACE, has_capability};
+use crate::error::MemscanError;
 use std::io::{self, Write};
 use std::thread;
 use std::time::Duration;
@@ -9,19 +11,44 @@ use std::time::Duration;
 pub struct SystemInfo {
     pub os: String,
     pub has_sudo: bool,
+    pub cap_sys_ptrace: bool,
     pub can_read_proc: bool,
     pub ptrace_scope: Option<u32>,
 }
 
-pub fn system_checks() -> Result<SystemInfo, String> {
+// which processes we can expect to attach to, from what we know at startup.
+// access::preflight has the final word for a specific PID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachScope {
+    AnyProcess,
+    SameUser,
+    Descendants,
+    Nothing,
+}
+
+impl SystemInfo {
+    pub fn attach_scope(&self) -> AttachScope {
+        match self.ptrace_scope {
+            Some(3) => AttachScope::Nothing,
+            _ if self.cap_sys_ptrace => AttachScope::AnyProcess,
+            Some(2) => AttachScope::Nothing,
+            Some(1) => AttachScope::Descendants,
+            _ => AttachScope::SameUser,
+        }
+    }
+}
+
+pub fn system_checks() -> Result<SystemInfo, MemscanError> {
     let mut info = SystemInfo {
         os: detect_os(),
         has_sudo: false,
+        cap_sys_ptrace: false,
         can_read_proc: false,
         ptrace_scope: None,
     };
 
     info.has_sudo = sudo_check();
+    info.cap_sys_ptrace = has_capability(CAP_SYS_PTRACE);
 
     info.can_read_proc = check_proc_access();
 
@@ -70,7 +97,7 @@ pub fn get_ptrace_scope() -> Option<u32> {
         .ok()
 }
 
-pub fn loading_with_checks() -> Result<SystemInfo, String> {
+pub fn loading_with_checks() -> Result<SystemInfo, MemscanError> {
     println!();
     println!();
     println!("                            Initializing Memscan...");
@@ -79,7 +106,7 @@ pub fn loading_with_checks() -> Result<SystemInfo, String> {
     let loading_chars = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
     let mut char_index = 0;
 
-    type CheckFn = fn(&mut SystemInfo) -> Result<(), String>;
+    type CheckFn = fn(&mut SystemInfo) -> Result<(), MemscanError>;
 
     let checks: &[(&str, CheckFn)] = &[
         ("Detecting operating system", detect_os_check),
@@ -92,6 +119,7 @@ pub fn loading_with_checks() -> Result<SystemInfo, String> {
     let mut system_info = SystemInfo {
         os: "Unknown".to_string(),
         has_sudo: false,
+        cap_sys_ptrace: false,
         can_read_proc: false,
         ptrace_scope: None,
     };
@@ -123,44 +151,40 @@ pub fn loading_with_checks() -> Result<SystemInfo, String> {
     Ok(system_info)
 }
 
-fn detect_os_check(info: &mut SystemInfo) -> Result<(), String> {
+fn detect_os_check(info: &mut SystemInfo) -> Result<(), MemscanError> {
     info.os = detect_os();
     thread::sleep(Duration::from_millis(200));
     Ok(())
 }
 
-fn check_privileges(info: &mut SystemInfo) -> Result<(), String> {
+// root isn't required: CAP_SYS_PTRACE, same-user targets and our own
+// children ('spawn') all work without it, and files always do. what a given
+// PID allows is checked when attaching to it
+fn check_privileges(info: &mut SystemInfo) -> Result<(), MemscanError> {
     info.has_sudo = sudo_check();
-    if !info.has_sudo {
-        return Err("    Insufficient privileges".to_string());
-    }
+    info.cap_sys_ptrace = has_capability(CAP_SYS_PTRACE);
     thread::sleep(Duration::from_millis(300));
     Ok(())
 }
 
-fn verify_proc_access(info: &mut SystemInfo) -> Result<(), String> {
+fn verify_proc_access(info: &mut SystemInfo) -> Result<(), MemscanError> {
     info.can_read_proc = check_proc_access();
     if !info.can_read_proc {
-        return Err("    Cannot access /proc filesystem".to_string());
+        return Err(MemscanError::unsupported("Cannot access /proc filesystem"));
     }
     thread::sleep(Duration::from_millis(250));
     Ok(())
 }
 
-fn check_security_settings(info: &mut SystemInfo) -> Result<(), String> {
+fn check_security_settings(info: &mut SystemInfo) -> Result<(), MemscanError> {
     if info.os == "Linux" {
         info.ptrace_scope = get_ptrace_scope();
-        if let Some(scope) = info.ptrace_scope {
-            if scope > 1 {
-                return Err(format!("    ptrace_scope too restrictive: {}", scope));
-            }
-        }
     }
     thread::sleep(Duration::from_millis(200));
     Ok(())
 }
 
-fn final_prep(_info: &mut SystemInfo) -> Result<(), String> {
+fn final_prep(_info: &mut SystemInfo) -> Result<(), MemscanError> {
     thread::sleep(Duration::from_millis(300));
     Ok(())
 }
@@ -194,6 +218,13 @@ pub fn display_system_info(info: &SystemInfo) {
     };
     println!("| Sudo Privileges:  {:18} |", sudo_status);
 
+    let cap_status = if info.cap_sys_ptrace {
+        "OK  Effective"
+    } else {
+        "--  Not held"
+    };
+    println!("| CAP_SYS_PTRACE:   {:18} |", cap_status);
+
     let proc_status = if info.can_read_proc {
         "OK  Available"
     } else {
@@ -210,19 +241,38 @@ pub fn display_system_info(info: &SystemInfo) {
         println!("| Ptrace Scope:     {:18} |", scope_status);
     }
 
+    let attach_status = match info.attach_scope() {
+        AttachScope::AnyProcess => "OK  Any process",
+        AttachScope::SameUser => "OK  Same user",
+        AttachScope::Descendants => "!!  Own children",
+        AttachScope::Nothing => "!!  None",
+    };
+    println!("| Can Attach To:    {:18} |", attach_status);
+
     println!("+---------------------------------------+");
 }
 
 pub fn suggest_fixes(info: &SystemInfo) {
-    if !info.has_sudo {
-        println!("\n    PRIVILEGE ISSUE DETECTED:");
-        println!("      Run with sudo or ./run_memscan.sh");
-    }
-
-    if let Some(scope) = info.ptrace_scope {
-        if scope > 1 {
+    match info.attach_scope() {
+        AttachScope::AnyProcess => {}
+        AttachScope::SameUser => {
+            println!("\n    RUNNING WITHOUT CAP_SYS_PTRACE:");
+            println!("      Processes of other users (and setuid ones) are off limits");
+            println!("      Run with sudo or ./run_memscan.sh to reach them");
+        }
+        AttachScope::Descendants => {
+            println!("\n    PTRACE RESTRICTION DETECTED (ptrace_scope=1):");
+            println!("      Only processes started with 'spawn' can be attached to");
+            println!("      Run with sudo or ./run_memscan.sh for anything else");
+        }
+        AttachScope::Nothing => {
             println!("\n    PTRACE RESTRICTION DETECTED:");
-            println!("      Run with sudo or ./run_memscan.sh")
+            if info.ptrace_scope == Some(3) {
+                println!("      ptrace_scope=3 blocks attaching until reboot");
+            } else {
+                println!("      Run with sudo or ./run_memscan.sh");
+            }
+            println!("      Core files and snapshots can still be analyzed");
         }
     }
 
@@ -3,21 +3,34 @@
 #include <stdlib.h>
 #include <string.h>
 
+// Values to search for in memory - all data types
+// globals so they show up in the symbol table and debug info (build with -g)
+int secret_number = 12345;
+int health_points = 100;
+int score = 999;
+long long big_number = 9876543210LL;
+long long coins = 1000000LL;
+float player_x = 42.5f;
+float speed = 15.75f;
+double balance = 1337.1337;
+double experience = 9999.9999;
+char username[32] = "testplayer";
+char weapon[16] = "sword";
+char location[20] = "dungeon";
+
+// a struct for the field-by-field viewers
+struct player_stats {
+    int level;
+    int health;
+    float x;
+    float y;
+    double gold;
+    char class_name[12];
+};
+
+struct player_stats player = { 7, 100, 42.5f, -3.25f, 250.5, "warrior" };
+
 int main() {
-    // Values to search for in memory - all data types
-    int secret_number = 12345;
-    int health_points = 100;
-    int score = 999;
-    long long big_number = 9876543210LL;
-    long long coins = 1000000LL;
-    float player_x = 42.5f;
-    float speed = 15.75f;
-    double balance = 1337.1337;
-    double experience = 9999.9999;
-    char username[32] = "testplayer";
-    char weapon[16] = "sword";
-    char location[20] = "dungeon";
-    
     // Print process info
     printf("=== Memscan Enhanced Test Target ===\n");
     printf("PID: %d\n", getpid());
@@ -45,6 +58,10 @@ int main() {
     printf("  weapon = '%s'\n", weapon);
     printf("  location = '%s'\n", location);
     
+    printf("\nStruct (player):\n");
+    printf("  level = %d, health = %d, x = %.2f, y = %.2f, gold = %.1f, class_name = '%s'\n",
+           player.level, player.health, player.x, player.y, player.gold, player.class_name);
+    
     printf("\nMemory addresses (for reference):\n");
     printf("  secret_number at: %p\n", (void*)&secret_number);
     printf("  health_points at: %p\n", (void*)&health_points);
@@ -58,6 +75,7 @@ int main() {
     printf("  username at: %p\n", (void*)&username);
     printf("  weapon at: %p\n", (void*)&weapon);
     printf("  location at: %p\n", (void*)&location);
+    printf("  player at: %p\n", (void*)&player);
     printf("\n");
     
     // Keep the program running so we can scan it
@@ -86,6 +104,9 @@ int main() {
     strcpy(username, "newplayer");
     strcpy(weapon, "axe");
     strcpy(location, "castle");
+    player.level = 8;
+    player.health = 75;
+    player.gold = 1000.25;
     
     printf("\n=== Values Changed! ===\n");
     printf("i32 values:\n");
@@ -110,6 +131,11 @@ int main() {
     printf("  weapon = '%s' (was 'sword')\n", weapon);
     printf("  location = '%s' (was 'dungeon')\n", location);
     
+    printf("\nStruct values:\n");
+    printf("  player.level = %d (was 7)\n", player.level);
+    printf("  player.health = %d (was 100)\n", player.health);
+    printf("  player.gold = %.2f (was 250.5)\n", player.gold);
+    
     printf("\nPress Enter to exit...\n");
     getchar();
     